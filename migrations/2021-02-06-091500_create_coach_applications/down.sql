@@ -0,0 +1 @@
+drop table if exists coach_applications;
\ No newline at end of file
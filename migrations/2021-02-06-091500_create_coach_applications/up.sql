@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS coach_applications (
+    id varchar(100) NOT NULL,
+    user_id varchar(100) NOT NULL,
+    full_name varchar(255) NOT NULL,
+    email varchar(255) NOT NULL,
+    status varchar(10) NOT NULL DEFAULT 'pending',
+    reviewed_by_id varchar(100),
+    reviewed_at datetime,
+ 	created_at datetime NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  	updated_at datetime NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+  	PRIMARY KEY (id),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
@@ -0,0 +1,4 @@
+alter table correspondences modify enrollment_id varchar(100) NOT NULL;
+alter table correspondences modify program_id varchar(100) NOT NULL;
+
+drop table if exists password_resets;
@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS password_resets (
+    id varchar(100) NOT NULL,
+    user_id varchar(100) NOT NULL,
+    token_digest varchar(64) NOT NULL UNIQUE,
+    expires_at datetime NOT NULL,
+    used_at datetime,
+ 	created_at datetime NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  	PRIMARY KEY (id),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- The system mails (like password reset) are not tied to any program or enrollment
+alter table correspondences modify program_id varchar(100);
+alter table correspondences modify enrollment_id varchar(100);
@@ -0,0 +1 @@
+alter table users drop column account_status;
\ No newline at end of file
@@ -0,0 +1,2 @@
+alter table users add column account_status varchar(20) NOT NULL DEFAULT 'active';
+update users set account_status = 'blocked' where blocked = 1;
@@ -0,0 +1,2 @@
+alter table correspondences modify error_reason varchar(100);
+alter table correspondences drop column attempts;
@@ -0,0 +1,2 @@
+alter table correspondences add column attempts int NOT NULL DEFAULT 0;
+alter table correspondences modify error_reason varchar(255);
@@ -0,0 +1,2 @@
+alter table sessions drop column sequence;
+alter table correspondences drop column calendar;
@@ -0,0 +1,2 @@
+alter table correspondences add column calendar text;
+alter table sessions add column sequence int NOT NULL DEFAULT 0;
@@ -0,0 +1,10 @@
+drop table if exists calendar_feeds;
+CREATE TABLE IF NOT EXISTS calendar_feeds (
+    id varchar(100) NOT NULL,
+    user_id varchar(100) NOT NULL,
+    token_digest varchar(64) NOT NULL UNIQUE,
+    revoked_at datetime,
+ 	created_at datetime NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  	PRIMARY KEY (id),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
@@ -0,0 +1,10 @@
+drop table if exists session_slots;
+CREATE TABLE IF NOT EXISTS session_slots (
+    id varchar(100) NOT NULL,
+    session_id varchar(100) NOT NULL,
+    start_date datetime NOT NULL,
+    end_date datetime NOT NULL,
+ 	created_at datetime NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  	PRIMARY KEY (id),
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
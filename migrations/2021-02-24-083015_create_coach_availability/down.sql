@@ -0,0 +1,2 @@
+drop table if exists blackouts;
+drop table if exists working_hours;
@@ -0,0 +1,25 @@
+-- The weekly working hours of a coach, as minutes from the midnight (UTC).
+-- A coach without any working hours is taken as available all the time.
+drop table if exists working_hours;
+CREATE TABLE IF NOT EXISTS working_hours (
+    id varchar(100) NOT NULL,
+    coach_id varchar(100) NOT NULL,
+    day_of_week int NOT NULL,
+    start_minute int NOT NULL,
+    end_minute int NOT NULL,
+ 	created_at datetime NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  	PRIMARY KEY (id),
+    FOREIGN KEY (coach_id) REFERENCES coaches(id) ON DELETE CASCADE
+);
+
+drop table if exists blackouts;
+CREATE TABLE IF NOT EXISTS blackouts (
+    id varchar(100) NOT NULL,
+    coach_id varchar(100) NOT NULL,
+    start_date datetime NOT NULL,
+    end_date datetime NOT NULL,
+    reason varchar(255),
+ 	created_at datetime NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  	PRIMARY KEY (id),
+    FOREIGN KEY (coach_id) REFERENCES coaches(id) ON DELETE CASCADE
+);
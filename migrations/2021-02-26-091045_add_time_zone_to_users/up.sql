@@ -0,0 +1 @@
+alter table users add column time_zone varchar(64) NOT NULL DEFAULT 'UTC';
@@ -0,0 +1,3 @@
+alter table sessions drop FOREIGN KEY fk_sessions_series;
+alter table sessions drop column series_id;
+drop table if exists session_series;
@@ -0,0 +1,23 @@
+-- A series of sessions, as per a recurrence rule (see commons::recurrence).
+-- The start_date and the exceptions are in UTC; the rule is expanded in the time_zone.
+drop table if exists session_series;
+CREATE TABLE IF NOT EXISTS session_series (
+    id varchar(100) NOT NULL,
+    program_id varchar(100) NOT NULL,
+    enrollment_id varchar(100) NOT NULL,
+    name varchar(100) NOT NULL,
+    description text,
+    duration int NOT NULL,
+    start_date datetime NOT NULL,
+    rule varchar(255) NOT NULL,
+    exceptions text,
+    time_zone varchar(64) NOT NULL DEFAULT 'UTC',
+ 	created_at datetime NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  	updated_at datetime NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+  	PRIMARY KEY (id),
+    FOREIGN KEY (program_id) REFERENCES programs(id) ON DELETE CASCADE,
+    FOREIGN KEY (enrollment_id) REFERENCES enrollments(id) ON DELETE CASCADE
+);
+
+alter table sessions add column series_id varchar(100);
+alter table sessions add CONSTRAINT fk_sessions_series FOREIGN KEY (series_id) REFERENCES session_series(id) ON DELETE SET NULL;
@@ -0,0 +1,17 @@
+-- The successors moved (revised dates) when a task slips, along with the task that caused the move.
+drop table if exists task_moves;
+CREATE TABLE IF NOT EXISTS task_moves (
+    id varchar(100) NOT NULL,
+    enrollment_id varchar(100) NOT NULL,
+    task_id varchar(100) NOT NULL,
+    cause_task_id varchar(100) NOT NULL,
+    from_start_date datetime NOT NULL,
+    from_end_date datetime NOT NULL,
+    to_start_date datetime NOT NULL,
+    to_end_date datetime NOT NULL,
+ 	created_at datetime NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  	PRIMARY KEY (id),
+    FOREIGN KEY (enrollment_id) REFERENCES enrollments(id) ON DELETE CASCADE,
+    FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE CASCADE,
+    FOREIGN KEY (cause_task_id) REFERENCES tasks(id) ON DELETE CASCADE
+);
@@ -0,0 +1,2 @@
+drop table if exists master_plan_revisions;
+alter table master_plans drop column version;
@@ -0,0 +1,16 @@
+-- The version guards a plan against concurrent saves; every save keeps a revision (snapshot) of the plan.
+alter table master_plans add column version int NOT NULL DEFAULT 0;
+
+drop table if exists master_plan_revisions;
+CREATE TABLE IF NOT EXISTS master_plan_revisions (
+    id varchar(100) NOT NULL,
+    master_plan_id varchar(100) NOT NULL,
+    version int NOT NULL,
+    snapshot mediumtext NOT NULL,
+    saved_by_id varchar(100) NOT NULL,
+ 	created_at datetime NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  	PRIMARY KEY (id),
+    UNIQUE KEY (master_plan_id, version),
+    FOREIGN KEY (master_plan_id) REFERENCES master_plans(id) ON DELETE CASCADE,
+    FOREIGN KEY (saved_by_id) REFERENCES users(id) ON DELETE CASCADE
+);
@@ -0,0 +1 @@
+alter table program_plans drop column is_default;
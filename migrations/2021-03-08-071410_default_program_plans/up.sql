@@ -0,0 +1,2 @@
+-- The default plan of a program is applied to every new enrollment of the program.
+alter table program_plans add column is_default tinyint(1) NOT NULL DEFAULT '0';
@@ -0,0 +1 @@
+drop table if exists master_plan_shares;
@@ -0,0 +1,14 @@
+-- A coach shares a master plan with all the (peer) coaches of a parent program.
+drop table if exists master_plan_shares;
+CREATE TABLE IF NOT EXISTS master_plan_shares (
+    id varchar(100) NOT NULL,
+    master_plan_id varchar(100) NOT NULL,
+    program_id varchar(100) NOT NULL,
+    shared_by_id varchar(100) NOT NULL,
+ 	created_at datetime NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  	PRIMARY KEY (id),
+    UNIQUE KEY (master_plan_id, program_id),
+    FOREIGN KEY (master_plan_id) REFERENCES master_plans(id) ON DELETE CASCADE,
+    FOREIGN KEY (program_id) REFERENCES programs(id) ON DELETE CASCADE,
+    FOREIGN KEY (shared_by_id) REFERENCES users(id) ON DELETE CASCADE
+);
@@ -0,0 +1,2 @@
+alter table tasks drop foreign key fk_tasks_master_plan;
+alter table tasks drop column master_plan_id;
@@ -0,0 +1,3 @@
+-- The master plan a task was instantiated from; none for the tasks added one by one.
+alter table tasks add column master_plan_id varchar(100) NULL;
+alter table tasks add constraint fk_tasks_master_plan FOREIGN KEY (master_plan_id) REFERENCES master_plans(id) ON DELETE SET NULL;
@@ -0,0 +1,122 @@
+use std::task::{Context, Poll};
+
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::error::ErrorUnauthorized;
+use actix_web::{Error, HttpMessage, HttpRequest};
+use chrono::Duration;
+use futures::future::{err, ok, Either, Ready};
+
+use crate::commons::token;
+
+const BEARER: &str = "Bearer ";
+const SESSION_VALIDITY_HOURS: i64 = 12;
+
+pub const UNAUTHENTICATED: &str = "Please login to continue.";
+
+/**
+ * The Caller is the user on whose behalf the request is being served.
+ *
+ * We resolve the caller from the session token and never from the
+ * arguments of a request.
+ */
+#[derive(Clone, Debug)]
+pub struct Caller {
+    pub user_id: String,
+}
+
+pub fn issue_session_token(user_id: &str) -> String {
+    token::sign(user_id, token::SESSION, Duration::hours(SESSION_VALIDITY_HOURS))
+}
+
+pub fn caller_of(request: &HttpRequest) -> Option<Caller> {
+    request.extensions().get::<Caller>().cloned()
+}
+
+/**
+ * Validates the session token offered through the Authorization header.
+ *
+ * A mandatory guard turns away the requests without a token, while an optional
+ * guard lets them pass through as anonymous. A bad token is never let in.
+ */
+pub struct Authentication {
+    mandatory: bool,
+}
+
+impl Authentication {
+    pub fn mandatory() -> Authentication {
+        Authentication { mandatory: true }
+    }
+
+    pub fn optional() -> Authentication {
+        Authentication { mandatory: false }
+    }
+}
+
+impl<S, B> Transform<S> for Authentication
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = AuthenticationMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(AuthenticationMiddleware {
+            service,
+            mandatory: self.mandatory,
+        })
+    }
+}
+
+pub struct AuthenticationMiddleware<S> {
+    service: S,
+    mandatory: bool,
+}
+
+impl<S, B> Service for AuthenticationMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: ServiceRequest) -> Self::Future {
+        match resolve_caller(&request) {
+            Ok(Some(caller)) => {
+                request.extensions_mut().insert(caller);
+                Either::Left(self.service.call(request))
+            }
+            Ok(None) if !self.mandatory => Either::Left(self.service.call(request)),
+            Ok(None) => Either::Right(err(ErrorUnauthorized(UNAUTHENTICATED))),
+            Err(e) => Either::Right(err(ErrorUnauthorized(e))),
+        }
+    }
+}
+
+fn resolve_caller(request: &ServiceRequest) -> Result<Option<Caller>, &'static str> {
+    let header = match request.headers().get("Authorization") {
+        Some(value) => value.to_str().map_err(|_| token::INVALID_TOKEN)?,
+        None => return Ok(None),
+    };
+
+    if !header.starts_with(BEARER) {
+        return Err(token::INVALID_TOKEN);
+    }
+
+    let claims = token::verify(&header[BEARER.len()..], token::SESSION)?;
+
+    Ok(Some(Caller { user_id: claims.sub }))
+}
@@ -0,0 +1,333 @@
+/**
+ * Authorization Policy.
+ *
+ * Authentication tells us who the caller is; the policy here tells whether
+ * that caller may touch a given record. Every mutation that changes records
+ * owned by a coach or a member states the Rule it needs, and the resolver
+ * checks it before handing over to the service.
+ */
+use diesel::prelude::*;
+
+use crate::commons::util;
+
+use crate::schema::conferences::dsl::conferences;
+use crate::schema::enrollments::dsl::enrollments;
+use crate::schema::master_plan_shares::dsl::master_plan_shares;
+use crate::schema::master_plans::dsl::master_plans;
+use crate::schema::master_tasks::dsl::master_tasks;
+use crate::schema::objectives::dsl::objectives;
+use crate::schema::observations::dsl::observations;
+use crate::schema::options::dsl::options;
+use crate::schema::programs::dsl::programs;
+use crate::schema::session_users::dsl::session_users;
+use crate::schema::sessions::dsl::sessions;
+use crate::schema::tasks::dsl::tasks;
+use crate::schema::users::dsl::users;
+use crate::schema::{conferences as conference, enrollments as enrollment, master_plan_shares as master_plan_share, master_plans as master_plan, master_tasks as master_task, objectives as objective, observations as observation, options as option, programs as program, session_users as session_user, sessions as session, tasks as task, users as user};
+
+use crate::services::programs::get_peer_coaches;
+
+pub const NOT_AUTHORIZED: &str = "You are not authorized to perform this action.";
+
+pub enum Rule<'a> {
+    /** Only the administrators of the platform. */
+    Administers,
+    /** The user, or one sharing an enrollment or a session with the user (by user id). */
+    MeetsUser(&'a str),
+    /** The coach, or a member with an enrollment under the coach (by coach id). */
+    SeesCoach(&'a str),
+    /** The coach or the member, when the member has an enrollment under the coach (by coach id, member id). */
+    PairsCoachMember(&'a str, &'a str),
+    /** Only the coach who offers the program (by program id). */
+    OwnsProgram(&'a str),
+    /** The coach of the program or a peer coach of its parent program (by program id). */
+    CoachesProgram(&'a str),
+    /** The coach or a peer coach of the program hosting the conference (by conference id). */
+    CoachesConference(&'a str),
+    /** The coach or a peer coach of the program the session belongs to (by session id). */
+    CoachesSession(&'a str),
+    /** Any one taking part in the session (by session id). */
+    AttendsSession(&'a str),
+    /** Only the user behind the session user (by session user id). */
+    OwnsSessionUser(&'a str),
+    /** The coach or a peer coach of the program of the enrollment (by enrollment id). */
+    CoachesEnrollment(&'a str),
+    /** The member of the enrollment, the coach or a peer coach of its program (by enrollment id). */
+    AttendsEnrollment(&'a str),
+    /** The member writing to a coach of the enrollment, or a coach writing to its member (by enrollment id, recipient id). */
+    WritesInEnrollment(&'a str, &'a str),
+    /** Any one taking part in the enrollment of the objective (by objective id). */
+    AttendsObjective(&'a str),
+    /** Any one taking part in the enrollment of the option (by option id). */
+    AttendsOption(&'a str),
+    /** Any one taking part in the enrollment of the observation (by observation id). */
+    AttendsObservation(&'a str),
+    /** Only the coach who drafted the master plan (by master plan id). */
+    OwnsMasterPlan(&'a str),
+    /** Only the coach who drafted the master plan the task belongs to (by master task id). */
+    OwnsMasterTask(&'a str),
+    /** The coach who drafted the master plan or a coach of a program it is shared with (by master plan id). */
+    UsesMasterPlan(&'a str),
+    /** The coach or a peer coach of the enrollment the task belongs to (by task id). */
+    CoachesTask(&'a str),
+    /** Only the member of the enrollment the task belongs to (by task id). */
+    OwnsTask(&'a str),
+}
+
+pub fn authorize(connection: &MysqlConnection, the_caller_id: &str, rule: Rule) -> Result<(), &'static str> {
+    let allowed = match rule {
+        Rule::Administers => administers(connection, the_caller_id),
+        Rule::MeetsUser(user_id) => meets_user(connection, the_caller_id, user_id),
+        Rule::SeesCoach(coach_id) => coach_id == the_caller_id || enrolled_under(connection, coach_id, the_caller_id),
+        Rule::PairsCoachMember(coach_id, member_id) => (coach_id == the_caller_id || member_id == the_caller_id) && enrolled_under(connection, coach_id, member_id),
+        Rule::OwnsProgram(program_id) => owns_program(connection, the_caller_id, program_id),
+        Rule::CoachesProgram(program_id) => coaches_program(connection, the_caller_id, program_id),
+        Rule::CoachesConference(conference_id) => coaches_conference(connection, the_caller_id, conference_id),
+        Rule::CoachesSession(session_id) => coaches_session(connection, the_caller_id, session_id),
+        Rule::AttendsSession(session_id) => attends_session(connection, the_caller_id, session_id),
+        Rule::OwnsSessionUser(session_user_id) => owns_session_user(connection, the_caller_id, session_user_id),
+        Rule::CoachesEnrollment(enrollment_id) => coaches_enrollment(connection, the_caller_id, enrollment_id),
+        Rule::AttendsEnrollment(enrollment_id) => attends_enrollment(connection, the_caller_id, enrollment_id),
+        Rule::WritesInEnrollment(enrollment_id, recipient_id) => writes_in_enrollment(connection, the_caller_id, enrollment_id, recipient_id),
+        Rule::AttendsObjective(objective_id) => {
+            let result = objectives.filter(objective::id.eq(objective_id)).select(objective::enrollment_id).first(connection);
+            attends_enrollment_of(connection, the_caller_id, result)
+        }
+        Rule::AttendsOption(option_id) => {
+            let result = options.filter(option::id.eq(option_id)).select(option::enrollment_id).first(connection);
+            attends_enrollment_of(connection, the_caller_id, result)
+        }
+        Rule::AttendsObservation(observation_id) => {
+            let result = observations.filter(observation::id.eq(observation_id)).select(observation::enrollment_id).first(connection);
+            attends_enrollment_of(connection, the_caller_id, result)
+        }
+        Rule::OwnsMasterPlan(master_plan_id) => owns_master_plan(connection, the_caller_id, master_plan_id),
+        Rule::OwnsMasterTask(master_task_id) => owns_master_task(connection, the_caller_id, master_task_id),
+        Rule::UsesMasterPlan(master_plan_id) => uses_master_plan(connection, the_caller_id, master_plan_id),
+        Rule::CoachesTask(task_id) => coaches_task(connection, the_caller_id, task_id),
+        Rule::OwnsTask(task_id) => owns_task(connection, the_caller_id, task_id),
+    };
+
+    if !allowed {
+        return Err(NOT_AUTHORIZED);
+    }
+
+    Ok(())
+}
+
+fn administers(connection: &MysqlConnection, the_caller_id: &str) -> bool {
+    let result: QueryResult<String> = users.filter(user::id.eq(the_caller_id)).select(user::user_type).first(connection);
+
+    match result {
+        Ok(user_type) => user_type == util::ADMIN,
+        Err(_) => false,
+    }
+}
+
+fn owns_program(connection: &MysqlConnection, the_caller_id: &str, program_id: &str) -> bool {
+    let result: QueryResult<String> = programs.filter(program::id.eq(program_id)).select(program::coach_id).first(connection);
+
+    match result {
+        Ok(coach_id) => coach_id == the_caller_id,
+        Err(_) => false,
+    }
+}
+
+fn meets_user(connection: &MysqlConnection, the_caller_id: &str, user_id: &str) -> bool {
+    if user_id == the_caller_id {
+        return true;
+    }
+
+    let result: QueryResult<Vec<(String, String)>> = enrollments
+        .filter(enrollment::member_id.eq_any(vec![the_caller_id, user_id]))
+        .select((enrollment::member_id, enrollment::program_id))
+        .load(connection);
+
+    let shares_enrollment = match result {
+        Ok(rows) => rows.iter().any(|(member_id, program_id)| {
+            (member_id == user_id && coaches_program(connection, the_caller_id, program_id.as_str())) || (member_id == the_caller_id && coaches_program(connection, user_id, program_id.as_str()))
+        }),
+        Err(_) => false,
+    };
+
+    if shares_enrollment {
+        return true;
+    }
+
+    let caller_sessions: Vec<String> = match session_users.filter(session_user::user_id.eq(the_caller_id)).select(session_user::session_id).load(connection) {
+        Ok(session_ids) => session_ids,
+        Err(_) => return false,
+    };
+
+    let result: QueryResult<i64> = session_users
+        .filter(session_user::user_id.eq(user_id))
+        .filter(session_user::session_id.eq_any(caller_sessions))
+        .count()
+        .get_result(connection);
+
+    match result {
+        Ok(count) => count > 0,
+        Err(_) => false,
+    }
+}
+
+fn enrolled_under(connection: &MysqlConnection, coach_id: &str, member_id: &str) -> bool {
+    let result: QueryResult<Vec<String>> = enrollments.filter(enrollment::member_id.eq(member_id)).select(enrollment::program_id).load(connection);
+
+    match result {
+        Ok(program_ids) => program_ids.iter().any(|program_id| coaches_program(connection, coach_id, program_id.as_str())),
+        Err(_) => false,
+    }
+}
+
+fn coaches_program(connection: &MysqlConnection, the_caller_id: &str, program_id: &str) -> bool {
+    if owns_program(connection, the_caller_id, program_id) {
+        return true;
+    }
+
+    match get_peer_coaches(connection, program_id) {
+        Ok(peers) => peers.iter().any(|peer| peer.coach.id == the_caller_id),
+        Err(_) => false,
+    }
+}
+
+fn coaches_conference(connection: &MysqlConnection, the_caller_id: &str, conference_id: &str) -> bool {
+    let result: QueryResult<String> = conferences.filter(conference::id.eq(conference_id)).select(conference::program_id).first(connection);
+
+    match result {
+        Ok(program_id) => coaches_program(connection, the_caller_id, program_id.as_str()),
+        Err(_) => false,
+    }
+}
+
+fn coaches_session(connection: &MysqlConnection, the_caller_id: &str, session_id: &str) -> bool {
+    let result: QueryResult<String> = sessions.filter(session::id.eq(session_id)).select(session::program_id).first(connection);
+
+    match result {
+        Ok(program_id) => coaches_program(connection, the_caller_id, program_id.as_str()),
+        Err(_) => false,
+    }
+}
+
+fn attends_session(connection: &MysqlConnection, the_caller_id: &str, session_id: &str) -> bool {
+    let result: QueryResult<i64> = session_users
+        .filter(session_user::session_id.eq(session_id))
+        .filter(session_user::user_id.eq(the_caller_id))
+        .count()
+        .get_result(connection);
+
+    match result {
+        Ok(count) => count > 0,
+        Err(_) => false,
+    }
+}
+
+fn coaches_enrollment(connection: &MysqlConnection, the_caller_id: &str, enrollment_id: &str) -> bool {
+    let result: QueryResult<String> = enrollments.filter(enrollment::id.eq(enrollment_id)).select(enrollment::program_id).first(connection);
+
+    match result {
+        Ok(program_id) => coaches_program(connection, the_caller_id, program_id.as_str()),
+        Err(_) => false,
+    }
+}
+
+fn owns_session_user(connection: &MysqlConnection, the_caller_id: &str, session_user_id: &str) -> bool {
+    let result: QueryResult<String> = session_users.filter(session_user::id.eq(session_user_id)).select(session_user::user_id).first(connection);
+
+    match result {
+        Ok(user_id) => user_id == the_caller_id,
+        Err(_) => false,
+    }
+}
+
+fn attends_enrollment(connection: &MysqlConnection, the_caller_id: &str, enrollment_id: &str) -> bool {
+    let result: QueryResult<(String, String)> = enrollments
+        .filter(enrollment::id.eq(enrollment_id))
+        .select((enrollment::member_id, enrollment::program_id))
+        .first(connection);
+
+    match result {
+        Ok((member_id, program_id)) => member_id == the_caller_id || coaches_program(connection, the_caller_id, program_id.as_str()),
+        Err(_) => false,
+    }
+}
+
+fn writes_in_enrollment(connection: &MysqlConnection, the_caller_id: &str, enrollment_id: &str, recipient_id: &str) -> bool {
+    let result: QueryResult<(String, String)> = enrollments
+        .filter(enrollment::id.eq(enrollment_id))
+        .select((enrollment::member_id, enrollment::program_id))
+        .first(connection);
+
+    match result {
+        Ok((member_id, program_id)) if member_id == the_caller_id => coaches_program(connection, recipient_id, program_id.as_str()),
+        Ok((member_id, program_id)) => member_id == recipient_id && coaches_program(connection, the_caller_id, program_id.as_str()),
+        Err(_) => false,
+    }
+}
+
+fn attends_enrollment_of(connection: &MysqlConnection, the_caller_id: &str, enrollment_id: QueryResult<String>) -> bool {
+    match enrollment_id {
+        Ok(enrollment_id) => attends_enrollment(connection, the_caller_id, enrollment_id.as_str()),
+        Err(_) => false,
+    }
+}
+
+fn owns_master_plan(connection: &MysqlConnection, the_caller_id: &str, master_plan_id: &str) -> bool {
+    let result: QueryResult<String> = master_plans.filter(master_plan::id.eq(master_plan_id)).select(master_plan::coach_id).first(connection);
+
+    match result {
+        Ok(coach_id) => coach_id == the_caller_id,
+        Err(_) => false,
+    }
+}
+
+fn owns_master_task(connection: &MysqlConnection, the_caller_id: &str, master_task_id: &str) -> bool {
+    let result: QueryResult<String> = master_tasks.filter(master_task::id.eq(master_task_id)).select(master_task::master_plan_id).first(connection);
+
+    match result {
+        Ok(master_plan_id) => owns_master_plan(connection, the_caller_id, master_plan_id.as_str()),
+        Err(_) => false,
+    }
+}
+
+fn uses_master_plan(connection: &MysqlConnection, the_caller_id: &str, master_plan_id: &str) -> bool {
+    if owns_master_plan(connection, the_caller_id, master_plan_id) {
+        return true;
+    }
+
+    let result: QueryResult<Vec<String>> = master_plan_shares
+        .filter(master_plan_share::master_plan_id.eq(master_plan_id))
+        .select(master_plan_share::program_id)
+        .load(connection);
+
+    match result {
+        Ok(program_ids) => program_ids.iter().any(|program_id| coaches_program(connection, the_caller_id, program_id.as_str())),
+        Err(_) => false,
+    }
+}
+
+fn coaches_task(connection: &MysqlConnection, the_caller_id: &str, task_id: &str) -> bool {
+    let result: QueryResult<String> = tasks
+        .inner_join(enrollments)
+        .filter(task::id.eq(task_id))
+        .select(enrollment::program_id)
+        .first(connection);
+
+    match result {
+        Ok(program_id) => coaches_program(connection, the_caller_id, program_id.as_str()),
+        Err(_) => false,
+    }
+}
+
+fn owns_task(connection: &MysqlConnection, the_caller_id: &str, task_id: &str) -> bool {
+    let result: QueryResult<String> = tasks
+        .inner_join(enrollments)
+        .filter(task::id.eq(task_id))
+        .select(enrollment::member_id)
+        .first(connection);
+
+    match result {
+        Ok(member_id) => member_id == the_caller_id,
+        Err(_) => false,
+    }
+}
@@ -1,15 +1,25 @@
 use crate::models::abstract_tasks::AbstractTask;
+use crate::models::availabilities::{Availability, Blackout, CandidateSlot, WorkingHour};
+use crate::models::coach_applications::CoachApplication;
 use crate::models::enrollments::Enrollment;
+use crate::models::master_plan_revisions::MasterPlanRevision;
+use crate::models::master_plan_shares::MasterPlanShare;
 use crate::models::master_plans::MasterPlan;
 use crate::models::master_tasks::MasterTask;
 use crate::models::notes::Note;
 use crate::models::objectives::Objective;
 use crate::models::observations::Observation;
 use crate::models::options::Constraint;
+use crate::models::program_plans::ProgramPlan;
 use crate::models::programs::{Program,ProgramCoach};
+use crate::models::schedules::{EnrollmentSchedule, PlanForecast};
 use crate::models::sessions::Session;
+use crate::models::session_series::SeriesRow;
+use crate::models::session_slots::SessionSlot;
 use crate::models::session_users::SessionPeople;
 use crate::models::conferences::Conference;
+use crate::models::task_links::TaskLink;
+use crate::models::task_moves::TaskMove;
 use crate::models::tasks::Task;
 use crate::models::user_events::{EventRow, PlanRow, ToDo};
 
@@ -17,7 +27,6 @@ use crate::models::user_programs::ProgramRow;
 use crate::models::coach_members::MemberRow;
 use crate::models::user_artifacts::NoteRow;
 use crate::models::user_artifacts::BoardRow;
-use crate::models::correspondences::Mailable;
 use crate::models::discussions::Discussion;
 use crate::models::discussion_queue::PendingFeed;
 
@@ -37,6 +46,7 @@ use crate::models::discussion_queue::PendingFeed;
  */
 use crate::models::users::User;
 use diesel::result::Error;
+use std::fmt;
 
 #[derive(juniper::GraphQLObject)]
 pub struct QueryError {
@@ -62,6 +72,8 @@ impl From<String> for QueryError {
 pub struct ValidationError {
     pub field: String,
     pub message: String,
+    /** The ids of the records the error refers to, like the clashing sessions. */
+    pub ids: Vec<String>,
 }
 
 impl ValidationError {
@@ -69,6 +81,15 @@ impl ValidationError {
         ValidationError {
             field: String::from(field),
             message: String::from(message),
+            ids: Vec::new(),
+        }
+    }
+
+    pub fn with_ids(field: &str, message: &str, ids: Vec<String>) -> ValidationError {
+        ValidationError {
+            field: String::from(field),
+            message: String::from(message),
+            ids,
         }
     }
 }
@@ -106,6 +127,136 @@ impl QueryResult<Vec<PendingFeed>> {
 }
 
 
+#[juniper::object(name = "CoachApplicationsResult")]
+impl QueryResult<Vec<CoachApplication>> {
+    pub fn applications(&self) -> Option<&Vec<CoachApplication>> {
+        self.0.as_ref().ok()
+    }
+    pub fn error(&self) -> Option<&QueryError> {
+        self.0.as_ref().err()
+    }
+}
+
+#[juniper::object(name = "AvailabilityResult")]
+impl QueryResult<Availability> {
+    pub fn availability(&self) -> Option<&Availability> {
+        self.0.as_ref().ok()
+    }
+    pub fn error(&self) -> Option<&QueryError> {
+        self.0.as_ref().err()
+    }
+}
+
+#[juniper::object(name = "CandidateSlotsResult")]
+impl QueryResult<Vec<CandidateSlot>> {
+    pub fn slots(&self) -> Option<&Vec<CandidateSlot>> {
+        self.0.as_ref().ok()
+    }
+    pub fn error(&self) -> Option<&QueryError> {
+        self.0.as_ref().err()
+    }
+}
+
+#[juniper::object(name = "SeriesRowResult")]
+impl QueryResult<SeriesRow> {
+    pub fn series(&self) -> Option<&SeriesRow> {
+        self.0.as_ref().ok()
+    }
+    pub fn error(&self) -> Option<&QueryError> {
+        self.0.as_ref().err()
+    }
+}
+
+#[juniper::object(name = "EnrollmentScheduleResult")]
+impl QueryResult<EnrollmentSchedule> {
+    pub fn schedule(&self) -> Option<&EnrollmentSchedule> {
+        self.0.as_ref().ok()
+    }
+    pub fn error(&self) -> Option<&QueryError> {
+        self.0.as_ref().err()
+    }
+}
+
+#[juniper::object(name = "TaskMovesResult")]
+impl QueryResult<Vec<TaskMove>> {
+    pub fn moves(&self) -> Option<&Vec<TaskMove>> {
+        self.0.as_ref().ok()
+    }
+    pub fn error(&self) -> Option<&QueryError> {
+        self.0.as_ref().err()
+    }
+}
+
+#[juniper::object(name = "PlanForecastResult")]
+impl QueryResult<PlanForecast> {
+    pub fn forecast(&self) -> Option<&PlanForecast> {
+        self.0.as_ref().ok()
+    }
+    pub fn error(&self) -> Option<&QueryError> {
+        self.0.as_ref().err()
+    }
+}
+
+#[juniper::object(name = "MasterPlanRevisionsResult")]
+impl QueryResult<Vec<MasterPlanRevision>> {
+    pub fn revisions(&self) -> Option<&Vec<MasterPlanRevision>> {
+        self.0.as_ref().ok()
+    }
+    pub fn error(&self) -> Option<&QueryError> {
+        self.0.as_ref().err()
+    }
+}
+
+#[juniper::object(name = "ProgramPlansResult")]
+impl QueryResult<Vec<ProgramPlan>> {
+    pub fn plans(&self) -> Option<&Vec<ProgramPlan>> {
+        self.0.as_ref().ok()
+    }
+    pub fn error(&self) -> Option<&QueryError> {
+        self.0.as_ref().err()
+    }
+}
+
+#[juniper::object(name = "MasterPlanSharesResult")]
+impl QueryResult<Vec<MasterPlanShare>> {
+    pub fn shares(&self) -> Option<&Vec<MasterPlanShare>> {
+        self.0.as_ref().ok()
+    }
+    pub fn error(&self) -> Option<&QueryError> {
+        self.0.as_ref().err()
+    }
+}
+
+#[juniper::object(name = "TaskLinksResult")]
+impl QueryResult<Vec<TaskLink>> {
+    pub fn links(&self) -> Option<&Vec<TaskLink>> {
+        self.0.as_ref().ok()
+    }
+    pub fn error(&self) -> Option<&QueryError> {
+        self.0.as_ref().err()
+    }
+}
+
+#[juniper::object(name = "PlanDocumentResult")]
+impl QueryResult<String> {
+    pub fn document(&self) -> Option<&String> {
+        self.0.as_ref().ok()
+    }
+    pub fn error(&self) -> Option<&QueryError> {
+        self.0.as_ref().err()
+    }
+}
+
+#[juniper::object(name = "SessionSlotsResult")]
+impl QueryResult<Vec<SessionSlot>> {
+    pub fn slots(&self) -> Option<&Vec<SessionSlot>> {
+        self.0.as_ref().ok()
+    }
+    pub fn error(&self) -> Option<&QueryError> {
+        self.0.as_ref().err()
+    }
+}
+
 #[juniper::object(name = "AbstractTasksResult")]
 impl QueryResult<Vec<AbstractTask>> {
     pub fn abstract_tasks(&self) -> Option<&Vec<AbstractTask>> {
@@ -266,9 +417,9 @@ impl QueryResult<Vec<MemberRow>> {
     }
 }
 
-#[juniper::object(name = "Mailables")]
-impl QueryResult<Vec<Mailable>> {
-    pub fn mails(&self) -> Option<&Vec<Mailable>> {
+#[juniper::object(name = "EnrolledMembers")]
+impl QueryResult<Vec<User>> {
+    pub fn members(&self) -> Option<&Vec<User>> {
         self.0.as_ref().ok()
     }
     pub fn error(&self) -> Option<&QueryError> {
@@ -284,6 +435,9 @@ pub fn query_error<T>(error: diesel::result::Error) -> QueryResult<T> {
     QueryResult(Err(e))
 }
 
+pub fn access_denied<T>(message: &str) -> QueryResult<T> {
+    QueryResult(Err(QueryError { message: String::from(message) }))
+}
 
 pub struct MutationResult<T>(pub Result<T, Vec<ValidationError>>);
 
@@ -298,6 +452,17 @@ impl MutationResult<Session> {
     }
 }
 
+#[juniper::object(name = "SessionSeriesResult")]
+impl MutationResult<SeriesRow> {
+    pub fn series(&self) -> Option<&SeriesRow> {
+        self.0.as_ref().ok()
+    }
+
+    pub fn errors(&self) -> Option<&Vec<ValidationError>> {
+        self.0.as_ref().err()
+    }
+}
+
 #[juniper::object(name = "ConferenceResult")]
 impl MutationResult<Conference> {
     pub fn conference(&self) -> Option<&Conference> {
@@ -342,6 +507,50 @@ impl MutationResult<MasterPlan> {
     }
 }
 
+#[juniper::object(name = "ProgramPlanResult")]
+impl MutationResult<ProgramPlan> {
+    pub fn program_plan(&self) -> Option<&ProgramPlan> {
+        self.0.as_ref().ok()
+    }
+
+    pub fn errors(&self) -> Option<&Vec<ValidationError>> {
+        self.0.as_ref().err()
+    }
+}
+
+#[juniper::object(name = "MasterPlanShareResult")]
+impl MutationResult<MasterPlanShare> {
+    pub fn share(&self) -> Option<&MasterPlanShare> {
+        self.0.as_ref().ok()
+    }
+
+    pub fn errors(&self) -> Option<&Vec<ValidationError>> {
+        self.0.as_ref().err()
+    }
+}
+
+#[juniper::object(name = "TaskLinksMutationResult")]
+impl MutationResult<Vec<TaskLink>> {
+    pub fn links(&self) -> Option<&Vec<TaskLink>> {
+        self.0.as_ref().ok()
+    }
+
+    pub fn errors(&self) -> Option<&Vec<ValidationError>> {
+        self.0.as_ref().err()
+    }
+}
+
+#[juniper::object(name = "CoachApplicationResult")]
+impl MutationResult<CoachApplication> {
+    pub fn application(&self) -> Option<&CoachApplication> {
+        self.0.as_ref().ok()
+    }
+
+    pub fn errors(&self) -> Option<&Vec<ValidationError>> {
+        self.0.as_ref().err()
+    }
+}
+
 #[juniper::object(name = "ProgramResult")]
 impl MutationResult<Program> {
     pub fn program(&self) -> Option<&Program> {
@@ -430,6 +639,28 @@ impl MutationResult<Task> {
     }
 }
 
+#[juniper::object(name = "TasksResult")]
+impl MutationResult<Vec<Task>> {
+    pub fn tasks(&self) -> Option<&Vec<Task>> {
+        self.0.as_ref().ok()
+    }
+
+    pub fn errors(&self) -> Option<&Vec<ValidationError>> {
+        self.0.as_ref().err()
+    }
+}
+
+#[juniper::object(name = "TaskMovesMutationResult")]
+impl MutationResult<Vec<TaskMove>> {
+    pub fn moves(&self) -> Option<&Vec<TaskMove>> {
+        self.0.as_ref().ok()
+    }
+
+    pub fn errors(&self) -> Option<&Vec<ValidationError>> {
+        self.0.as_ref().err()
+    }
+}
+
 #[juniper::object(name = "MasterTaskResult")]
 impl MutationResult<MasterTask> {
     pub fn master_task(&self) -> Option<&MasterTask> {
@@ -441,6 +672,28 @@ impl MutationResult<MasterTask> {
     }
 }
 
+#[juniper::object(name = "WorkingHoursResult")]
+impl MutationResult<Vec<WorkingHour>> {
+    pub fn working_hours(&self) -> Option<&Vec<WorkingHour>> {
+        self.0.as_ref().ok()
+    }
+
+    pub fn errors(&self) -> Option<&Vec<ValidationError>> {
+        self.0.as_ref().err()
+    }
+}
+
+#[juniper::object(name = "BlackoutResult")]
+impl MutationResult<Blackout> {
+    pub fn blackout(&self) -> Option<&Blackout> {
+        self.0.as_ref().ok()
+    }
+
+    pub fn errors(&self) -> Option<&Vec<ValidationError>> {
+        self.0.as_ref().err()
+    }
+}
+
 #[juniper::object(name = "Updates")]
 impl MutationResult<String> {
     pub fn rows(&self) -> Option<&String> {
@@ -468,6 +721,7 @@ pub fn service_error<T>(message: &str) -> MutationResult<T> {
     let ve = ValidationError {
         field: String::from("service"),
         message: String::from(message),
+        ids: Vec::new(),
     };
     v.push(ve);
     MutationResult(Err(v))
@@ -479,8 +733,66 @@ pub fn mutation_error<T>(error: diesel::result::Error) -> MutationResult<T> {
     let mut v: Vec<ValidationError> = Vec::new();
     let ve = ValidationError {
         field: String::from("service"),message,
+        ids: Vec::new(),
     };
     v.push(ve);
 
     MutationResult(Err(v))
 }
+
+pub fn authentication_error<T>(message: &str) -> MutationResult<T> {
+    let mut v: Vec<ValidationError> = Vec::new();
+    let ve = ValidationError {
+        field: String::from("authentication"),
+        message: String::from(message),
+        ids: Vec::new(),
+    };
+    v.push(ve);
+    MutationResult(Err(v))
+}
+
+/**
+ * Why a resolver turned the caller away: no caller at all, or one the
+ * authorization policy does not allow. A query answers both with an error;
+ * a mutation tells them apart by the field of the error.
+ */
+pub enum Denial {
+    Unauthenticated(&'static str),
+    Unauthorized(&'static str),
+}
+
+impl fmt::Display for Denial {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Denial::Unauthenticated(message) | Denial::Unauthorized(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl<T> From<Denial> for QueryResult<T> {
+    fn from(denial: Denial) -> QueryResult<T> {
+        match denial {
+            Denial::Unauthenticated(message) | Denial::Unauthorized(message) => access_denied(message),
+        }
+    }
+}
+
+impl<T> From<Denial> for MutationResult<T> {
+    fn from(denial: Denial) -> MutationResult<T> {
+        match denial {
+            Denial::Unauthenticated(message) => authentication_error(message),
+            Denial::Unauthorized(message) => authorization_error(message),
+        }
+    }
+}
+
+pub fn authorization_error<T>(message: &str) -> MutationResult<T> {
+    let mut v: Vec<ValidationError> = Vec::new();
+    let ve = ValidationError {
+        field: String::from("authorization"),
+        message: String::from(message),
+        ids: Vec::new(),
+    };
+    v.push(ve);
+    MutationResult(Err(v))
+}
@@ -0,0 +1,408 @@
+/**
+ * Diagrams of the plans, to print or to attach to the program content and
+ * the mails.
+ *
+ * A master plan renders as a graph: Graphviz DOT, for those who have the
+ * Graphviz tools, or a plain SVG laid out here, column by column along the
+ * forward links. The tasks of an enrollment render as a Gantt chart: an SVG,
+ * or a Mermaid chart for the markdown documents. The backward links (rework)
+ * are dashed and do not take part in the layout.
+ */
+use chrono::{Duration, NaiveDateTime};
+
+const NODE_WIDTH: i64 = 160;
+const NODE_HEIGHT: i64 = 48;
+const COLUMN_GAP: i64 = 80;
+const ROW_GAP: i64 = 32;
+const MARGIN: i64 = 24;
+
+const CHART_WIDTH: i64 = 960;
+const LABEL_WIDTH: i64 = 200;
+const BAR_HEIGHT: i64 = 16;
+const ROW_HEIGHT: i64 = 28;
+const HEADER_HEIGHT: i64 = 40;
+
+pub struct Node {
+    pub id: String,
+    pub label: String,
+    /** The TaskType of the master task, like START or DECISION. */
+    pub kind: String,
+    pub role: String,
+}
+
+pub struct Edge {
+    pub source_id: String,
+    pub target_id: String,
+    /** In hours. */
+    pub lead_time: i32,
+    pub is_forward: bool,
+}
+
+pub struct Bar {
+    pub label: String,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub actual_start: Option<NaiveDateTime>,
+    pub actual_end: Option<NaiveDateTime>,
+    pub is_critical: bool,
+}
+
+/**
+ * The Graphviz shape of each kind of task.
+ */
+fn shape_of(kind: &str) -> &'static str {
+    match kind {
+        "START" => "shape=\"circle\"",
+        "STOP" => "shape=\"doublecircle\"",
+        "DECISION" => "shape=\"diamond\"",
+        "BUFFER" => "shape=\"box\", style=\"dashed\"",
+        "PROCEDURE" => "shape=\"component\"",
+        _ => "shape=\"box\"",
+    }
+}
+
+pub fn as_dot(name: &str, nodes: &[Node], edges: &[Edge]) -> String {
+    let mut lines: Vec<String> = vec![
+        format!("digraph \"{}\" {{", escape_dot(name)),
+        String::from("    rankdir=LR;"),
+        String::from("    node [fontname=\"Helvetica\", fontsize=10];"),
+        String::from("    edge [fontname=\"Helvetica\", fontsize=9];"),
+    ];
+
+    for node in nodes {
+        lines.push(format!(
+            "    \"{}\" [label=\"{}\\n({})\", {}];",
+            escape_dot(node.id.as_str()),
+            escape_dot(node.label.as_str()),
+            escape_dot(node.role.as_str()),
+            shape_of(node.kind.as_str())
+        ));
+    }
+
+    for edge in edges {
+        let mut attributes: Vec<String> = Vec::new();
+
+        if edge.lead_time != 0 {
+            attributes.push(format!("label=\"+{}h\"", edge.lead_time));
+        }
+
+        if !edge.is_forward {
+            attributes.push(String::from("style=\"dashed\", constraint=false"));
+        }
+
+        let attributes = if attributes.is_empty() { String::new() } else { format!(" [{}]", attributes.join(", ")) };
+
+        lines.push(format!("    \"{}\" -> \"{}\"{};", escape_dot(edge.source_id.as_str()), escape_dot(edge.target_id.as_str()), attributes));
+    }
+
+    lines.push(String::from("}"));
+
+    lines.join("\n") + "\n"
+}
+
+/**
+ * The column of each node is the longest chain of forward links before it;
+ * the nodes of a (broken, draft) cycle stop moving right after as many
+ * rounds as there are nodes.
+ */
+fn columns_of(nodes: &[Node], edges: &[Edge]) -> Vec<usize> {
+    let index_of = |id: &str| nodes.iter().position(|node| node.id == id);
+    let mut columns: Vec<usize> = vec![0; nodes.len()];
+
+    for _ in 0..nodes.len() {
+        let mut moved = false;
+
+        for edge in edges.iter().filter(|edge| edge.is_forward) {
+            if let (Some(source), Some(target)) = (index_of(edge.source_id.as_str()), index_of(edge.target_id.as_str())) {
+                if columns[target] < columns[source] + 1 {
+                    columns[target] = columns[source] + 1;
+                    moved = true;
+                }
+            }
+        }
+
+        if !moved {
+            break;
+        }
+    }
+
+    columns
+}
+
+pub fn as_graph_svg(name: &str, nodes: &[Node], edges: &[Edge]) -> String {
+    let columns = columns_of(nodes, edges);
+    let mut rows: Vec<usize> = vec![0; nodes.len()];
+    let mut column_sizes: Vec<usize> = vec![0; columns.iter().max().map_or(1, |max| max + 1)];
+
+    for (index, column) in columns.iter().enumerate() {
+        rows[index] = column_sizes[*column];
+        column_sizes[*column] += 1;
+    }
+
+    let x_of = |index: usize| MARGIN + columns[index] as i64 * (NODE_WIDTH + COLUMN_GAP);
+    let y_of = |index: usize| MARGIN * 2 + rows[index] as i64 * (NODE_HEIGHT + ROW_GAP);
+
+    let width = MARGIN * 2 + column_sizes.len() as i64 * (NODE_WIDTH + COLUMN_GAP) - COLUMN_GAP;
+    let height = MARGIN * 3 + *column_sizes.iter().max().unwrap_or(&1) as i64 * (NODE_HEIGHT + ROW_GAP) - ROW_GAP;
+
+    let mut parts: Vec<String> = vec![
+        svg_header(width, height),
+        String::from("<defs><marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" markerWidth=\"8\" markerHeight=\"8\" orient=\"auto\"><path d=\"M 0 0 L 10 5 L 0 10 z\" fill=\"#555\"/></marker></defs>"),
+        format!("<text x=\"{}\" y=\"{}\" font-size=\"14\" font-weight=\"bold\">{}</text>", MARGIN, MARGIN, escape_xml(name)),
+    ];
+
+    let index_of = |id: &str| nodes.iter().position(|node| node.id == id);
+
+    for edge in edges {
+        if let (Some(source), Some(target)) = (index_of(edge.source_id.as_str()), index_of(edge.target_id.as_str())) {
+            let dash = if edge.is_forward { "" } else { " stroke-dasharray=\"4 4\"" };
+            parts.push(format!(
+                "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#555\"{} marker-end=\"url(#arrow)\"/>",
+                x_of(source) + NODE_WIDTH,
+                y_of(source) + NODE_HEIGHT / 2,
+                x_of(target),
+                y_of(target) + NODE_HEIGHT / 2,
+                dash
+            ));
+        }
+    }
+
+    for (index, node) in nodes.iter().enumerate() {
+        let (x, y) = (x_of(index), y_of(index));
+        let rounding = match node.kind.as_str() {
+            "START" | "STOP" => NODE_HEIGHT / 2,
+            "DECISION" => 0,
+            _ => 6,
+        };
+        let fill = match node.kind.as_str() {
+            "START" | "STOP" => "#e3f2fd",
+            "DECISION" => "#fff8e1",
+            "BUFFER" => "#f5f5f5",
+            _ => "#ffffff",
+        };
+
+        parts.push(format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{}\" fill=\"{}\" stroke=\"#333\"/>",
+            x, y, NODE_WIDTH, NODE_HEIGHT, rounding, fill
+        ));
+        parts.push(format!("<text x=\"{}\" y=\"{}\" font-size=\"12\" text-anchor=\"middle\">{}</text>", x + NODE_WIDTH / 2, y + 20, escape_xml(node.label.as_str())));
+        parts.push(format!(
+            "<text x=\"{}\" y=\"{}\" font-size=\"10\" text-anchor=\"middle\" fill=\"#666\">{} · {}</text>",
+            x + NODE_WIDTH / 2,
+            y + 36,
+            escape_xml(node.kind.as_str()),
+            escape_xml(node.role.as_str())
+        ));
+    }
+
+    parts.push(String::from("</svg>"));
+
+    parts.join("\n") + "\n"
+}
+
+/**
+ * The planned bars are blue (red on the critical path); the actual ones,
+ * from the actual start to the actual end or to now, overlay them in green.
+ */
+pub fn as_gantt_svg(title: &str, bars: &[Bar], now: NaiveDateTime) -> String {
+    let first = bars.iter().map(|bar| bar.actual_start.map_or(bar.start, |actual| actual.min(bar.start))).min().unwrap_or(now);
+    let last = bars.iter().map(|bar| bar.actual_end.map_or(bar.end, |actual| actual.max(bar.end))).max().unwrap_or(now);
+    let span = (last - first).num_minutes().max(60);
+
+    let scale = |date: NaiveDateTime| LABEL_WIDTH + (date - first).num_minutes() * (CHART_WIDTH - LABEL_WIDTH - MARGIN) / span;
+    let height = HEADER_HEIGHT + bars.len() as i64 * ROW_HEIGHT + MARGIN;
+
+    let mut parts: Vec<String> = vec![
+        svg_header(CHART_WIDTH, height),
+        format!("<text x=\"{}\" y=\"{}\" font-size=\"14\" font-weight=\"bold\">{}</text>", MARGIN / 2, MARGIN, escape_xml(title)),
+    ];
+
+    let mut day = first.date().and_hms(0, 0, 0) + Duration::days(1);
+    while day < last {
+        let x = scale(day);
+        parts.push(format!("<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#eee\"/>", x, HEADER_HEIGHT - 8, x, height - MARGIN));
+        day += Duration::days(1);
+    }
+
+    for (index, bar) in bars.iter().enumerate() {
+        let y = HEADER_HEIGHT + index as i64 * ROW_HEIGHT;
+        let fill = if bar.is_critical { "#e57373" } else { "#64b5f6" };
+
+        parts.push(format!("<text x=\"{}\" y=\"{}\" font-size=\"11\">{}</text>", MARGIN / 2, y + 12, escape_xml(bar.label.as_str())));
+        parts.push(format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"><title>{} → {}</title></rect>",
+            scale(bar.start),
+            y,
+            (scale(bar.end) - scale(bar.start)).max(2),
+            BAR_HEIGHT,
+            fill,
+            bar.start.format("%Y-%m-%d %H:%M"),
+            bar.end.format("%Y-%m-%d %H:%M")
+        ));
+
+        if let Some(actual_start) = bar.actual_start {
+            let actual_end = bar.actual_end.unwrap_or(now);
+            parts.push(format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#43a047\"/>",
+                scale(actual_start),
+                y + BAR_HEIGHT - 4,
+                (scale(actual_end) - scale(actual_start)).max(2),
+                6
+            ));
+        }
+    }
+
+    parts.push(format!(
+        "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#333\" stroke-dasharray=\"2 2\"/>",
+        scale(now.max(first).min(last)),
+        HEADER_HEIGHT - 8,
+        scale(now.max(first).min(last)),
+        height - MARGIN
+    ));
+    parts.push(String::from("</svg>"));
+
+    parts.join("\n") + "\n"
+}
+
+/**
+ * A Mermaid gantt chart; done, active and critical tasks are tagged as such.
+ */
+pub fn as_mermaid_gantt(title: &str, bars: &[Bar]) -> String {
+    let mut lines: Vec<String> = vec![
+        String::from("gantt"),
+        format!("    title {}", as_mermaid_text(title)),
+        String::from("    dateFormat YYYY-MM-DDTHH:mm"),
+        String::from("    axisFormat %d %b"),
+        String::from("    section Tasks"),
+    ];
+
+    for (index, bar) in bars.iter().enumerate() {
+        let mut tags: Vec<String> = Vec::new();
+
+        if bar.is_critical {
+            tags.push(String::from("crit"));
+        }
+
+        if bar.actual_end.is_some() {
+            tags.push(String::from("done"));
+        } else if bar.actual_start.is_some() {
+            tags.push(String::from("active"));
+        }
+
+        tags.push(format!("t{}", index + 1));
+        tags.push(bar.start.format("%Y-%m-%dT%H:%M").to_string());
+        tags.push(bar.end.format("%Y-%m-%dT%H:%M").to_string());
+
+        lines.push(format!("    {} :{}", as_mermaid_text(bar.label.as_str()), tags.join(", ")));
+    }
+
+    lines.join("\n") + "\n"
+}
+
+fn svg_header(width: i64, height: i64) -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" font-family=\"Helvetica, Arial, sans-serif\">",
+        width, height, width, height
+    )
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/**
+ * The colon and the hash mean something to Mermaid in a task line.
+ */
+fn as_mermaid_text(text: &str) -> String {
+    text.replace([':', '#', ';'], " ").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::commons::util;
+
+    fn node(id: &str, kind: &str) -> Node {
+        Node {
+            id: String::from(id),
+            label: format!("Task \"{}\"", id),
+            kind: String::from(kind),
+            role: String::from("member"),
+        }
+    }
+
+    fn edge(source_id: &str, target_id: &str, is_forward: bool) -> Edge {
+        Edge {
+            source_id: String::from(source_id),
+            target_id: String::from(target_id),
+            lead_time: if is_forward { 4 } else { 0 },
+            is_forward,
+        }
+    }
+
+    fn bar(id: &str, start_time: &str, hours: i64, is_critical: bool) -> Bar {
+        let start = util::as_date(start_time).unwrap();
+
+        Bar {
+            label: format!("Review: {}", id),
+            start,
+            end: start + Duration::hours(hours),
+            actual_start: None,
+            actual_end: None,
+            is_critical,
+        }
+    }
+
+    #[test]
+    fn should_render_dot() {
+        let nodes = vec![node("a", "START"), node("b", "DECISION"), node("c", "STOP")];
+        let edges = vec![edge("a", "b", true), edge("b", "c", true), edge("c", "a", false)];
+
+        let dot = as_dot("Onboarding", &nodes, &edges);
+
+        assert!(dot.starts_with("digraph \"Onboarding\" {\n"));
+        assert!(dot.contains("\"a\" [label=\"Task \\\"a\\\"\\n(member)\", shape=\"circle\"];"));
+        assert!(dot.contains("shape=\"diamond\""));
+        assert!(dot.contains("\"a\" -> \"b\" [label=\"+4h\"];"));
+        assert!(dot.contains("\"c\" -> \"a\" [style=\"dashed\", constraint=false];"));
+        assert!(dot.ends_with("}\n"));
+    }
+
+    #[test]
+    fn should_lay_out_columns_along_forward_links() {
+        let nodes = vec![node("c", "STOP"), node("b", "ACTIVITY"), node("a", "START"), node("d", "ACTIVITY")];
+        let edges = vec![edge("a", "b", true), edge("b", "c", true), edge("a", "c", true), edge("c", "a", false), edge("d", "d", true)];
+
+        assert_eq!(vec![2, 1, 0, 4], columns_of(&nodes, &edges));
+
+        let svg = as_graph_svg("Onboarding", &nodes[..3], &edges[..4]);
+        assert_eq!(3, svg.matches("<rect").count());
+        assert_eq!(4, svg.matches("<line").count());
+        assert_eq!(1, svg.matches("stroke-dasharray").count());
+        assert!(svg.contains("Task &quot;a&quot;"));
+    }
+
+    #[test]
+    fn should_render_gantt() {
+        let mut done = bar("a", "2021-03-15T09:00:00Z", 24, true);
+        done.actual_start = Some(done.start);
+        done.actual_end = Some(done.end);
+        let mut running = bar("b", "2021-03-16T09:00:00Z", 48, false);
+        running.actual_start = Some(running.start);
+        let bars = vec![done, running, bar("c", "2021-03-18T09:00:00Z", 8, true)];
+
+        let svg = as_gantt_svg("Plan", &bars, util::as_date("2021-03-17T09:00:00Z").unwrap());
+        assert_eq!(5, svg.matches("<rect").count());
+        assert_eq!(2, svg.matches("#e57373").count());
+
+        let mermaid = as_mermaid_gantt("Plan", &bars);
+        assert!(mermaid.contains("    Review  a :crit, done, t1, 2021-03-15T09:00, 2021-03-16T09:00\n"));
+        assert!(mermaid.contains("    Review  b :active, t2, 2021-03-16T09:00, 2021-03-18T09:00\n"));
+        assert!(mermaid.contains("    Review  c :crit, t3, 2021-03-18T09:00, 2021-03-18T17:00\n"));
+    }
+}
@@ -0,0 +1,260 @@
+/**
+ * iCalendar (RFC 5545).
+ *
+ * We generate a VCALENDAR with a single VEVENT for the session mails, so that
+ * any standard mail client can add (or remove) the session to a calendar.
+ *
+ * The UID of the event is the id of the session and the SEQUENCE tells the
+ * revision of the event; a client replaces an event of the same UID when it
+ * receives a higher SEQUENCE. All the dates are in UTC.
+ *
+ * The calendar feed of a user publishes many such events in one VCALENDAR.
+ */
+use chrono::NaiveDateTime;
+use chrono_tz::Tz;
+
+use crate::commons::util;
+
+const PRODUCT_ID: &str = "-//krscode//Ferris//EN";
+const CRLF: &str = "\r\n";
+const MAX_LINE_OCTETS: usize = 75;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Method {
+    Publish,
+    Request,
+    Cancel,
+}
+
+impl Method {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Method::Publish => "PUBLISH",
+            Method::Request => "REQUEST",
+            Method::Cancel => "CANCEL",
+        }
+    }
+
+    fn status(self) -> &'static str {
+        match self {
+            Method::Publish | Method::Request => "CONFIRMED",
+            Method::Cancel => "CANCELLED",
+        }
+    }
+}
+
+pub struct Attendee {
+    pub name: String,
+    pub email: String,
+}
+
+pub struct CalendarEvent {
+    pub uid: String,
+    pub sequence: i32,
+    pub summary: String,
+    pub description: Option<String>,
+    pub organizer: Option<Attendee>,
+    pub attendees: Vec<Attendee>,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub method: Method,
+}
+
+impl CalendarEvent {
+    pub fn to_ics(&self) -> String {
+        as_calendar(self.method, &[], std::slice::from_ref(self))
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = vec![
+            String::from("BEGIN:VEVENT"),
+            format!("UID:{}", escape(self.uid.as_str())),
+            format!("SEQUENCE:{}", self.sequence),
+            format!("DTSTAMP:{}", as_utc(&util::now())),
+            format!("DTSTART:{}", as_utc(&self.start)),
+            format!("DTEND:{}", as_utc(&self.end)),
+            format!("SUMMARY:{}", escape(self.summary.as_str())),
+        ];
+
+        if let Some(description) = &self.description {
+            lines.push(format!("DESCRIPTION:{}", escape(description.as_str())));
+        }
+
+        if let Some(organizer) = &self.organizer {
+            lines.push(format!("ORGANIZER;CN={}:mailto:{}", as_param(organizer.name.as_str()), organizer.email));
+        }
+
+        for attendee in &self.attendees {
+            lines.push(format!(
+                "ATTENDEE;CN={};ROLE=REQ-PARTICIPANT;PARTSTAT=NEEDS-ACTION;RSVP=TRUE:mailto:{}",
+                as_param(attendee.name.as_str()),
+                attendee.email
+            ));
+        }
+
+        lines.push(format!("STATUS:{}", self.method.status()));
+        lines.push(String::from("END:VEVENT"));
+
+        lines
+    }
+}
+
+/**
+ * A VCALENDAR of the given events, as published in the calendar feed of a user.
+ * The events stay in UTC; X-WR-TIMEZONE lets the calendar apps show them in
+ * the zone of the user.
+ */
+pub fn as_feed(events: &[CalendarEvent], zone: Tz) -> String {
+    as_calendar(Method::Publish, &[format!("X-WR-TIMEZONE:{}", zone.name())], events)
+}
+
+fn as_calendar(method: Method, headers: &[String], events: &[CalendarEvent]) -> String {
+    let mut lines: Vec<String> = vec![
+        String::from("BEGIN:VCALENDAR"),
+        format!("PRODID:{}", PRODUCT_ID),
+        String::from("VERSION:2.0"),
+        String::from("CALSCALE:GREGORIAN"),
+        format!("METHOD:{}", method.as_str()),
+    ];
+
+    lines.extend_from_slice(headers);
+
+    for event in events {
+        lines.extend(event.lines());
+    }
+
+    lines.push(String::from("END:VCALENDAR"));
+
+    lines.iter().map(|line| fold(line.as_str())).collect::<Vec<String>>().join(CRLF) + CRLF
+}
+
+/**
+ * The METHOD of a generated calendar, as required by the Content-Type of the
+ * text/calendar part of the mail.
+ */
+pub fn method_of(ics: &str) -> &'static str {
+    if ics.contains("METHOD:CANCEL") {
+        return Method::Cancel.as_str();
+    }
+
+    Method::Request.as_str()
+}
+
+fn as_utc(date: &NaiveDateTime) -> String {
+    util::format_time(date)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/**
+ * A parameter value with a separator in it has to be quoted.
+ */
+fn as_param(text: &str) -> String {
+    let text = text.replace('"', "'");
+
+    if text.contains([':', ';', ',']) {
+        return format!("\"{}\"", text);
+    }
+
+    text
+}
+
+/**
+ * Lines longer than 75 octets are folded into continuation lines that begin
+ * with a space, without splitting a multi-byte character.
+ */
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut octets = 0;
+
+    for c in line.chars() {
+        let width = c.len_utf8();
+        if octets + width > MAX_LINE_OCTETS {
+            folded.push_str(CRLF);
+            folded.push(' ');
+            octets = 1;
+        }
+        folded.push(c);
+        octets += width;
+    }
+
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn build_event(method: Method) -> CalendarEvent {
+        CalendarEvent {
+            uid: String::from("session-1"),
+            sequence: 2,
+            summary: String::from("Review, Plan; Act"),
+            description: Some(String::from("Line one\nLine two")),
+            organizer: Some(Attendee {
+                name: String::from("Coach"),
+                email: String::from("coach@krscode.com"),
+            }),
+            attendees: vec![Attendee {
+                name: String::from("Member, One"),
+                email: String::from("member@krscode.com"),
+            }],
+            start: util::as_date("2021-02-20T10:00:00Z").unwrap(),
+            end: util::as_date("2021-02-20T11:00:00Z").unwrap(),
+            method,
+        }
+    }
+
+    #[test]
+    fn should_generate_request() {
+        let ics = build_event(Method::Request).to_ics();
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.contains("METHOD:REQUEST\r\n"));
+        assert!(ics.contains("UID:session-1\r\n"));
+        assert!(ics.contains("SEQUENCE:2\r\n"));
+        assert!(ics.contains("DTSTART:20210220T100000Z\r\n"));
+        assert!(ics.contains("DTEND:20210220T110000Z\r\n"));
+        assert!(ics.contains("SUMMARY:Review\\, Plan\\; Act\r\n"));
+        assert!(ics.contains("DESCRIPTION:Line one\\nLine two\r\n"));
+        assert!(ics.contains("ORGANIZER;CN=Coach:mailto:coach@krscode.com\r\n"));
+        assert!(ics.contains("STATUS:CONFIRMED\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!("REQUEST", method_of(ics.as_str()));
+    }
+
+    #[test]
+    fn should_generate_cancel() {
+        let ics = build_event(Method::Cancel).to_ics();
+
+        assert!(ics.contains("METHOD:CANCEL\r\n"));
+        assert!(ics.contains("STATUS:CANCELLED\r\n"));
+        assert_eq!("CANCEL", method_of(ics.as_str()));
+    }
+
+    #[test]
+    fn should_publish_many_events_in_a_feed() {
+        let ics = as_feed(&[build_event(Method::Publish), build_event(Method::Publish)], Tz::Asia__Kolkata);
+
+        assert!(ics.contains("METHOD:PUBLISH\r\n"));
+        assert!(ics.contains("X-WR-TIMEZONE:Asia/Kolkata\r\n"));
+        assert_eq!(2, ics.matches("BEGIN:VEVENT\r\n").count());
+        assert_eq!(1, ics.matches("BEGIN:VCALENDAR\r\n").count());
+    }
+
+    #[test]
+    fn should_fold_long_lines() {
+        let line = "DESCRIPTION:".to_owned() + &"é".repeat(60);
+        let folded = fold(line.as_str());
+
+        assert!(folded.split(CRLF).all(|part| part.len() <= MAX_LINE_OCTETS));
+        assert_eq!(line, folded.replace("\r\n ", ""));
+    }
+}
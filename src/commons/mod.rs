@@ -1,2 +1,8 @@
+pub mod authz;
 pub mod chassis;
-pub mod util;
+pub mod diagrams;
+pub mod ical;
+pub mod recurrence;
+pub mod scheduler;
+pub mod token;
+pub mod util;
\ No newline at end of file
@@ -0,0 +1,238 @@
+/**
+ * The recurrence rule (RRULE of RFC 5545) of a session series.
+ *
+ * We support a subset of the rule: FREQ (DAILY, WEEKLY or MONTHLY), INTERVAL
+ * and either COUNT or UNTIL, say "FREQ=WEEKLY;COUNT=12" for every week for
+ * 12 weeks. A series should end, so one of COUNT and UNTIL is a must.
+ *
+ * The occurrences are expanded in the time zone of the series, so that
+ * "every Tuesday 10:00" stays at 10:00 across the daylight saving shifts.
+ * As in RFC 5545, a monthly occurrence on a day missing in a month (say
+ * the 31st) is skipped, and the exceptions (EXDATE) are removed only after
+ * the COUNT is reached.
+ */
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime};
+use chrono_tz::Tz;
+
+use crate::commons::util;
+
+pub const MAX_OCCURRENCES: usize = 104;
+
+const UNTIL_PATTERN: &str = "%Y%m%dT%H%M%SZ";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl Frequency {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    pub until: Option<NaiveDateTime>,
+}
+
+impl Recurrence {
+    pub fn parse(rule: &str) -> Result<Recurrence, String> {
+        let rule = rule.trim();
+        let rule = rule.strip_prefix("RRULE:").unwrap_or(rule);
+
+        let mut frequency: Option<Frequency> = None;
+        let mut interval: u32 = 1;
+        let mut count: Option<u32> = None;
+        let mut until: Option<NaiveDateTime> = None;
+
+        for part in rule.split(';').filter(|part| !part.is_empty()) {
+            let mut pair = part.splitn(2, '=');
+            let key = pair.next().unwrap_or_default().to_uppercase();
+            let value = pair.next().ok_or(format!("{} has no value", key))?;
+
+            match key.as_str() {
+                "FREQ" => {
+                    frequency = match value.to_uppercase().as_str() {
+                        "DAILY" => Some(Frequency::Daily),
+                        "WEEKLY" => Some(Frequency::Weekly),
+                        "MONTHLY" => Some(Frequency::Monthly),
+                        _ => return Err(format!("FREQ={} is not supported", value)),
+                    }
+                }
+                "INTERVAL" => interval = value.parse().ok().filter(|n| *n > 0).ok_or("INTERVAL should be a positive number")?,
+                "COUNT" => count = Some(value.parse().ok().filter(|n| *n > 0).ok_or("COUNT should be a positive number")?),
+                "UNTIL" => until = Some(NaiveDateTime::parse_from_str(value, UNTIL_PATTERN).map_err(|_| "UNTIL should be a UTC date time, say 20210601T000000Z")?),
+                _ => return Err(format!("{} is not supported", key)),
+            }
+        }
+
+        let frequency = frequency.ok_or("FREQ is a must")?;
+
+        match (count, until) {
+            (Some(_), Some(_)) => Err(String::from("Either COUNT or UNTIL, not both")),
+            (None, None) => Err(String::from("Either COUNT or UNTIL is a must")),
+            _ => Ok(Recurrence {
+                frequency,
+                interval,
+                count,
+                until,
+            }),
+        }
+    }
+
+    pub fn to_rule(&self) -> String {
+        let mut parts = vec![format!("FREQ={}", self.frequency.as_str())];
+
+        if self.interval > 1 {
+            parts.push(format!("INTERVAL={}", self.interval));
+        }
+
+        if let Some(count) = self.count {
+            parts.push(format!("COUNT={}", count));
+        }
+
+        if let Some(until) = self.until {
+            parts.push(format!("UNTIL={}", until.format(UNTIL_PATTERN)));
+        }
+
+        parts.join(";")
+    }
+
+    /**
+     * The same rule, ending with the given number of occurrences.
+     */
+    pub fn ending_after(&self, count: u32) -> Recurrence {
+        Recurrence {
+            count: Some(count),
+            until: None,
+            ..self.clone()
+        }
+    }
+
+    /**
+     * The start dates (UTC) of the occurrences from the given start date, at
+     * most one beyond MAX_OCCURRENCES so that an overlong rule shows up.
+     */
+    pub fn occurrences(&self, start_date: NaiveDateTime, zone: Tz) -> Vec<NaiveDateTime> {
+        let local_start = util::in_zone(start_date, zone).naive_local();
+        let limit = self.count.map(|count| count as usize).unwrap_or(MAX_OCCURRENCES + 1).min(MAX_OCCURRENCES + 1);
+
+        let mut dates: Vec<NaiveDateTime> = Vec::new();
+        let mut step: i64 = 0;
+
+        // The skipped months (no 31st, say) are bounded, yet guard the loop
+        while dates.len() < limit && step <= (MAX_OCCURRENCES as i64) * 12 {
+            if let Some(local) = self.nth(local_start, step) {
+                let date = util::from_local(local, zone);
+
+                if self.until.is_some_and(|until| date > until) {
+                    break;
+                }
+
+                dates.push(date);
+            }
+
+            step += 1;
+        }
+
+        dates
+    }
+
+    /**
+     * The occurrences, less the exceptions.
+     */
+    pub fn expand(&self, start_date: NaiveDateTime, zone: Tz, exceptions: &[NaiveDateTime]) -> Vec<NaiveDateTime> {
+        self.occurrences(start_date, zone).into_iter().filter(|date| !exceptions.contains(date)).collect()
+    }
+
+    fn nth(&self, local_start: NaiveDateTime, step: i64) -> Option<NaiveDateTime> {
+        let steps = step * self.interval as i64;
+
+        match self.frequency {
+            Frequency::Daily => Some(local_start + Duration::days(steps)),
+            Frequency::Weekly => Some(local_start + Duration::weeks(steps)),
+            Frequency::Monthly => {
+                let months = local_start.month0() as i64 + steps;
+                let year = local_start.year() + (months / 12) as i32;
+                let month = (months % 12) as u32 + 1;
+
+                NaiveDate::from_ymd_opt(year, month, local_start.day()).map(|date| date.and_time(local_start.time()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn should_parse_the_supported_rules() {
+        let recurrence = Recurrence::parse("RRULE:FREQ=WEEKLY;INTERVAL=2;COUNT=6").unwrap();
+
+        assert_eq!(Frequency::Weekly, recurrence.frequency);
+        assert_eq!(2, recurrence.interval);
+        assert_eq!(Some(6), recurrence.count);
+        assert_eq!("FREQ=WEEKLY;INTERVAL=2;COUNT=6", recurrence.to_rule());
+
+        let recurrence = Recurrence::parse("FREQ=DAILY;UNTIL=20210601T000000Z").unwrap();
+        assert_eq!("FREQ=DAILY;UNTIL=20210601T000000Z", recurrence.to_rule());
+    }
+
+    #[test]
+    fn should_reject_the_unsupported_rules() {
+        assert!(Recurrence::parse("FREQ=YEARLY;COUNT=2").is_err());
+        assert!(Recurrence::parse("FREQ=WEEKLY;BYDAY=TU;COUNT=2").is_err());
+        assert!(Recurrence::parse("FREQ=WEEKLY").is_err());
+        assert!(Recurrence::parse("FREQ=WEEKLY;COUNT=2;UNTIL=20210601T000000Z").is_err());
+        assert!(Recurrence::parse("FREQ=WEEKLY;COUNT=0").is_err());
+    }
+
+    #[test]
+    fn should_keep_the_local_time_across_daylight_saving() {
+        let zone = util::as_zone("Europe/Paris").unwrap();
+        let recurrence = Recurrence::parse("FREQ=WEEKLY;COUNT=3").unwrap();
+
+        // Tuesdays at 10:00 in Paris; the clocks move forward on 2021-03-28
+        let dates = recurrence.occurrences(util::as_date("2021-03-23T09:00:00Z").unwrap(), zone);
+
+        assert_eq!(vec![util::as_date("2021-03-23T09:00:00Z").unwrap(), util::as_date("2021-03-30T08:00:00Z").unwrap(), util::as_date("2021-04-06T08:00:00Z").unwrap()], dates);
+    }
+
+    #[test]
+    fn should_skip_the_missing_days_of_a_month() {
+        let recurrence = Recurrence::parse("FREQ=MONTHLY;COUNT=3").unwrap();
+        let dates = recurrence.occurrences(util::as_date("2021-01-31T10:00:00Z").unwrap(), Tz::UTC);
+
+        assert_eq!(vec![util::as_date("2021-01-31T10:00:00Z").unwrap(), util::as_date("2021-03-31T10:00:00Z").unwrap(), util::as_date("2021-05-31T10:00:00Z").unwrap()], dates);
+    }
+
+    #[test]
+    fn should_end_by_until_and_drop_the_exceptions() {
+        let recurrence = Recurrence::parse("FREQ=DAILY;UNTIL=20210305T100000Z").unwrap();
+        let exceptions = vec![util::as_date("2021-03-03T10:00:00Z").unwrap()];
+
+        let dates = recurrence.expand(util::as_date("2021-03-01T10:00:00Z").unwrap(), Tz::UTC, &exceptions);
+
+        assert_eq!(4, dates.len());
+        assert!(!dates.contains(&exceptions[0]));
+    }
+
+    #[test]
+    fn should_stop_beyond_the_max_occurrences() {
+        let recurrence = Recurrence::parse("FREQ=DAILY;COUNT=1000").unwrap();
+
+        assert_eq!(MAX_OCCURRENCES + 1, recurrence.occurrences(util::as_date("2021-03-01T10:00:00Z").unwrap(), Tz::UTC).len());
+    }
+}
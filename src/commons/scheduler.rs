@@ -0,0 +1,434 @@
+/**
+ * Scheduling of a plan graph.
+ *
+ * A plan is a set of activities (tasks) joined by dependencies (links). A
+ * dependency holds the target until the source ends and the lead time of
+ * the link passes. Only the forward links take part in scheduling; the
+ * backward links mark a rework loop, which would otherwise make the graph
+ * cyclic. The durations and the lead times are in hours.
+ *
+ * The critical path method (CPM) runs a forward pass for the earliest dates
+ * and a backward pass, from the end of the plan, for the latest dates. The
+ * slack of an activity is how long it may slip without delaying the end of
+ * the plan; the activities with no slack make the critical path.
+ *
+ * PERT takes three estimates of every activity: the optimistic (a), the most
+ * likely (m) and the pessimistic (b). The expected duration is
+ * (a + 4m + b) / 6 with a variance of ((b - a) / 6)^2. The plan is expected
+ * to take as long as its critical path over the expected durations; taking
+ * the sum of the variances along the path as normal (the central limit
+ * theorem), we get the chance to finish within a given time.
+ */
+use std::collections::{HashMap, VecDeque};
+
+use chrono::{Duration, NaiveDateTime};
+
+pub const CYCLIC_PLAN: &str = "The forward links of the plan form a cycle.";
+pub const UNKNOWN_TASK: &str = "A link of the plan refers to a task outside the plan.";
+
+pub struct Activity {
+    pub id: String,
+    pub duration: i64,
+    /** The activity may not start before this date, even if its sources are over. */
+    pub not_before: Option<NaiveDateTime>,
+}
+
+pub struct Dependency {
+    pub source_id: String,
+    pub target_id: String,
+    pub lead_time: i64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Schedule {
+    pub start_date: NaiveDateTime,
+    pub end_date: NaiveDateTime,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Timing {
+    pub earliest: Schedule,
+    pub latest: Schedule,
+}
+
+impl Timing {
+    pub fn slack(&self) -> Duration {
+        self.latest.start_date - self.earliest.start_date
+    }
+
+    pub fn is_critical(&self) -> bool {
+        self.slack() <= Duration::zero()
+    }
+}
+
+pub struct Estimate {
+    pub id: String,
+    pub optimistic: f64,
+    pub likely: f64,
+    pub pessimistic: f64,
+}
+
+impl Estimate {
+    pub fn expected(&self) -> f64 {
+        (self.optimistic + 4.0 * self.likely + self.pessimistic) / 6.0
+    }
+
+    pub fn variance(&self) -> f64 {
+        ((self.pessimistic - self.optimistic) / 6.0).powi(2)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Forecast {
+    /** The ids of the activities on the critical path, in order. */
+    pub critical_path: Vec<String>,
+    pub expected: f64,
+    pub variance: f64,
+}
+
+/** The z-scores of the percentiles we forecast: 50th, 80th and 95th. */
+pub const PERCENTILES: [(i32, f64); 3] = [(50, 0.0), (80, 0.8416), (95, 1.6449)];
+
+impl Forecast {
+    pub fn standard_deviation(&self) -> f64 {
+        self.variance.sqrt()
+    }
+
+    /** The time within which the plan ends with the given chance (z-score). */
+    pub fn within(&self, z_score: f64) -> f64 {
+        self.expected + z_score * self.standard_deviation()
+    }
+}
+
+/**
+ * The activities in an order where every activity comes after all its
+ * sources (Kahn's algorithm), as indices into the given activities. Among
+ * the ready activities, the earlier given one comes first.
+ */
+pub fn topological_order(activities: &[Activity], dependencies: &[Dependency]) -> Result<Vec<usize>, &'static str> {
+    let (successors, predecessors) = adjacency(activities, dependencies)?;
+    let order = peel(&successors, &predecessors);
+
+    if order.len() < activities.len() {
+        return Err(CYCLIC_PLAN);
+    }
+
+    Ok(order)
+}
+
+/**
+ * The activities on a cycle (or on a path between two cycles), as indices
+ * into the given activities. We peel off the activities with no sources
+ * and then those with no targets; what is left can not be ordered.
+ */
+pub fn cyclic_activities(activities: &[Activity], dependencies: &[Dependency]) -> Result<Vec<usize>, &'static str> {
+    let (successors, predecessors) = adjacency(activities, dependencies)?;
+
+    let from_sources = peel(&successors, &predecessors);
+    let from_targets = peel(&predecessors, &successors);
+
+    Ok((0..activities.len()).filter(|i| !from_sources.contains(i) && !from_targets.contains(i)).collect())
+}
+
+type Adjacency = Vec<Vec<usize>>;
+
+fn adjacency(activities: &[Activity], dependencies: &[Dependency]) -> Result<(Adjacency, Adjacency), &'static str> {
+    let index = index_of(activities);
+
+    let mut successors: Adjacency = vec![Vec::new(); activities.len()];
+    let mut predecessors: Adjacency = vec![Vec::new(); activities.len()];
+
+    for dependency in dependencies {
+        let source = *index.get(dependency.source_id.as_str()).ok_or(UNKNOWN_TASK)?;
+        let target = *index.get(dependency.target_id.as_str()).ok_or(UNKNOWN_TASK)?;
+
+        successors[source].push(target);
+        predecessors[target].push(source);
+    }
+
+    Ok((successors, predecessors))
+}
+
+/**
+ * Kahn's algorithm: the nodes in the order they run out of incoming edges.
+ */
+fn peel(outgoing: &[Vec<usize>], incoming: &[Vec<usize>]) -> Vec<usize> {
+    let mut in_degree: Vec<usize> = incoming.iter().map(|edges| edges.len()).collect();
+
+    let mut ready: VecDeque<usize> = (0..in_degree.len()).filter(|i| in_degree[*i] == 0).collect();
+    let mut order: Vec<usize> = Vec::with_capacity(in_degree.len());
+
+    while let Some(current) = ready.pop_front() {
+        order.push(current);
+
+        for next in &outgoing[current] {
+            in_degree[*next] -= 1;
+            if in_degree[*next] == 0 {
+                ready.push_back(*next);
+            }
+        }
+    }
+
+    order
+}
+
+/**
+ * The earliest schedule of every activity when the plan begins at the given
+ * date: an activity with no sources starts with the plan, and any other
+ * starts once the last of its sources (plus the lead time) is over.
+ */
+pub fn earliest_schedules(activities: &[Activity], dependencies: &[Dependency], start_date: NaiveDateTime) -> Result<Vec<Schedule>, &'static str> {
+    let order = topological_order(activities, dependencies)?;
+
+    Ok(forward_pass(activities, dependencies, &order, start_date))
+}
+
+/**
+ * The earliest and the latest schedules of every activity (CPM). The latest
+ * schedules keep the end of the plan where the earliest schedules put it.
+ */
+pub fn critical_path(activities: &[Activity], dependencies: &[Dependency], start_date: NaiveDateTime) -> Result<Vec<Timing>, &'static str> {
+    let order = topological_order(activities, dependencies)?;
+    let index = index_of(activities);
+
+    let earliest = forward_pass(activities, dependencies, &order, start_date);
+    let plan_end = earliest.iter().map(|schedule| schedule.end_date).max().unwrap_or(start_date);
+
+    let mut latest: Vec<Option<Schedule>> = vec![None; activities.len()];
+
+    for current in order.into_iter().rev() {
+        let activity = &activities[current];
+
+        let mut latest_end = plan_end;
+        for dependency in dependencies.iter().filter(|d| d.source_id == activity.id) {
+            let target = index[dependency.target_id.as_str()];
+
+            if let Some(schedule) = latest[target] {
+                let needed_by = schedule.start_date - Duration::hours(dependency.lead_time);
+                latest_end = latest_end.min(needed_by);
+            }
+        }
+
+        latest[current] = Some(Schedule {
+            start_date: latest_end - Duration::hours(activity.duration),
+            end_date: latest_end,
+        });
+    }
+
+    Ok(earliest
+        .into_iter()
+        .zip(latest.into_iter().flatten())
+        .map(|(earliest, latest)| Timing { earliest, latest })
+        .collect())
+}
+
+/**
+ * The PERT forecast of the plan, in the unit of the estimates (hours). On a
+ * tie between two paths, the one with the wider variance is critical, as it
+ * is the riskier of the two.
+ */
+pub fn pert(estimates: &[Estimate], dependencies: &[Dependency]) -> Result<Forecast, &'static str> {
+    let activities: Vec<Activity> = estimates
+        .iter()
+        .map(|estimate| Activity {
+            id: estimate.id.to_owned(),
+            duration: 0,
+            not_before: None,
+        })
+        .collect();
+
+    let order = topological_order(&activities, dependencies)?;
+    let index = index_of(&activities);
+
+    // The longest (expected) path ending at every activity: its end, variance and the previous activity
+    let mut paths: Vec<(f64, f64, Option<usize>)> = vec![(0.0, 0.0, None); estimates.len()];
+
+    for current in order {
+        let estimate = &estimates[current];
+        let mut start = (0.0, 0.0, None);
+
+        for dependency in dependencies.iter().filter(|d| d.target_id == estimate.id) {
+            let source = index[dependency.source_id.as_str()];
+            let (end, variance, _) = paths[source];
+            let ready_at = end + dependency.lead_time as f64;
+
+            if ready_at > start.0 || (ready_at == start.0 && variance > start.1) {
+                start = (ready_at, variance, Some(source));
+            }
+        }
+
+        paths[current] = (start.0 + estimate.expected(), start.1 + estimate.variance(), start.2);
+    }
+
+    let last = (0..paths.len()).fold(None, |best: Option<usize>, i| match best {
+        Some(b) if paths[b].0 > paths[i].0 || (paths[b].0 == paths[i].0 && paths[b].1 >= paths[i].1) => Some(b),
+        _ => Some(i),
+    });
+
+    let mut critical_path: Vec<String> = Vec::new();
+    let mut current = last;
+    while let Some(i) = current {
+        critical_path.push(estimates[i].id.to_owned());
+        current = paths[i].2;
+    }
+    critical_path.reverse();
+
+    let (expected, variance) = last.map_or((0.0, 0.0), |i| (paths[i].0, paths[i].1));
+
+    Ok(Forecast {
+        critical_path,
+        expected,
+        variance,
+    })
+}
+
+fn forward_pass(activities: &[Activity], dependencies: &[Dependency], order: &[usize], start_date: NaiveDateTime) -> Vec<Schedule> {
+    let index = index_of(activities);
+
+    let mut schedules: Vec<Option<Schedule>> = vec![None; activities.len()];
+
+    for current in order {
+        let activity = &activities[*current];
+
+        let mut earliest_start = activity.not_before.map_or(start_date, |date| date.max(start_date));
+        for dependency in dependencies.iter().filter(|d| d.target_id == activity.id) {
+            let source = index[dependency.source_id.as_str()];
+
+            if let Some(schedule) = schedules[source] {
+                let ready_at = schedule.end_date + Duration::hours(dependency.lead_time);
+                earliest_start = earliest_start.max(ready_at);
+            }
+        }
+
+        schedules[*current] = Some(Schedule {
+            start_date: earliest_start,
+            end_date: earliest_start + Duration::hours(activity.duration),
+        });
+    }
+
+    schedules.into_iter().flatten().collect()
+}
+
+fn index_of(activities: &[Activity]) -> HashMap<&str, usize> {
+    activities.iter().enumerate().map(|(i, activity)| (activity.id.as_str(), i)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::commons::util;
+
+    fn activity(id: &str, duration: i64) -> Activity {
+        Activity {
+            id: String::from(id),
+            duration,
+            not_before: None,
+        }
+    }
+
+    fn dependency(source_id: &str, target_id: &str, lead_time: i64) -> Dependency {
+        Dependency {
+            source_id: String::from(source_id),
+            target_id: String::from(target_id),
+            lead_time,
+        }
+    }
+
+    #[test]
+    fn should_start_after_the_last_source() {
+        let activities = vec![activity("a", 2), activity("b", 5), activity("c", 1)];
+        let dependencies = vec![dependency("a", "c", 0), dependency("b", "c", 3)];
+        let start_date = util::as_date("2021-03-01T09:00:00Z").unwrap();
+
+        let schedules = earliest_schedules(&activities, &dependencies, start_date).unwrap();
+
+        assert_eq!(start_date, schedules[0].start_date);
+        assert_eq!(start_date, schedules[1].start_date);
+        assert_eq!(util::as_date("2021-03-01T17:00:00Z").unwrap(), schedules[2].start_date);
+        assert_eq!(util::as_date("2021-03-01T18:00:00Z").unwrap(), schedules[2].end_date);
+    }
+
+    #[test]
+    fn should_order_sources_first() {
+        let activities = vec![activity("c", 1), activity("b", 1), activity("a", 1)];
+        let dependencies = vec![dependency("a", "b", 0), dependency("b", "c", 0)];
+
+        assert_eq!(vec![2, 1, 0], topological_order(&activities, &dependencies).unwrap());
+    }
+
+    #[test]
+    fn should_reject_cycles_and_strangers() {
+        let activities = vec![activity("a", 1), activity("b", 1)];
+
+        let cycle = vec![dependency("a", "b", 0), dependency("b", "a", 0)];
+        assert_eq!(Err(CYCLIC_PLAN), topological_order(&activities, &cycle));
+
+        let stranger = vec![dependency("a", "z", 0)];
+        assert_eq!(Err(UNKNOWN_TASK), topological_order(&activities, &stranger));
+    }
+
+    #[test]
+    fn should_find_the_activities_on_a_cycle() {
+        // a -> b -> c -> b -> d
+        let activities = vec![activity("a", 1), activity("b", 1), activity("c", 1), activity("d", 1)];
+        let dependencies = vec![dependency("a", "b", 0), dependency("b", "c", 0), dependency("c", "b", 0), dependency("c", "d", 0)];
+
+        assert_eq!(vec![1, 2], cyclic_activities(&activities, &dependencies).unwrap());
+    }
+
+    #[test]
+    fn should_find_the_slack_and_the_critical_path() {
+        // a (2h) -> c (1h) and b (5h) -> c, so a may slip by 3 hours
+        let activities = vec![activity("a", 2), activity("b", 5), activity("c", 1)];
+        let dependencies = vec![dependency("a", "c", 0), dependency("b", "c", 0)];
+        let start_date = util::as_date("2021-03-01T09:00:00Z").unwrap();
+
+        let timings = critical_path(&activities, &dependencies, start_date).unwrap();
+
+        assert_eq!(Duration::hours(3), timings[0].slack());
+        assert!(!timings[0].is_critical());
+        assert!(timings[1].is_critical());
+        assert!(timings[2].is_critical());
+        assert_eq!(util::as_date("2021-03-01T15:00:00Z").unwrap(), timings[2].latest.end_date);
+    }
+
+    #[test]
+    fn should_hold_an_activity_until_its_date() {
+        let mut late = activity("b", 1);
+        late.not_before = Some(util::as_date("2021-03-02T09:00:00Z").unwrap());
+
+        let activities = vec![activity("a", 2), late];
+        let start_date = util::as_date("2021-03-01T09:00:00Z").unwrap();
+
+        let timings = critical_path(&activities, &[], start_date).unwrap();
+
+        assert_eq!(util::as_date("2021-03-02T09:00:00Z").unwrap(), timings[1].earliest.start_date);
+        assert!(timings[1].is_critical());
+        assert_eq!(Duration::hours(22), timings[0].slack());
+    }
+
+    fn estimate(id: &str, optimistic: f64, likely: f64, pessimistic: f64) -> Estimate {
+        Estimate {
+            id: String::from(id),
+            optimistic,
+            likely,
+            pessimistic,
+        }
+    }
+
+    #[test]
+    fn should_forecast_along_the_critical_path() {
+        // a (1, 2, 9) -> c (2, 2, 2) and b (2, 3, 4) -> c
+        let estimates = vec![estimate("a", 1.0, 2.0, 9.0), estimate("b", 2.0, 3.0, 4.0), estimate("c", 2.0, 2.0, 2.0)];
+        let dependencies = vec![dependency("a", "c", 0), dependency("b", "c", 1)];
+
+        let forecast = pert(&estimates, &dependencies).unwrap();
+
+        // a is expected to take 3 hours, b 3 hours plus a lead time of 1
+        assert_eq!(vec!["b", "c"], forecast.critical_path);
+        assert!((forecast.expected - 6.0).abs() < 1e-9);
+        assert!((forecast.variance - 1.0 / 9.0).abs() < 1e-9);
+        assert!(forecast.within(1.6449) > forecast.within(0.0));
+    }
+}
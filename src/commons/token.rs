@@ -0,0 +1,143 @@
+/**
+ * Signed Tokens.
+ *
+ * A token is a compact JWT (header.claims.signature) signed with HMAC-SHA256.
+ * The signing secret is read from the TOKEN_SECRET entry of the .env file.
+ *
+ * The audience (aud) of a token tells its purpose, so that a token issued for
+ * one purpose (say, a login session) can never be replayed for another.
+ */
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use sodiumoxide::crypto::auth::hmacsha256;
+use sodiumoxide::crypto::hash::sha256;
+
+use crate::commons::util;
+
+pub const SESSION: &str = "session";
+pub const VERIFY_EMAIL: &str = "verify_email";
+
+pub const INVALID_TOKEN: &str = "Invalid Token";
+pub const EXPIRED_TOKEN: &str = "The Token has expired";
+
+const HEADER: &str = r#"{"alg":"HS256","typ":"JWT"}"#;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Claims {
+    pub sub: String,
+    pub aud: String,
+    pub exp: i64,
+}
+
+pub fn sign(subject: &str, audience: &str, validity: Duration) -> String {
+    let claims = Claims {
+        sub: subject.to_owned(),
+        aud: audience.to_owned(),
+        exp: (util::now() + validity).timestamp(),
+    };
+
+    let header = encode(HEADER.as_bytes());
+    let payload = encode(serde_json::to_string(&claims).unwrap().as_bytes());
+    let content = format!("{}.{}", header, payload);
+
+    let tag = hmacsha256::authenticate(content.as_bytes(), &secret_key());
+
+    format!("{}.{}", content, encode(tag.as_ref()))
+}
+
+/**
+ * The signature is verified before we even look at the claims.
+ */
+pub fn verify(token: &str, audience: &str) -> Result<Claims, &'static str> {
+    let parts: Vec<&str> = token.trim().split('.').collect();
+    if parts.len() != 3 {
+        return Err(INVALID_TOKEN);
+    }
+
+    let signature = decode(parts[2])?;
+    let tag = hmacsha256::Tag::from_slice(&signature).ok_or(INVALID_TOKEN)?;
+
+    let content = format!("{}.{}", parts[0], parts[1]);
+    if !hmacsha256::verify(&tag, content.as_bytes(), &secret_key()) {
+        return Err(INVALID_TOKEN);
+    }
+
+    let payload = decode(parts[1])?;
+    let claims: Claims = serde_json::from_slice(&payload).map_err(|_| INVALID_TOKEN)?;
+
+    if claims.aud != audience {
+        return Err(INVALID_TOKEN);
+    }
+
+    if claims.exp < util::now().timestamp() {
+        return Err(EXPIRED_TOKEN);
+    }
+
+    Ok(claims)
+}
+
+/**
+ * The HMAC key is the SHA-256 digest of the configured secret, which gives
+ * us the exact key length irrespective of the length of the secret.
+ */
+fn secret_key() -> hmacsha256::Key {
+    sodiumoxide::init().unwrap();
+
+    let secret = dotenv::var("TOKEN_SECRET").expect("The TOKEN_SECRET should be set");
+    let digest = sha256::hash(secret.as_bytes());
+
+    hmacsha256::Key(digest.0)
+}
+
+fn encode(bytes: &[u8]) -> String {
+    base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+fn decode(text: &str) -> Result<Vec<u8>, &'static str> {
+    base64::decode_config(text, base64::URL_SAFE_NO_PAD).map_err(|_| INVALID_TOKEN)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn init() {
+        std::env::set_var("TOKEN_SECRET", "a-secret-for-testing");
+    }
+
+    #[test]
+    fn should_verify_signed_token() {
+        init();
+        let token = sign("user-1", SESSION, Duration::hours(1));
+
+        let claims = verify(token.as_str(), SESSION).unwrap();
+        assert_eq!(claims.sub, "user-1");
+    }
+
+    #[test]
+    fn should_reject_tampered_token() {
+        init();
+        let token = sign("user-1", SESSION, Duration::hours(1));
+        let forged_claims = encode(r#"{"sub":"user-2","aud":"session","exp":9999999999}"#.as_bytes());
+
+        let parts: Vec<&str> = token.split('.').collect();
+        let forged = format!("{}.{}.{}", parts[0], forged_claims, parts[2]);
+
+        assert_eq!(verify(forged.as_str(), SESSION).unwrap_err(), INVALID_TOKEN);
+    }
+
+    #[test]
+    fn should_reject_other_audience() {
+        init();
+        let token = sign("user-1", "other", Duration::hours(1));
+        assert_eq!(verify(token.as_str(), SESSION).unwrap_err(), INVALID_TOKEN);
+    }
+
+    #[test]
+    fn should_reject_expired_token() {
+        init();
+        let token = sign("user-1", SESSION, Duration::hours(-1));
+        assert_eq!(verify(token.as_str(), SESSION).unwrap_err(), EXPIRED_TOKEN);
+    }
+}
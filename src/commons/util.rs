@@ -1,45 +1,102 @@
 use chrono::format::strftime::StrftimeItems;
-use chrono::{Duration, NaiveDate, NaiveDateTime, Timelike, Utc};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+use sodiumoxide::crypto::hash::sha256;
 use sodiumoxide::crypto::pwhash::argon2id13;
+use sodiumoxide::randombytes::randombytes;
 use std::ops::Sub;
 use uuid::Uuid;
 
-const DATE_TIME_PATTERN: &str = "%Y-%m-%dT%H:%M:%SZ";
 const DATE_PATTERN: &str = "%Y-%m-%d";
 
 pub const BAD_DATE: &str = "Date format error";
+pub const BAD_TIME_ZONE: &str = "Unknown time zone";
+
+pub const UTC_ZONE: &str = "UTC";
 
 pub const MEMBER: &str = "member";
 pub const COACH: &str = "coach";
+pub const ADMIN: &str = "admin";
 
 pub const MONO: &str = "mono";
 pub const MULTI: &str = "multi";
 
-pub fn as_date(date_str: &str) -> NaiveDateTime {
-    let given_date = NaiveDateTime::parse_from_str(date_str, DATE_TIME_PATTERN).unwrap_or_else(|_| Utc::now().naive_utc());
-    strip_seconds(given_date)
+/**
+ * Parses an RFC 3339 date time, say 2021-02-20T10:00:00Z or
+ * 2021-02-20T15:30:00+05:30, into the UTC date time we persist.
+ */
+pub fn as_date(date_str: &str) -> Result<NaiveDateTime, &'static str> {
+    match DateTime::parse_from_rfc3339(date_str.trim()) {
+        Ok(date) => Ok(strip_seconds(date.naive_utc())),
+        Err(_) => Err(BAD_DATE),
+    }
 }
 
-pub fn as_start_date(date_str: &str) -> Result<NaiveDateTime, String> {
+/**
+ * The beginning (00:00) of the given day (YYYY-MM-DD) in the given zone, in UTC.
+ */
+pub fn as_start_date(date_str: &str, zone: Tz) -> Result<NaiveDateTime, String> {
     let date = NaiveDate::parse_from_str(date_str, DATE_PATTERN);
 
     if let Ok(nd) = date {
-        return Ok(nd.and_hms(0,0,0));
+        return Ok(from_local(nd.and_hms(0, 0, 0), zone));
     }
 
     Err(BAD_DATE.to_owned())
 }
 
-pub fn as_end_date(date_str: &str) -> Result<NaiveDateTime, String> {
+/**
+ * The end (23:59) of the given day (YYYY-MM-DD) in the given zone, in UTC.
+ */
+pub fn as_end_date(date_str: &str, zone: Tz) -> Result<NaiveDateTime, String> {
     let date = NaiveDate::parse_from_str(date_str, DATE_PATTERN);
 
     if let Ok(nd) = date {
-        return Ok(nd.and_hms(23, 59, 0));
+        return Ok(from_local(nd.and_hms(23, 59, 0), zone));
     }
 
     Err(BAD_DATE.to_owned())
 }
 
+/**
+ * A local time that falls in the gap of a daylight saving shift does not
+ * exist; we take the moment the clocks moved on, an hour later.
+ */
+pub fn from_local(local: NaiveDateTime, zone: Tz) -> NaiveDateTime {
+    let mapped = zone.from_local_datetime(&local).earliest().or_else(|| zone.from_local_datetime(&(local + Duration::hours(1))).earliest());
+
+    match mapped {
+        Some(date) => date.naive_utc(),
+        None => local,
+    }
+}
+
+/**
+ * An IANA time zone, say Asia/Kolkata or Europe/Paris.
+ */
+pub fn as_zone(zone_name: &str) -> Result<Tz, String> {
+    zone_name.trim().parse::<Tz>().map_err(|_| BAD_TIME_ZONE.to_owned())
+}
+
+pub fn is_valid_zone(zone_name: &str) -> bool {
+    as_zone(zone_name).is_ok()
+}
+
+/**
+ * The persisted (UTC) date time as seen in the given zone.
+ */
+pub fn in_zone(given_date: NaiveDateTime, zone: Tz) -> DateTime<Tz> {
+    zone.from_utc_datetime(&given_date)
+}
+
+/**
+ * The dates go out of the API with an explicit offset, so that a client
+ * never mistakes a UTC date time for a local one.
+ */
+pub fn with_offset(given_date: NaiveDateTime) -> DateTime<Utc> {
+    DateTime::<Utc>::from_utc(given_date, Utc)
+}
+
 pub fn format_time(given_time: &NaiveDateTime) -> String {
     let fmt = StrftimeItems::new("%Y%m%dT%H%M%SZ");
     given_time.format_with_items(fmt.clone()).to_string()
@@ -51,10 +108,7 @@ pub fn strip_seconds(given_date: NaiveDateTime) -> NaiveDateTime {
 }
 
 pub fn is_valid_date(date_str: &str) -> bool {
-    let parse_from_str = NaiveDateTime::parse_from_str;
-    let given_date = parse_from_str(date_str, DATE_TIME_PATTERN);
-
-    given_date.is_ok()
+    as_date(date_str).is_ok()
 }
 
 pub fn now() -> NaiveDateTime {
@@ -118,7 +172,28 @@ pub fn is_equal(hashed_password: &str, given_password: &str) -> bool {
     status
 }
 
+/**
+ * A random, url safe token to be mailed out; only its digest is persisted.
+ */
+pub fn random_token() -> String {
+    sodiumoxide::init().unwrap();
+
+    to_hex(&randombytes(32))
+}
+
+/**
+ * The SHA-256 digest (in hex) of a token. Unlike the password hash, the digest
+ * is deterministic, so that we can look a token up by its digest.
+ */
+pub fn digest(token: &str) -> String {
+    sodiumoxide::init().unwrap();
 
+    to_hex(&sha256::hash(token.as_bytes()).0)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
 
 pub fn find_diff(current: Vec<String>, given: Vec<String>) -> Vec<String> {
     let mut diff: Vec<String> = Vec::new();
@@ -143,13 +218,39 @@ mod tests {
     #[test]
     fn should_be_in_past() {
         let start_time = "2020-08-27T06:53:09Z";
-        assert_eq!(true, is_in_past(as_date(start_time)));
+        assert_eq!(true, is_in_past(as_date(start_time).unwrap()));
     }
 
     #[test]
     fn should_handle_pure_date() {
         let start_date = "2020-08-25T10:45:07Z";
-        println!("{:?}", as_end_date(start_date));
+        println!("{:?}", as_end_date(start_date, Tz::UTC));
+    }
+
+    #[test]
+    fn should_parse_dates_with_offsets() {
+        assert_eq!(as_date("2021-02-20T10:00:00Z").unwrap(), as_date("2021-02-20T15:30:00+05:30").unwrap());
+        assert!(as_date("2021-02-20 10:00").is_err());
+        assert!(!is_valid_date("not a date"));
+    }
+
+    #[test]
+    fn should_interpret_days_in_the_zone() {
+        let zone = as_zone("Asia/Kolkata").unwrap();
+
+        assert_eq!(as_date("2021-02-19T18:30:00Z").unwrap(), as_start_date("2021-02-20", zone).unwrap());
+        assert_eq!(as_date("2021-02-20T18:29:00Z").unwrap(), as_end_date("2021-02-20", zone).unwrap());
+        assert_eq!("2021-02-20T16:00:00+05:30", in_zone(as_date("2021-02-20T10:30:00Z").unwrap(), zone).to_rfc3339());
+        assert!(!is_valid_zone("Mars/Olympus"));
+    }
+
+    #[test]
+    fn should_skip_the_daylight_saving_gap() {
+        // The clocks in Paris moved from 02:00 to 03:00 on 2021-03-28
+        let zone = as_zone("Europe/Paris").unwrap();
+        let local = NaiveDate::from_ymd(2021, 3, 28).and_hms(2, 30, 0);
+
+        assert_eq!(as_date("2021-03-28T01:30:00Z").unwrap(), from_local(local, zone));
     }
 
     #[test]
@@ -167,6 +268,15 @@ mod tests {
         println!("{}", hash("harini"));
     }
 
+    #[test]
+    fn should_digest_token_deterministically() {
+        let token = random_token();
+
+        assert_eq!(64, token.len());
+        assert_eq!(digest(token.as_str()), digest(token.as_str()));
+        assert_ne!(token, digest(token.as_str()));
+    }
+
     #[test]
     fn find_diff_between_old_and_new() {
         let old = vec![String::from("1"), String::from("2"), String::from("3"), String::from("4")];
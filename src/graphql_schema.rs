@@ -1,74 +1,282 @@
+use diesel::MysqlConnection;
 use juniper::{FieldResult, RootNode};
 
+use crate::auth_manager::{issue_session_token, Caller, UNAUTHENTICATED};
 use crate::db_manager::MySqlConnectionPool;
 
-use crate::models::abstract_tasks::{AbstractTask, AbstractTaskCriteria, NewAbstractTaskRequest};
+use crate::models::availabilities::{Availability, Blackout, CandidateSlot, NewBlackoutRequest, WorkingHour, WorkingHoursRequest};
+use crate::models::abstract_tasks::{AbstractTask, NewAbstractTaskRequest};
+use crate::models::coach_applications::{CoachApplication, ReviewApplicationRequest};
 use crate::models::coach_members::{get_coach_members, CoachCriteria, MemberRow};
 use crate::models::conferences::{Conference, MemberRequest, NewConferenceRequest};
-use crate::models::correspondences::Mailable;
 use crate::models::discussion_queue::PendingFeed;
 use crate::models::discussions::{Discussion, DiscussionCriteria, NewDiscussionRequest};
 use crate::models::enrollments::{Enrollment, EnrollmentCriteria, ManagedEnrollmentRequest, NewEnrollmentRequest, PlanCriteria};
-use crate::models::master_plans::{MasterPlan, MasterPlanCriteria, NewMasterPlanRequest, UpdateMasterPlanRequest};
+use crate::models::master_plan_revisions::{MasterPlanRevision, RestoreRevisionRequest};
+use crate::models::master_plan_shares::{MasterPlanShare, SharePlanRequest};
+use crate::models::master_plans::{ApplyMasterPlanRequest, ClonePlanRequest, MasterPlan, NewMasterPlanRequest, UpdateMasterPlanRequest};
 use crate::models::master_tasks::{MasterTask, MasterTaskCriteria, NewMasterTaskRequest, UpdateMasterTaskRequest};
 use crate::models::notes::{NewNoteRequest, Note, NoteCriteria};
 use crate::models::objectives::{NewObjectiveRequest, Objective, UpdateObjectiveRequest};
 use crate::models::observations::{NewObservationRequest, Observation, UpdateObservationRequest};
 use crate::models::options::{Constraint, NewOptionRequest, UpdateOptionRequest};
+use crate::models::password_resets::CompletePasswordResetRequest;
+use crate::models::program_plans::{AttachPlanRequest, ProgramPlan, ProgramPlanRequest};
+use crate::models::plan_documents::{ImportPlanRequest, PlanDocument};
 use crate::models::programs::{AssociateCoachRequest, ChangeProgramStateRequest, NewProgramRequest, Program, ProgramCoach};
-use crate::models::sessions::{ChangeSessionStateRequest, NewSessionRequest, Session};
+use crate::models::schedules::{EnrollmentSchedule, PlanForecast};
+use crate::models::session_series::{CancelOccurrenceRequest, NewSessionSeriesRequest, SeriesRow, UpdateSeriesRequest};
+use crate::models::session_slots::SessionSlot;
+use crate::models::sessions::{AcceptSessionRequest, ChangeSessionStateRequest, DeclineSessionRequest, MemberSessionRequest, NewSessionRequest, RescheduleRequest, Session};
+use crate::models::task_links::{TaskLink, UpdateTaskLinksRequest};
+use crate::models::task_moves::{PropagateDelaysRequest, TaskMove};
 use crate::models::tasks::{ChangeCoachTaskStateRequest, ChangeMemberTaskStateRequest, NewTaskRequest, Task, UpdateClosingNoteRequest, UpdateResponseRequest, UpdateTaskRequest};
 use crate::models::user_artifacts::{get_boards, get_enrollment_notes, BoardRow, NoteRow};
 use crate::models::user_events::{get_events,get_plan_events, get_to_dos, EventCriteria, EventRow, PlanRow, ToDo};
 use crate::models::session_users::{get_people,SessionCriteria, SessionPeople};
 use crate::models::user_programs::{get_programs, ProgramCriteria, ProgramRow};
-use crate::models::users::{LoginRequest, Registration, ResetPasswordRequest, User, UserCriteria};
+use crate::models::users::{Login, LoginRequest, Registration, ResetPasswordRequest, TimeZoneRequest, User, UserCriteria};
 
 use crate::services::abstract_tasks::{create_abstract_task, get_abstract_tasks};
-use crate::services::conferences::{create_conference, manage_members};
-use crate::services::correspondences::sendable_mails;
+use crate::services::availabilities::{add_blackout, find_available_slots, get_availability, remove_blackout, save_working_hours};
+use crate::services::calendar_feeds::{issue_feed, revoke_feed};
+use crate::services::coaches::{apply_as_coach, get_pending_applications, review_application};
+use crate::services::conferences::{create_conference, manage_members, reschedule_conference};
 use crate::services::discussions::{create_new_discussion, get_discussions, get_pending_discussions};
 use crate::services::enrollments::{create_managed_enrollment, create_new_enrollment, get_active_enrollments};
-use crate::services::master_plans::{create_master_plan, get_master_plans, update_master_plan};
+use crate::services::master_plan_shares::{get_plan_shares, get_shared_master_plans, share_master_plan, unshare_master_plan};
+use crate::services::master_plans::{apply_master_plan, clone_master_plan, create_master_plan, export_master_plan, import_master_plan, get_plan_forecast, get_master_plans, get_revisions, restore_revision, update_master_plan};
 use crate::services::master_tasks::{create_master_task, get_master_tasks, update_master_task};
 use crate::services::notes::{create_new_note, get_notes};
 use crate::services::objectives::{create_objective, get_objectives, update_objective};
 use crate::services::observations::{create_observation, get_observations, update_observation};
 use crate::services::options::{create_option, get_options, update_option};
+use crate::services::program_plans::{attach_plan, detach_plan, get_program_plans, make_default};
 use crate::services::programs::{associate_coach, change_program_state, create_new_program, get_peer_coaches};
-use crate::services::sessions::{change_session_state, create_session, find};
+use crate::services::schedules::get_enrollment_schedule;
+use crate::services::session_series::{cancel_occurrence, create_series, get_series, update_following};
+use crate::services::sessions::{accept_session_request, change_session_state, create_session, decline_session_request, find, get_session_slots, request_session, reschedule_session};
+use crate::services::task_links::{get_task_links, save_task_links};
+use crate::services::task_moves::{get_task_moves, propagate_delays};
 use crate::services::tasks::{change_coach_task_state, change_member_task_state, create_task, get_tasks, update_closing_notes, update_response, update_task};
-use crate::services::users::{authenticate, register, reset_password};
+use crate::services::users::{authenticate, complete_password_reset, register, request_password_reset, reset_password, update_time_zone, verify_email};
 
-use crate::commons::chassis::{mutation_error, query_error, service_error, MutationResult, QueryError, QueryResult};
+use crate::commons::authz::{authorize, Rule};
+use crate::commons::chassis::{access_denied, authentication_error, authorization_error, mutation_error, query_error, service_error, Denial, MutationResult, QueryError, QueryResult};
 
 #[derive(Clone)]
 pub struct DBContext {
     pub db: MySqlConnectionPool,
+    pub caller: Option<Caller>,
 }
 
+impl DBContext {
+    pub fn new(db: MySqlConnectionPool) -> DBContext {
+        DBContext { db, caller: None }
+    }
+
+    /**
+     * The context is shared across requests, so each request gets its own copy
+     * that carries the caller resolved by the Authentication middleware.
+     */
+    pub fn for_caller(&self, caller: Option<Caller>) -> DBContext {
+        DBContext {
+            db: self.db.clone(),
+            caller,
+        }
+    }
+
+    pub fn caller_id(&self) -> Result<&str, &'static str> {
+        match &self.caller {
+            Some(caller) => Ok(caller.user_id.as_str()),
+            None => Err(UNAUTHENTICATED),
+        }
+    }
+}
+
+/**
+ * The caller, provided the authorization policy lets them act under the rule.
+ */
+fn authorized<'a>(context: &'a DBContext, connection: &MysqlConnection, rule: Rule) -> Result<&'a str, Denial> {
+    let the_user_id = context.caller_id().map_err(Denial::Unauthenticated)?;
+
+    authorize(connection, the_user_id, rule).map_err(Denial::Unauthorized)?;
+
+    Ok(the_user_id)
+}
 
 pub struct QueryRoot;
 
 #[juniper::object(Context = DBContext,description="Graph Query Root")]
 impl QueryRoot {
     #[graphql(description = "Authenticate a user with email and password")]
-    fn authenticate(context: &DBContext, request: LoginRequest) -> FieldResult<User> {
+    fn authenticate(context: &DBContext, request: LoginRequest) -> FieldResult<Login> {
         let connection = context.db.get().unwrap();
         let user = authenticate(&connection, request)?;
-        Ok(user)
+        let token = issue_session_token(user.id.as_str());
+        Ok(Login { user, token })
     }
 
-    #[graphql(description = "Return the basic information of a user")]
+    #[graphql(description = "Return the basic information of the caller, or of a user the caller shares an enrollment or a session with")]
     fn get_user(context: &DBContext, criteria: UserCriteria) -> FieldResult<User> {
+        let the_user_id = context.caller_id()?;
+        let wanted_id = criteria.id.as_deref().unwrap_or(the_user_id);
+
         let connection = context.db.get().unwrap();
-        let user = crate::services::users::find(&connection, &criteria.id)?;
+        authorized(context, &connection, Rule::MeetsUser(wanted_id))?;
+
+        let user = crate::services::users::find(&connection, wanted_id)?;
         Ok(user)
     }
 
-    fn get_pending_discussions(context: &DBContext, criteria: UserCriteria) -> QueryResult<Vec<PendingFeed>> {
+    fn get_pending_discussions(context: &DBContext) -> QueryResult<Vec<PendingFeed>> {
+        let the_user_id = match context.caller_id() {
+            Ok(id) => id,
+            Err(e) => return access_denied(e),
+        };
+        let connection = context.db.get().unwrap();
+        let result = get_pending_discussions(&connection, the_user_id);
+
+        match result {
+            Ok(value) => QueryResult(Ok(value)),
+            Err(e) => query_error(e),
+        }
+    }
+
+    #[graphql(description = "Get the applications to become a Coach, waiting for a review by an admin")]
+    fn get_pending_applications(context: &DBContext) -> QueryResult<Vec<CoachApplication>> {
+        let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::Administers) {
+            return denial.into();
+        }
+
+        let result = get_pending_applications(&connection);
+
+        match result {
+            Ok(value) => QueryResult(Ok(value)),
+            Err(e) => query_error(e),
+        }
+    }
+
+    #[graphql(description = "Get the weekly working hours and the blackouts of a coach")]
+    fn get_coach_availability(context: &DBContext, coach_id: String) -> QueryResult<Availability> {
+        let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::SeesCoach(coach_id.as_str())) {
+            return denial.into();
+        }
+
+        let result = get_availability(&connection, coach_id.as_str());
+
+        match result {
+            Ok(value) => QueryResult(Ok(value)),
+            Err(e) => query_error(e),
+        }
+    }
+
+    #[graphql(description = "Find the free slots common to a coach and a member, between the dates (YYYY-MM-DD) of the caller")]
+    fn find_available_slots(context: &DBContext, coach_id: String, member_id: String, duration_minutes: i32, from: String, to: String) -> QueryResult<Vec<CandidateSlot>> {
+        let connection = context.db.get().unwrap();
+        let the_user_id = match authorized(context, &connection, Rule::PairsCoachMember(coach_id.as_str(), member_id.as_str())) {
+            Ok(id) => id,
+            Err(denial) => return denial.into(),
+        };
+
+        let zone = match crate::services::users::find(&connection, the_user_id) {
+            Ok(user) => user.zone(),
+            Err(e) => return access_denied(e),
+        };
+
+        let result = find_available_slots(&connection, coach_id.as_str(), member_id.as_str(), duration_minutes, from.as_str(), to.as_str(), zone);
+
+        match result {
+            Ok(value) => QueryResult(Ok(value)),
+            Err(e) => QueryResult(Err(QueryError::from(e))),
+        }
+    }
+
+    #[graphql(description = "Get the PERT forecast (in hours) of a master plan")]
+    fn get_plan_forecast(context: &DBContext, master_plan_id: String) -> QueryResult<PlanForecast> {
+        let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::UsesMasterPlan(master_plan_id.as_str())) {
+            return denial.into();
+        }
+
+        let result = get_plan_forecast(&connection, master_plan_id.as_str());
+
+        match result {
+            Ok(value) => QueryResult(Ok(value)),
+            Err(e) => QueryResult(Err(QueryError::from(e))),
+        }
+    }
+
+    #[graphql(description = "Get the saved revisions of a master plan, latest first")]
+    fn get_master_plan_revisions(context: &DBContext, master_plan_id: String) -> QueryResult<Vec<MasterPlanRevision>> {
+        let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::OwnsMasterPlan(master_plan_id.as_str())) {
+            return denial.into();
+        }
+
+        let result = get_revisions(&connection, master_plan_id.as_str());
+
+        match result {
+            Ok(value) => QueryResult(Ok(value)),
+            Err(e) => query_error(e),
+        }
+    }
+
+    #[graphql(description = "Get the critical path schedule of the tasks of an enrollment")]
+    fn get_enrollment_schedule(context: &DBContext, enrollment_id: String) -> QueryResult<EnrollmentSchedule> {
+        let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::CoachesEnrollment(enrollment_id.as_str())) {
+            return denial.into();
+        }
+
+        let result = get_enrollment_schedule(&connection, enrollment_id.as_str());
+
+        match result {
+            Ok(value) => QueryResult(Ok(value)),
+            Err(e) => QueryResult(Err(QueryError::from(e))),
+        }
+    }
+
+    #[graphql(description = "Get the tasks of an enrollment moved as the earlier tasks slipped, latest first")]
+    fn get_task_moves(context: &DBContext, enrollment_id: String) -> QueryResult<Vec<TaskMove>> {
+        let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::CoachesEnrollment(enrollment_id.as_str())) {
+            return denial.into();
+        }
+
+        let result = get_task_moves(&connection, enrollment_id.as_str());
+
+        match result {
+            Ok(value) => QueryResult(Ok(value)),
+            Err(e) => query_error(e),
+        }
+    }
+
+    #[graphql(description = "Get the series a session belongs to, along with its sessions")]
+    fn get_session_series(context: &DBContext, session_id: String) -> QueryResult<SeriesRow> {
+        let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::AttendsSession(session_id.as_str())) {
+            return denial.into();
+        }
+
+        let result = get_series(&connection, session_id.as_str());
+
+        match result {
+            Ok(value) => QueryResult(Ok(value)),
+            Err(e) => access_denied(e),
+        }
+    }
+
+    #[graphql(description = "Get the slots offered by the member for a requested session")]
+    fn get_session_slots(context: &DBContext, session_id: String) -> QueryResult<Vec<SessionSlot>> {
         let connection = context.db.get().unwrap();
-        let result = get_pending_discussions(&connection, &criteria);
+        if let Err(denial) = authorized(context, &connection, Rule::AttendsSession(session_id.as_str())) {
+            return denial.into();
+        }
+
+        let result = get_session_slots(&connection, session_id.as_str());
 
         match result {
             Ok(value) => QueryResult(Ok(value)),
@@ -79,7 +287,23 @@ impl QueryRoot {
     #[graphql(description = "Get Programs of a Coach Or Member Or Latest 10.")]
     fn get_programs(context: &DBContext, criteria: ProgramCriteria) -> QueryResult<Vec<ProgramRow>> {
         let connection = context.db.get().unwrap();
-        let result = get_programs(&connection, &criteria);
+        let the_user_id = context.caller_id().unwrap_or_default();
+        let result = get_programs(&connection, the_user_id, &criteria);
+
+        match result {
+            Ok(value) => QueryResult(Ok(value)),
+            Err(e) => query_error(e),
+        }
+    }
+
+    #[graphql(description = "Get the master plans offered in a program")]
+    fn get_program_plans(context: &DBContext, program_id: String) -> QueryResult<Vec<ProgramPlan>> {
+        let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::CoachesProgram(program_id.as_str())) {
+            return denial.into();
+        }
+
+        let result = get_program_plans(&connection, program_id.as_str());
 
         match result {
             Ok(value) => QueryResult(Ok(value)),
@@ -90,6 +314,10 @@ impl QueryRoot {
     #[graphql(description = "Get the list of coaches associated with a Program through its parent program.")]
     fn get_program_coaches(context: &DBContext, program_id: String) -> QueryResult<Vec<ProgramCoach>> {
         let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::OwnsProgram(program_id.as_str())) {
+            return denial.into();
+        }
+
         let result = get_peer_coaches(&connection, program_id.as_str());
 
         match result {
@@ -99,9 +327,13 @@ impl QueryRoot {
     }
 
     #[graphql(description = "Get The List of Abstract Tasks of a Coach")]
-    fn get_abstract_tasks(context: &DBContext, criteria: AbstractTaskCriteria) -> QueryResult<Vec<AbstractTask>> {
+    fn get_abstract_tasks(context: &DBContext) -> QueryResult<Vec<AbstractTask>> {
+        let the_user_id = match context.caller_id() {
+            Ok(id) => id,
+            Err(e) => return access_denied(e),
+        };
         let connection = context.db.get().unwrap();
-        let result = get_abstract_tasks(&connection, &criteria);
+        let result = get_abstract_tasks(&connection, the_user_id);
 
         match result {
             Ok(value) => QueryResult(Ok(value)),
@@ -110,9 +342,58 @@ impl QueryRoot {
     }
 
     #[graphql(description = "Get The List of Master Plans of a Coach")]
-    fn get_master_plans(context: &DBContext, criteria: MasterPlanCriteria) -> QueryResult<Vec<MasterPlan>> {
+    fn get_master_plans(context: &DBContext) -> QueryResult<Vec<MasterPlan>> {
+        let the_user_id = match context.caller_id() {
+            Ok(id) => id,
+            Err(e) => return access_denied(e),
+        };
+        let connection = context.db.get().unwrap();
+        let result = get_master_plans(&connection, the_user_id);
+
+        match result {
+            Ok(value) => QueryResult(Ok(value)),
+            Err(e) => query_error(e),
+        }
+    }
+
+    #[graphql(description = "Export a master plan as a portable JSON document")]
+    fn export_master_plan(context: &DBContext, master_plan_id: String) -> QueryResult<String> {
+        let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::UsesMasterPlan(master_plan_id.as_str())) {
+            return denial.into();
+        }
+
+        let result = export_master_plan(&connection, master_plan_id.as_str());
+
+        match result {
+            Ok(value) => QueryResult(Ok(value)),
+            Err(e) => QueryResult(Err(QueryError::from(String::from(e)))),
+        }
+    }
+
+    #[graphql(description = "Get the master plans other coaches shared with the programs of the coach")]
+    fn get_shared_master_plans(context: &DBContext) -> QueryResult<Vec<MasterPlan>> {
+        let the_user_id = match context.caller_id() {
+            Ok(id) => id,
+            Err(e) => return access_denied(e),
+        };
+        let connection = context.db.get().unwrap();
+        let result = get_shared_master_plans(&connection, the_user_id);
+
+        match result {
+            Ok(value) => QueryResult(Ok(value)),
+            Err(e) => query_error(e),
+        }
+    }
+
+    #[graphql(description = "Get the parent programs a master plan is shared with")]
+    fn get_master_plan_shares(context: &DBContext, master_plan_id: String) -> QueryResult<Vec<MasterPlanShare>> {
         let connection = context.db.get().unwrap();
-        let result = get_master_plans(&connection, &criteria);
+        if let Err(denial) = authorized(context, &connection, Rule::OwnsMasterPlan(master_plan_id.as_str())) {
+            return denial.into();
+        }
+
+        let result = get_plan_shares(&connection, master_plan_id.as_str());
 
         match result {
             Ok(value) => QueryResult(Ok(value)),
@@ -123,6 +404,10 @@ impl QueryRoot {
     #[graphql(description = "Get the list of tasks for an Enrollment")]
     fn get_master_tasks(context: &DBContext, criteria: MasterTaskCriteria) -> QueryResult<Vec<MasterTask>> {
         let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::UsesMasterPlan(criteria.master_plan_id.as_str())) {
+            return denial.into();
+        }
+
         let result = get_master_tasks(&connection, criteria);
 
         match result {
@@ -132,15 +417,28 @@ impl QueryRoot {
     }
 
     #[graphql(description = "Get the list of members enrolled into a Program")]
-    fn get_enrollments(context: &DBContext, criteria: EnrollmentCriteria) -> Vec<User> {
+    fn get_enrollments(context: &DBContext, criteria: EnrollmentCriteria) -> QueryResult<Vec<User>> {
         let connection = context.db.get().unwrap();
-        get_active_enrollments(&connection, criteria).unwrap()
+        if let Err(denial) = authorized(context, &connection, Rule::CoachesProgram(criteria.program_id.as_str())) {
+            return denial.into();
+        }
+
+        let result = get_active_enrollments(&connection, criteria);
+
+        match result {
+            Ok(value) => QueryResult(Ok(value)),
+            Err(e) => access_denied(e),
+        }
     }
 
     #[graphql(description = "Get the list of members enrolled into Programs offered by a Coach")]
     fn get_coach_members(context: &DBContext, criteria: CoachCriteria) -> QueryResult<Vec<MemberRow>> {
+        let the_user_id = match context.caller_id() {
+            Ok(id) => id,
+            Err(e) => return access_denied(e),
+        };
         let connection = context.db.get().unwrap();
-        let result = get_coach_members(&connection, criteria);
+        let result = get_coach_members(&connection, the_user_id, criteria);
 
         match result {
             Ok(value) => QueryResult(Ok(value)),
@@ -150,8 +448,12 @@ impl QueryRoot {
 
     #[graphql(description = "Get the Session Events for a User, during a period")]
     fn get_events(context: &DBContext, criteria: EventCriteria) -> QueryResult<Vec<EventRow>> {
+        let the_user_id = match context.caller_id() {
+            Ok(id) => id,
+            Err(e) => return access_denied(e),
+        };
         let connection = context.db.get().unwrap();
-        let result = get_events(&connection, criteria);
+        let result = get_events(&connection, the_user_id, criteria);
 
         match result {
             Ok(value) => QueryResult(Ok(value)),
@@ -161,8 +463,12 @@ impl QueryRoot {
 
     #[graphql(description = "Get the list of Plan Events for a User")]
     fn get_plan_events(context: &DBContext, criteria: EventCriteria) -> QueryResult<Vec<PlanRow>> {
+        let the_user_id = match context.caller_id() {
+            Ok(id) => id,
+            Err(e) => return access_denied(e),
+        };
         let connection = context.db.get().unwrap();
-        let result = get_plan_events(&connection, criteria);
+        let result = get_plan_events(&connection, the_user_id, criteria);
 
         match result {
             Ok(value) => QueryResult(Ok(value)),
@@ -172,8 +478,12 @@ impl QueryRoot {
 
     #[graphql(description = "Get the list of events due for a user")]
     fn get_due(context: &DBContext, criteria: EventCriteria) -> QueryResult<Vec<ToDo>> {
+        let the_user_id = match context.caller_id() {
+            Ok(id) => id,
+            Err(e) => return access_denied(e),
+        };
         let connection = context.db.get().unwrap();
-        let result = get_to_dos(&connection, criteria);
+        let result = get_to_dos(&connection, the_user_id, criteria);
 
         match result {
             Ok(value) => QueryResult(Ok(value)),
@@ -184,6 +494,10 @@ impl QueryRoot {
     #[graphql(description = "Get the list of objectives for an Enrollment")]
     fn get_objectives(context: &DBContext, criteria: PlanCriteria) -> QueryResult<Vec<Objective>> {
         let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::AttendsEnrollment(criteria.enrollment_id.as_str())) {
+            return denial.into();
+        }
+
         let result = get_objectives(&connection, criteria);
 
         match result {
@@ -195,6 +509,10 @@ impl QueryRoot {
     #[graphql(description = "Get the list of options for an Enrollment")]
     fn get_options(context: &DBContext, criteria: PlanCriteria) -> QueryResult<Vec<Constraint>> {
         let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::AttendsEnrollment(criteria.enrollment_id.as_str())) {
+            return denial.into();
+        }
+
         let result = get_options(&connection, criteria);
 
         match result {
@@ -206,6 +524,10 @@ impl QueryRoot {
     #[graphql(description = "Get the list of observations for an Enrollment")]
     fn get_observations(context: &DBContext, criteria: PlanCriteria) -> QueryResult<Vec<Observation>> {
         let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::AttendsEnrollment(criteria.enrollment_id.as_str())) {
+            return denial.into();
+        }
+
         let result = get_observations(&connection, criteria);
 
         match result {
@@ -217,6 +539,10 @@ impl QueryRoot {
     #[graphql(description = "Get the list of tasks for an Enrollment")]
     fn get_tasks(context: &DBContext, criteria: PlanCriteria) -> QueryResult<Vec<Task>> {
         let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::AttendsEnrollment(criteria.enrollment_id.as_str())) {
+            return denial.into();
+        }
+
         let result = get_tasks(&connection, criteria);
 
         match result {
@@ -225,9 +551,28 @@ impl QueryRoot {
         }
     }
 
+    #[graphql(description = "Get the links between the tasks of an Enrollment")]
+    fn get_task_links(context: &DBContext, enrollment_id: String) -> QueryResult<Vec<TaskLink>> {
+        let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::AttendsEnrollment(enrollment_id.as_str())) {
+            return denial.into();
+        }
+
+        let result = get_task_links(&connection, enrollment_id.as_str());
+
+        match result {
+            Ok(value) => QueryResult(Ok(value)),
+            Err(e) => query_error(e),
+        }
+    }
+
     #[graphql(description = "Get the list of notes for a SessionUser")]
     fn get_notes(context: &DBContext, criteria: NoteCriteria) -> QueryResult<Vec<Note>> {
         let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::OwnsSessionUser(criteria.session_user_id.as_str())) {
+            return denial.into();
+        }
+
         let result = get_notes(&connection, criteria);
 
         match result {
@@ -238,6 +583,10 @@ impl QueryRoot {
 
     fn get_discussions(context: &DBContext, criteria: DiscussionCriteria) -> QueryResult<Vec<Discussion>> {
         let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::AttendsEnrollment(criteria.enrollment_id.as_str())) {
+            return denial.into();
+        }
+
         let result = get_discussions(&connection, criteria);
 
         match result {
@@ -249,6 +598,10 @@ impl QueryRoot {
     #[graphql(description = "Get the list of notes of an enrollment. Hence both the member and the coach notes directly to the member.")]
     fn get_enrollment_notes(context: &DBContext, criteria: PlanCriteria) -> QueryResult<Vec<NoteRow>> {
         let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::AttendsEnrollment(criteria.enrollment_id.as_str())) {
+            return denial.into();
+        }
+
         let result = get_enrollment_notes(&connection, criteria);
 
         match result {
@@ -260,6 +613,8 @@ impl QueryRoot {
     #[graphql(description = "Get the Session by its id")]
     fn get_session(context: &DBContext, criteria: SessionCriteria) -> FieldResult<Session> {
         let connection = context.db.get().unwrap();
+        authorized(context, &connection, Rule::AttendsSession(criteria.id.as_str()))?;
+
         let session = find(&connection, &criteria.id)?;
         Ok(session)
     }
@@ -267,18 +622,11 @@ impl QueryRoot {
     #[graphql(description = "Get the People participating in an Event")]
     fn get_session_users(context: &DBContext, criteria: SessionCriteria) -> QueryResult<Vec<SessionPeople>> {
         let connection = context.db.get().unwrap();
-        let result = get_people(&connection, criteria);
-
-        match result {
-            Ok(value) => QueryResult(Ok(value)),
-            Err(e) => query_error(e),
+        if let Err(denial) = authorized(context, &connection, Rule::AttendsSession(criteria.id.as_str())) {
+            return denial.into();
         }
-    }
 
-    #[graphql(description = "Top 3 mails marked as Pending")]
-    fn get_sendable_mails(context: &DBContext) -> QueryResult<Vec<Mailable>> {
-        let connection = context.db.get().unwrap();
-        let result = sendable_mails(&connection);
+        let result = get_people(&connection, criteria);
 
         match result {
             Ok(value) => QueryResult(Ok(value)),
@@ -288,8 +636,12 @@ impl QueryRoot {
 
     #[graphql(description = "Get the List of all the Boards of an enrolled member")]
     fn get_boards(context: &DBContext, criteria: EventCriteria) -> QueryResult<Vec<BoardRow>> {
+        let the_user_id = match context.caller_id() {
+            Ok(id) => id,
+            Err(e) => return access_denied(e),
+        };
         let connection = context.db.get().unwrap();
-        let result = get_boards(&connection, criteria);
+        let result = get_boards(&connection, the_user_id, criteria);
 
         match result {
             Ok(value) => QueryResult(Ok(value)),
@@ -313,14 +665,9 @@ impl MutationRoot {
         }
     }
 
-    fn reset_password(context: &DBContext, request: ResetPasswordRequest) -> MutationResult<User> {
-        let errors = request.validate();
-        if !errors.is_empty() {
-            return MutationResult(Err(errors));
-        }
-
+    fn verify_email(context: &DBContext, token: String) -> MutationResult<User> {
         let connection = context.db.get().unwrap();
-        let result = reset_password(&connection, &request);
+        let result = verify_email(&connection, token.as_str());
 
         match result {
             Ok(user) => MutationResult(Ok(user)),
@@ -328,138 +675,502 @@ impl MutationRoot {
         }
     }
 
-    fn create_abstract_task(context: &DBContext, request: NewAbstractTaskRequest) -> MutationResult<AbstractTask> {
-        let errors = request.validate();
-        if !errors.is_empty() {
-            return MutationResult(Err(errors));
-        }
+    #[graphql(description = "Issue a fresh calendar feed URL for the caller, revoking the earlier one")]
+    fn issue_calendar_feed(context: &DBContext) -> MutationResult<String> {
+        let the_user_id = match context.caller_id() {
+            Ok(id) => id,
+            Err(e) => return authentication_error(e),
+        };
 
         let connection = context.db.get().unwrap();
-        let result = create_abstract_task(&connection, &request);
+        let result = issue_feed(&connection, the_user_id);
 
         match result {
-            Ok(abstract_task) => MutationResult(Ok(abstract_task)),
-            Err(e) => mutation_error(e),
+            Ok(feed_url) => MutationResult(Ok(feed_url)),
+            Err(e) => service_error(e),
         }
     }
 
-    fn create_master_plan(context: &DBContext, request: NewMasterPlanRequest) -> MutationResult<MasterPlan> {
-        let errors = request.validate();
-        if !errors.is_empty() {
-            return MutationResult(Err(errors));
-        }
+    fn revoke_calendar_feed(context: &DBContext) -> MutationResult<String> {
+        let the_user_id = match context.caller_id() {
+            Ok(id) => id,
+            Err(e) => return authentication_error(e),
+        };
 
         let connection = context.db.get().unwrap();
-        let result = create_master_plan(&connection, &request);
+        let result = revoke_feed(&connection, the_user_id);
 
         match result {
-            Ok(master_plan) => MutationResult(Ok(master_plan)),
-            Err(e) => mutation_error(e),
+            Ok(_) => MutationResult(Ok(String::from("Ok"))),
+            Err(e) => service_error(e),
         }
     }
 
-    fn create_master_task(context: &DBContext, new_master_task_request: NewMasterTaskRequest) -> MutationResult<MasterTask> {
-        let errors = new_master_task_request.validate();
+    fn reset_password(context: &DBContext, request: ResetPasswordRequest) -> MutationResult<User> {
+        let errors = request.validate();
         if !errors.is_empty() {
             return MutationResult(Err(errors));
         }
 
         let connection = context.db.get().unwrap();
-        let result = create_master_task(&connection, &new_master_task_request);
+        let result = reset_password(&connection, &request);
 
         match result {
-            Ok(master_task) => MutationResult(Ok(master_task)),
-            Err(e) => mutation_error(e),
+            Ok(user) => MutationResult(Ok(user)),
+            Err(e) => service_error(e),
         }
     }
 
-    fn update_master_task(context: &DBContext, update_master_task_request: UpdateMasterTaskRequest) -> MutationResult<MasterTask> {
-        let errors = update_master_task_request.validate();
+    #[graphql(description = "Set the IANA time zone (say Asia/Kolkata) of the caller")]
+    fn update_time_zone(context: &DBContext, request: TimeZoneRequest) -> MutationResult<User> {
+        let the_user_id = match context.caller_id() {
+            Ok(id) => id,
+            Err(e) => return authentication_error(e),
+        };
+
+        let errors = request.validate();
         if !errors.is_empty() {
             return MutationResult(Err(errors));
         }
 
         let connection = context.db.get().unwrap();
-        let result = update_master_task(&connection, &update_master_task_request);
+        let result = update_time_zone(&connection, the_user_id, &request);
 
         match result {
-            Ok(task) => MutationResult(Ok(task)),
-            Err(e) => mutation_error(e),
+            Ok(user) => MutationResult(Ok(user)),
+            Err(e) => service_error(e),
         }
     }
 
-    fn save_master_plan(context: &DBContext, request: UpdateMasterPlanRequest) -> MutationResult<String> {
+    fn request_password_reset(context: &DBContext, email: String) -> MutationResult<String> {
         let connection = context.db.get().unwrap();
-        let result = update_master_plan(&connection, &request);
+        let result = request_password_reset(&connection, email.as_str());
 
         match result {
-            Ok(value) => MutationResult(Ok(value)),
-            Err(e) => mutation_error(e),
+            Ok(_) => MutationResult(Ok(String::from("Ok"))),
+            Err(e) => service_error(e),
         }
     }
 
-    fn create_program(context: &DBContext, new_program_request: NewProgramRequest) -> MutationResult<Program> {
-        let errors = new_program_request.validate();
+    fn complete_password_reset(context: &DBContext, request: CompletePasswordResetRequest) -> MutationResult<User> {
+        let errors = request.validate();
         if !errors.is_empty() {
             return MutationResult(Err(errors));
         }
 
         let connection = context.db.get().unwrap();
-        let result = create_new_program(&connection, &new_program_request);
-
-        match result {
-            Ok(program) => MutationResult(Ok(program)),
-            Err(e) => service_error(e),
-        }
-    }
-
-    fn associate_coach(context: &DBContext, request: AssociateCoachRequest) -> MutationResult<Program> {
-        let connection = context.db.get().unwrap();
-        let result = associate_coach(&connection, &request);
+        let result = complete_password_reset(&connection, &request);
 
         match result {
-            Ok(program) => MutationResult(Ok(program)),
+            Ok(user) => MutationResult(Ok(user)),
             Err(e) => service_error(e),
         }
     }
 
-    fn create_enrollment(context: &DBContext, new_enrollment_request: NewEnrollmentRequest) -> MutationResult<Enrollment> {
-        let errors = new_enrollment_request.validate();
-        if !errors.is_empty() {
-            return MutationResult(Err(errors));
-        }
+    fn apply_as_coach(context: &DBContext) -> MutationResult<CoachApplication> {
+        let the_user_id = match context.caller_id() {
+            Ok(id) => id,
+            Err(e) => return authentication_error(e),
+        };
 
         let connection = context.db.get().unwrap();
-        let result = create_new_enrollment(&connection, &new_enrollment_request);
+        let result = apply_as_coach(&connection, the_user_id);
 
         match result {
-            Ok(enrollment) => MutationResult(Ok(enrollment)),
+            Ok(application) => MutationResult(Ok(application)),
             Err(e) => service_error(e),
         }
     }
 
-    fn managed_enrollment(context: &DBContext, managed_enrollment_request: ManagedEnrollmentRequest) -> MutationResult<Enrollment> {
+    fn review_coach_application(context: &DBContext, request: ReviewApplicationRequest) -> MutationResult<CoachApplication> {
         let connection = context.db.get().unwrap();
-        let result = create_managed_enrollment(&connection, &managed_enrollment_request);
+        let the_user_id = match authorized(context, &connection, Rule::Administers) {
+            Ok(id) => id,
+            Err(denial) => return denial.into(),
+        };
+
+        let result = review_application(&connection, the_user_id, &request);
 
         match result {
-            Ok(enrollment) => MutationResult(Ok(enrollment)),
+            Ok(application) => MutationResult(Ok(application)),
             Err(e) => service_error(e),
         }
     }
 
-    fn create_session(context: &DBContext, new_session_request: NewSessionRequest) -> MutationResult<Session> {
-        let errors = new_session_request.validate();
+    fn create_abstract_task(context: &DBContext, request: NewAbstractTaskRequest) -> MutationResult<AbstractTask> {
+        let the_user_id = match context.caller_id() {
+            Ok(id) => id,
+            Err(e) => return authentication_error(e),
+        };
+
+        let errors = request.validate();
         if !errors.is_empty() {
             return MutationResult(Err(errors));
         }
 
         let connection = context.db.get().unwrap();
-        let result = create_session(&connection, &new_session_request);
+        let result = create_abstract_task(&connection, the_user_id, &request);
 
         match result {
-            Ok(session) => MutationResult(Ok(session)),
-            Err(e) => service_error(e),
+            Ok(abstract_task) => MutationResult(Ok(abstract_task)),
+            Err(e) => mutation_error(e),
+        }
+    }
+
+    fn create_master_plan(context: &DBContext, request: NewMasterPlanRequest) -> MutationResult<MasterPlan> {
+        let the_user_id = match context.caller_id() {
+            Ok(id) => id,
+            Err(e) => return authentication_error(e),
+        };
+
+        let errors = request.validate();
+        if !errors.is_empty() {
+            return MutationResult(Err(errors));
+        }
+
+        let connection = context.db.get().unwrap();
+        let result = create_master_plan(&connection, the_user_id, &request);
+
+        match result {
+            Ok(master_plan) => MutationResult(Ok(master_plan)),
+            Err(e) => mutation_error(e),
+        }
+    }
+
+    fn create_master_task(context: &DBContext, new_master_task_request: NewMasterTaskRequest) -> MutationResult<MasterTask> {
+        let errors = new_master_task_request.validate();
+        if !errors.is_empty() {
+            return MutationResult(Err(errors));
+        }
+
+        let connection = context.db.get().unwrap();
+        let the_user_id = match authorized(context, &connection, Rule::OwnsMasterPlan(new_master_task_request.master_plan_id.as_str())) {
+            Ok(id) => id,
+            Err(denial) => return denial.into(),
+        };
+
+        let result = create_master_task(&connection, the_user_id, &new_master_task_request);
+
+        match result {
+            Ok(master_task) => MutationResult(Ok(master_task)),
+            Err(e) => service_error(e),
+        }
+    }
+
+    fn update_master_task(context: &DBContext, update_master_task_request: UpdateMasterTaskRequest) -> MutationResult<MasterTask> {
+        let errors = update_master_task_request.validate();
+        if !errors.is_empty() {
+            return MutationResult(Err(errors));
+        }
+
+        let connection = context.db.get().unwrap();
+        let the_user_id = match authorized(context, &connection, Rule::OwnsMasterTask(update_master_task_request.id.as_str())) {
+            Ok(id) => id,
+            Err(denial) => return denial.into(),
+        };
+
+        let result = update_master_task(&connection, the_user_id, &update_master_task_request);
+
+        match result {
+            Ok(task) => MutationResult(Ok(task)),
+            Err(e) => service_error(e),
+        }
+    }
+
+    fn apply_master_plan(context: &DBContext, request: ApplyMasterPlanRequest) -> MutationResult<Vec<Task>> {
+        let errors = request.validate();
+        if !errors.is_empty() {
+            return MutationResult(Err(errors));
+        }
+
+        let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::CoachesEnrollment(request.enrollment_id.as_str())) {
+            return denial.into();
+        }
+
+        if let Err(denial) = authorized(context, &connection, Rule::UsesMasterPlan(request.master_plan_id.as_str())) {
+            return denial.into();
+        }
+
+        let result = apply_master_plan(&connection, &request);
+        match result {
+            Ok(value) => MutationResult(Ok(value)),
+            Err(e) => MutationResult(Err(e.errors)),
+        }
+    }
+
+    fn propagate_delays(context: &DBContext, request: PropagateDelaysRequest) -> MutationResult<Vec<TaskMove>> {
+        let connection = context.db.get().unwrap();
+        let the_user_id = match authorized(context, &connection, Rule::CoachesEnrollment(request.enrollment_id.as_str())) {
+            Ok(id) => id,
+            Err(denial) => return denial.into(),
+        };
+
+        let result = propagate_delays(&connection, the_user_id, &request);
+        match result {
+            Ok(value) => MutationResult(Ok(value)),
+            Err(e) => service_error(e),
+        }
+    }
+
+    fn save_master_plan(context: &DBContext, request: UpdateMasterPlanRequest) -> MutationResult<MasterPlan> {
+        let connection = context.db.get().unwrap();
+        let the_user_id = match authorized(context, &connection, Rule::OwnsMasterPlan(request.master_plan_id.as_str())) {
+            Ok(id) => id,
+            Err(denial) => return denial.into(),
+        };
+
+        let result = update_master_plan(&connection, the_user_id, &request);
+
+        match result {
+            Ok(value) => MutationResult(Ok(value)),
+            Err(e) => MutationResult(Err(e.errors)),
+        }
+    }
+
+    fn clone_master_plan(context: &DBContext, request: ClonePlanRequest) -> MutationResult<MasterPlan> {
+        let errors = request.validate();
+        if !errors.is_empty() {
+            return MutationResult(Err(errors));
+        }
+
+        let connection = context.db.get().unwrap();
+        let the_user_id = match authorized(context, &connection, Rule::UsesMasterPlan(request.master_plan_id.as_str())) {
+            Ok(id) => id,
+            Err(denial) => return denial.into(),
+        };
+
+        // A copy for a peer coach only when the plan is shared with them as well
+        if request.target_coach_id != the_user_id {
+            if let Err(e) = authorize(&connection, request.target_coach_id.as_str(), Rule::UsesMasterPlan(request.master_plan_id.as_str())) {
+                return authorization_error(e);
+            }
+        }
+
+        let result = clone_master_plan(&connection, &request);
+        match result {
+            Ok(value) => MutationResult(Ok(value)),
+            Err(e) => service_error(e),
+        }
+    }
+
+    fn import_master_plan(context: &DBContext, request: ImportPlanRequest) -> MutationResult<MasterPlan> {
+        let the_user_id = match context.caller_id() {
+            Ok(id) => id,
+            Err(e) => return authentication_error(e),
+        };
+
+        let document = match PlanDocument::parse(request.document.as_str()) {
+            Ok(document) => document,
+            Err(e) => return MutationResult(Err(vec![e])),
+        };
+
+        let connection = context.db.get().unwrap();
+        let target_id = request.target_coach_id.as_deref().unwrap_or(the_user_id);
+        if target_id != the_user_id {
+            if let Err(denial) = authorized(context, &connection, Rule::Administers) {
+                return denial.into();
+            }
+        }
+
+        let result = import_master_plan(&connection, target_id, &document);
+
+        match result {
+            Ok(value) => MutationResult(Ok(value)),
+            Err(e) => MutationResult(Err(e.errors)),
+        }
+    }
+
+    fn share_master_plan(context: &DBContext, request: SharePlanRequest) -> MutationResult<MasterPlanShare> {
+        let errors = request.validate();
+        if !errors.is_empty() {
+            return MutationResult(Err(errors));
+        }
+
+        let connection = context.db.get().unwrap();
+        let the_user_id = match authorized(context, &connection, Rule::OwnsMasterPlan(request.master_plan_id.as_str())) {
+            Ok(id) => id,
+            Err(denial) => return denial.into(),
+        };
+        if let Err(denial) = authorized(context, &connection, Rule::CoachesProgram(request.program_id.as_str())) {
+            return denial.into();
+        }
+
+        let result = share_master_plan(&connection, the_user_id, &request);
+        match result {
+            Ok(value) => MutationResult(Ok(value)),
+            Err(e) => service_error(e),
+        }
+    }
+
+    fn unshare_master_plan(context: &DBContext, request: SharePlanRequest) -> MutationResult<MasterPlanShare> {
+        let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::OwnsMasterPlan(request.master_plan_id.as_str())) {
+            return denial.into();
+        }
+
+        let result = unshare_master_plan(&connection, &request);
+        match result {
+            Ok(value) => MutationResult(Ok(value)),
+            Err(e) => service_error(e),
+        }
+    }
+
+    fn restore_master_plan(context: &DBContext, request: RestoreRevisionRequest) -> MutationResult<MasterPlan> {
+        let errors = request.validate();
+        if !errors.is_empty() {
+            return MutationResult(Err(errors));
+        }
+
+        let connection = context.db.get().unwrap();
+        let the_user_id = match authorized(context, &connection, Rule::OwnsMasterPlan(request.master_plan_id.as_str())) {
+            Ok(id) => id,
+            Err(denial) => return denial.into(),
+        };
+
+        let result = restore_revision(&connection, the_user_id, &request);
+        match result {
+            Ok(value) => MutationResult(Ok(value)),
+            Err(e) => service_error(e),
+        }
+    }
+
+    fn create_program(context: &DBContext, new_program_request: NewProgramRequest) -> MutationResult<Program> {
+        let the_user_id = match context.caller_id() {
+            Ok(id) => id,
+            Err(e) => return authentication_error(e),
+        };
+
+        let errors = new_program_request.validate();
+        if !errors.is_empty() {
+            return MutationResult(Err(errors));
+        }
+
+        let connection = context.db.get().unwrap();
+        let result = create_new_program(&connection, the_user_id, &new_program_request);
+
+        match result {
+            Ok(program) => MutationResult(Ok(program)),
+            Err(e) => service_error(e),
+        }
+    }
+
+    fn associate_coach(context: &DBContext, request: AssociateCoachRequest) -> MutationResult<Program> {
+        let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::OwnsProgram(request.program_id.as_str())) {
+            return denial.into();
+        }
+
+        let result = associate_coach(&connection, &request);
+
+        match result {
+            Ok(program) => MutationResult(Ok(program)),
+            Err(e) => service_error(e),
+        }
+    }
+
+    fn attach_program_plan(context: &DBContext, request: AttachPlanRequest) -> MutationResult<ProgramPlan> {
+        let errors = request.validate();
+        if !errors.is_empty() {
+            return MutationResult(Err(errors));
+        }
+
+        let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::OwnsProgram(request.program_id.as_str())) {
+            return denial.into();
+        }
+
+        if let Err(denial) = authorized(context, &connection, Rule::UsesMasterPlan(request.master_plan_id.as_str())) {
+            return denial.into();
+        }
+
+        let result = attach_plan(&connection, &request);
+
+        match result {
+            Ok(value) => MutationResult(Ok(value)),
+            Err(e) => service_error(e),
+        }
+    }
+
+    fn make_default_program_plan(context: &DBContext, request: ProgramPlanRequest) -> MutationResult<ProgramPlan> {
+        let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::OwnsProgram(request.program_id.as_str())) {
+            return denial.into();
+        }
+
+        let result = make_default(&connection, &request);
+
+        match result {
+            Ok(value) => MutationResult(Ok(value)),
+            Err(e) => service_error(e),
+        }
+    }
+
+    fn detach_program_plan(context: &DBContext, request: ProgramPlanRequest) -> MutationResult<ProgramPlan> {
+        let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::OwnsProgram(request.program_id.as_str())) {
+            return denial.into();
+        }
+
+        let result = detach_plan(&connection, &request);
+
+        match result {
+            Ok(value) => MutationResult(Ok(value)),
+            Err(e) => service_error(e),
+        }
+    }
+
+    fn create_enrollment(context: &DBContext, new_enrollment_request: NewEnrollmentRequest) -> MutationResult<Enrollment> {
+        let the_user_id = match context.caller_id() {
+            Ok(id) => id,
+            Err(e) => return authentication_error(e),
+        };
+
+        let errors = new_enrollment_request.validate();
+        if !errors.is_empty() {
+            return MutationResult(Err(errors));
+        }
+
+        let connection = context.db.get().unwrap();
+        let result = create_new_enrollment(&connection, the_user_id, &new_enrollment_request);
+
+        match result {
+            Ok(enrollment) => MutationResult(Ok(enrollment)),
+            Err(e) => service_error(e),
+        }
+    }
+
+    fn managed_enrollment(context: &DBContext, managed_enrollment_request: ManagedEnrollmentRequest) -> MutationResult<Enrollment> {
+        let connection = context.db.get().unwrap();
+        let the_user_id = match authorized(context, &connection, Rule::OwnsProgram(managed_enrollment_request.program_id.as_str())) {
+            Ok(id) => id,
+            Err(denial) => return denial.into(),
+        };
+
+        let result = create_managed_enrollment(&connection, the_user_id, &managed_enrollment_request);
+
+        match result {
+            Ok(enrollment) => MutationResult(Ok(enrollment)),
+            Err(e) => service_error(e),
+        }
+    }
+
+    fn create_session(context: &DBContext, new_session_request: NewSessionRequest) -> MutationResult<Session> {
+        let errors = new_session_request.validate();
+        if !errors.is_empty() {
+            return MutationResult(Err(errors));
+        }
+
+        let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::CoachesProgram(new_session_request.program_id.as_str())) {
+            return denial.into();
+        }
+
+        let result = create_session(&connection, &new_session_request);
+
+        match result {
+            Ok(session) => MutationResult(Ok(session)),
+            Err(e) => MutationResult(Err(e.errors)),
         }
     }
 
@@ -470,16 +1181,24 @@ impl MutationRoot {
         }
 
         let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::CoachesProgram(new_conference_request.program_id.as_str())) {
+            return denial.into();
+        }
+
         let result = create_conference(&connection, &new_conference_request);
 
         match result {
             Ok(conference) => MutationResult(Ok(conference)),
-            Err(e) => service_error(e),
+            Err(e) => MutationResult(Err(e.errors)),
         }
     }
 
     fn manage_conference(context: &DBContext, member_request: MemberRequest) -> MutationResult<Vec<String>> {
         let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::CoachesConference(member_request.conference_id.as_str())) {
+            return denial.into();
+        }
+
         let result = manage_members(&connection, &member_request);
 
         match result {
@@ -495,11 +1214,15 @@ impl MutationRoot {
         }
 
         let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::AttendsEnrollment(new_objective_request.enrollment_id.as_str())) {
+            return denial.into();
+        }
+
         let result = create_objective(&connection, &new_objective_request);
 
         match result {
             Ok(objective) => MutationResult(Ok(objective)),
-            Err(e) => mutation_error(e),
+            Err(e) => service_error(e),
         }
     }
 
@@ -510,6 +1233,10 @@ impl MutationRoot {
         }
 
         let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::AttendsEnrollment(new_option_request.enrollment_id.as_str())) {
+            return denial.into();
+        }
+
         let result = create_option(&connection, &new_option_request);
 
         match result {
@@ -525,6 +1252,10 @@ impl MutationRoot {
         }
 
         let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::AttendsEnrollment(new_observation_request.enrollment_id.as_str())) {
+            return denial.into();
+        }
+
         let result = create_observation(&connection, &new_observation_request);
 
         match result {
@@ -540,6 +1271,10 @@ impl MutationRoot {
         }
 
         let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::AttendsObservation(update_observation_request.id.as_str())) {
+            return denial.into();
+        }
+
         let result = update_observation(&connection, &update_observation_request);
 
         match result {
@@ -555,6 +1290,10 @@ impl MutationRoot {
         }
 
         let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::AttendsOption(update_option_request.id.as_str())) {
+            return denial.into();
+        }
+
         let result = update_option(&connection, &update_option_request);
 
         match result {
@@ -570,11 +1309,15 @@ impl MutationRoot {
         }
 
         let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::AttendsObjective(update_objective_request.id.as_str())) {
+            return denial.into();
+        }
+
         let result = update_objective(&connection, &update_objective_request);
 
         match result {
             Ok(objective) => MutationResult(Ok(objective)),
-            Err(e) => mutation_error(e),
+            Err(e) => service_error(e),
         }
     }
 
@@ -585,11 +1328,15 @@ impl MutationRoot {
         }
 
         let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::CoachesEnrollment(new_task_request.enrollment_id.as_str())) {
+            return denial.into();
+        }
+
         let result = create_task(&connection, &new_task_request);
 
         match result {
             Ok(task) => MutationResult(Ok(task)),
-            Err(e) => mutation_error(e),
+            Err(e) => service_error(e),
         }
     }
 
@@ -600,16 +1347,44 @@ impl MutationRoot {
         }
 
         let connection = context.db.get().unwrap();
-        let result = update_task(&connection, &update_task_request);
+        let the_user_id = match authorized(context, &connection, Rule::CoachesTask(update_task_request.id.as_str())) {
+            Ok(id) => id,
+            Err(denial) => return denial.into(),
+        };
+
+        let result = update_task(&connection, the_user_id, &update_task_request);
 
         match result {
             Ok(task) => MutationResult(Ok(task)),
-            Err(e) => mutation_error(e),
+            Err(e) => service_error(e),
+        }
+    }
+
+    fn save_task_links(context: &DBContext, request: UpdateTaskLinksRequest) -> MutationResult<Vec<TaskLink>> {
+        let errors = request.validate();
+        if !errors.is_empty() {
+            return MutationResult(Err(errors));
+        }
+
+        let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::CoachesEnrollment(request.enrollment_id.as_str())) {
+            return denial.into();
+        }
+
+        let result = save_task_links(&connection, &request);
+
+        match result {
+            Ok(value) => MutationResult(Ok(value)),
+            Err(e) => MutationResult(Err(e.errors)),
         }
     }
 
     fn update_task_closing_notes(context: &DBContext, request: UpdateClosingNoteRequest) -> MutationResult<Task> {
         let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::CoachesTask(request.id.as_str())) {
+            return denial.into();
+        }
+
         let result = update_closing_notes(&connection, &request);
         match result {
             Ok(task) => MutationResult(Ok(task)),
@@ -619,6 +1394,10 @@ impl MutationRoot {
 
     fn update_task_response(context: &DBContext, request: UpdateResponseRequest) -> MutationResult<Task> {
         let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::OwnsTask(request.id.as_str())) {
+            return denial.into();
+        }
+
         let result = update_response(&connection, &request);
         match result {
             Ok(task) => MutationResult(Ok(task)),
@@ -627,6 +1406,10 @@ impl MutationRoot {
     }
     fn alter_coach_task_state(context: &DBContext, request: ChangeCoachTaskStateRequest) -> MutationResult<Task> {
         let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::CoachesTask(request.id.as_str())) {
+            return denial.into();
+        }
+
         let result = change_coach_task_state(&connection, &request);
         match result {
             Ok(task) => MutationResult(Ok(task)),
@@ -636,6 +1419,10 @@ impl MutationRoot {
 
     fn alter_member_task_state(context: &DBContext, request: ChangeMemberTaskStateRequest) -> MutationResult<Task> {
         let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::OwnsTask(request.id.as_str())) {
+            return denial.into();
+        }
+
         let result = change_member_task_state(&connection, &request);
         match result {
             Ok(task) => MutationResult(Ok(task)),
@@ -645,6 +1432,10 @@ impl MutationRoot {
 
     fn alter_session_state(context: &DBContext, request: ChangeSessionStateRequest) -> MutationResult<Session> {
         let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::AttendsSession(request.id.as_str())) {
+            return denial.into();
+        }
+
         let result = change_session_state(&connection, &request);
         match result {
             Ok(session) => MutationResult(Ok(session)),
@@ -652,8 +1443,209 @@ impl MutationRoot {
         }
     }
 
+    fn save_working_hours(context: &DBContext, request: WorkingHoursRequest) -> MutationResult<Vec<WorkingHour>> {
+        let the_user_id = match context.caller_id() {
+            Ok(id) => id,
+            Err(e) => return authentication_error(e),
+        };
+
+        let errors = request.validate();
+        if !errors.is_empty() {
+            return MutationResult(Err(errors));
+        }
+
+        let connection = context.db.get().unwrap();
+        let result = save_working_hours(&connection, the_user_id, &request);
+
+        match result {
+            Ok(hours) => MutationResult(Ok(hours)),
+            Err(e) => service_error(e),
+        }
+    }
+
+    fn add_blackout(context: &DBContext, request: NewBlackoutRequest) -> MutationResult<Blackout> {
+        let the_user_id = match context.caller_id() {
+            Ok(id) => id,
+            Err(e) => return authentication_error(e),
+        };
+
+        let errors = request.validate();
+        if !errors.is_empty() {
+            return MutationResult(Err(errors));
+        }
+
+        let connection = context.db.get().unwrap();
+        let result = add_blackout(&connection, the_user_id, &request);
+
+        match result {
+            Ok(blackout) => MutationResult(Ok(blackout)),
+            Err(e) => service_error(e),
+        }
+    }
+
+    fn remove_blackout(context: &DBContext, id: String) -> MutationResult<String> {
+        let the_user_id = match context.caller_id() {
+            Ok(id) => id,
+            Err(e) => return authentication_error(e),
+        };
+
+        let connection = context.db.get().unwrap();
+        let result = remove_blackout(&connection, the_user_id, id.as_str());
+
+        match result {
+            Ok(_) => MutationResult(Ok(String::from("Ok"))),
+            Err(e) => service_error(e),
+        }
+    }
+
+    #[graphql(description = "A member requests a session, offering a few slots to the coach")]
+    fn request_session(context: &DBContext, request: MemberSessionRequest) -> MutationResult<Session> {
+        let the_user_id = match context.caller_id() {
+            Ok(id) => id,
+            Err(e) => return authentication_error(e),
+        };
+
+        let errors = request.validate();
+        if !errors.is_empty() {
+            return MutationResult(Err(errors));
+        }
+
+        let connection = context.db.get().unwrap();
+        let result = request_session(&connection, the_user_id, &request);
+
+        match result {
+            Ok(session) => MutationResult(Ok(session)),
+            Err(e) => service_error(e),
+        }
+    }
+
+    fn accept_session_request(context: &DBContext, request: AcceptSessionRequest) -> MutationResult<Session> {
+        let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::CoachesSession(request.id.as_str())) {
+            return denial.into();
+        }
+
+        let result = accept_session_request(&connection, &request);
+        match result {
+            Ok(session) => MutationResult(Ok(session)),
+            Err(e) => MutationResult(Err(e.errors)),
+        }
+    }
+
+    fn decline_session_request(context: &DBContext, request: DeclineSessionRequest) -> MutationResult<Session> {
+        let errors = request.validate();
+        if !errors.is_empty() {
+            return MutationResult(Err(errors));
+        }
+
+        let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::CoachesSession(request.id.as_str())) {
+            return denial.into();
+        }
+
+        let result = decline_session_request(&connection, &request);
+        match result {
+            Ok(session) => MutationResult(Ok(session)),
+            Err(e) => service_error(e),
+        }
+    }
+
+    fn reschedule_session(context: &DBContext, request: RescheduleRequest) -> MutationResult<Session> {
+        let errors = request.validate();
+        if !errors.is_empty() {
+            return MutationResult(Err(errors));
+        }
+
+        let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::AttendsSession(request.id.as_str())) {
+            return denial.into();
+        }
+
+        let result = reschedule_session(&connection, &request);
+        match result {
+            Ok(session) => MutationResult(Ok(session)),
+            Err(e) => MutationResult(Err(e.errors)),
+        }
+    }
+
+    fn create_session_series(context: &DBContext, request: NewSessionSeriesRequest) -> MutationResult<SeriesRow> {
+        let errors = request.validate();
+        if !errors.is_empty() {
+            return MutationResult(Err(errors));
+        }
+
+        let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::CoachesProgram(request.program_id.as_str())) {
+            return denial.into();
+        }
+
+        let result = create_series(&connection, &request);
+        match result {
+            Ok(row) => MutationResult(Ok(row)),
+            Err(e) => MutationResult(Err(e.errors)),
+        }
+    }
+
+    fn update_following_sessions(context: &DBContext, request: UpdateSeriesRequest) -> MutationResult<SeriesRow> {
+        let errors = request.validate();
+        if !errors.is_empty() {
+            return MutationResult(Err(errors));
+        }
+
+        let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::CoachesSession(request.session_id.as_str())) {
+            return denial.into();
+        }
+
+        let result = update_following(&connection, &request);
+        match result {
+            Ok(row) => MutationResult(Ok(row)),
+            Err(e) => MutationResult(Err(e.errors)),
+        }
+    }
+
+    fn cancel_occurrence(context: &DBContext, request: CancelOccurrenceRequest) -> MutationResult<Session> {
+        let errors = request.validate();
+        if !errors.is_empty() {
+            return MutationResult(Err(errors));
+        }
+
+        let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::CoachesSession(request.session_id.as_str())) {
+            return denial.into();
+        }
+
+        let result = cancel_occurrence(&connection, &request);
+        match result {
+            Ok(session) => MutationResult(Ok(session)),
+            Err(e) => service_error(e),
+        }
+    }
+
+    fn reschedule_conference(context: &DBContext, request: RescheduleRequest) -> MutationResult<Conference> {
+        let errors = request.validate();
+        if !errors.is_empty() {
+            return MutationResult(Err(errors));
+        }
+
+        let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::CoachesConference(request.id.as_str())) {
+            return denial.into();
+        }
+
+        let result = reschedule_conference(&connection, &request);
+        match result {
+            Ok(conference) => MutationResult(Ok(conference)),
+            Err(e) => MutationResult(Err(e.errors)),
+        }
+    }
+
     fn alter_program_state(context: &DBContext, request: ChangeProgramStateRequest) -> MutationResult<String> {
         let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::OwnsProgram(request.id.as_str())) {
+            return denial.into();
+        }
+
         let result = change_program_state(&connection, &request);
 
         match result {
@@ -669,17 +1661,31 @@ impl MutationRoot {
         }
 
         let connection = context.db.get().unwrap();
+        if let Err(denial) = authorized(context, &connection, Rule::OwnsSessionUser(new_note_request.session_user_id.as_str())) {
+            return denial.into();
+        }
+
         let result = create_new_note(&connection, &new_note_request);
 
         match result {
             Ok(note) => MutationResult(Ok(note)),
-            Err(e) => mutation_error(e),
+            Err(e) => service_error(e),
         }
     }
 
     fn create_discussion(context: &DBContext, new_discussion_request: NewDiscussionRequest) -> MutationResult<Discussion> {
         let connection = context.db.get().unwrap();
-        let result = create_new_discussion(&connection, &new_discussion_request);
+        let the_user_id = match authorized(context, &connection, Rule::AttendsEnrollment(new_discussion_request.enrollment_id.as_str())) {
+            Ok(id) => id,
+            Err(denial) => return denial.into(),
+        };
+
+        let recipient = Rule::WritesInEnrollment(new_discussion_request.enrollment_id.as_str(), new_discussion_request.to_id.as_str());
+        if let Err(denial) = authorized(context, &connection, recipient) {
+            return denial.into();
+        }
+
+        let result = create_new_discussion(&connection, the_user_id, &new_discussion_request);
 
         match result {
             Ok(discussion) => MutationResult(Ok(discussion)),
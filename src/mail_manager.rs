@@ -0,0 +1,186 @@
+use std::thread;
+use std::time::Duration as StdDuration;
+
+use chrono::{Duration, NaiveDateTime};
+use lettre::message::{header::ContentType, Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+
+use crate::commons::ical;
+use crate::commons::util;
+use crate::db_manager::MySqlConnectionPool;
+use crate::models::correspondences::{Mailable, CC};
+use crate::services::correspondences::{dispatchable_mails, mark_as_failed, mark_as_sent};
+
+const BATCH_SIZE: i64 = 10;
+const POLL_INTERVAL_IN_SECONDS: u64 = 30;
+
+// A failed mail is retried after 1, 2, 4, 8... minutes till it
+// exhausts the attempts.
+const MAX_ATTEMPTS: i32 = 6;
+const BASE_BACKOFF_IN_MINUTES: i64 = 1;
+
+/**
+ * The Mail Dispatcher delivers the pending correspondences over SMTP.
+ *
+ * It runs on its own thread and polls the correspondences table, so that a
+ * mail survives a restart of the server: it stays pending till it is sent
+ * or till we give up after MAX_ATTEMPTS.
+ *
+ * The SMTP server is read from the .env file (SMTP_HOST, SMTP_PORT and the
+ * optional SMTP_USER, SMTP_PASSWORD). The mails go over TLS, unless the
+ * .env opts out with SMTP_TLS=false; plain SMTP is meant for a local test
+ * SMTP sink, so it never carries the credentials.
+ */
+pub fn start_dispatcher(pool: MySqlConnectionPool) {
+    let mailer = build_transport();
+
+    thread::spawn(move || loop {
+        dispatch(&pool, &mailer);
+        thread::sleep(StdDuration::from_secs(POLL_INTERVAL_IN_SECONDS));
+    });
+}
+
+fn dispatch(pool: &MySqlConnectionPool, mailer: &SmtpTransport) {
+    let connection = match pool.get() {
+        Ok(connection) => connection,
+        Err(e) => {
+            eprintln!("Mail dispatcher could not get a connection: {}", e);
+            return;
+        }
+    };
+
+    let mailables = match dispatchable_mails(&connection, BATCH_SIZE) {
+        Ok(mailables) => mailables,
+        Err(e) => {
+            eprintln!("Mail dispatcher could not fetch the mails: {}", e);
+            return;
+        }
+    };
+
+    for mailable in mailables {
+        let correspondence = &mailable.correspondence;
+
+        let result = build_message(&mailable).and_then(|message| mailer.send(&message).map_err(|e| e.to_string()));
+
+        let outcome = match result {
+            Ok(_) => mark_as_sent(&connection, correspondence.id.as_str()),
+            Err(reason) => {
+                let retry_on = retry_on(correspondence.attempts + 1, util::now());
+                mark_as_failed(&connection, correspondence.id.as_str(), reason.as_str(), retry_on)
+            }
+        };
+
+        if let Err(e) = outcome {
+            eprintln!("Mail dispatcher could not update the mail {}: {}", correspondence.id, e);
+        }
+    }
+}
+
+fn build_message(mailable: &Mailable) -> Result<Message, String> {
+    let correspondence = &mailable.correspondence;
+
+    let mut builder = Message::builder()
+        .from(as_mailbox(correspondence.from_email.as_str())?)
+        .subject(correspondence.subject.as_str());
+
+    if mailable.receipients.is_empty() {
+        return Err(String::from("The mail has no recipients"));
+    }
+
+    for recipient in &mailable.receipients {
+        let mailbox = as_mailbox(recipient.to_email.as_str())?;
+        builder = match recipient.to_type.as_str() {
+            CC => builder.cc(mailbox),
+            _ => builder.to(mailbox),
+        };
+    }
+
+    let content = correspondence.content.clone().unwrap_or_default();
+
+    let result = match &correspondence.calendar {
+        Some(calendar) => builder.multipart(with_calendar(content, calendar)?),
+        None => builder.header(ContentType::TEXT_PLAIN).body(content),
+    };
+
+    result.map_err(|e| e.to_string())
+}
+
+/**
+ * The event goes both inline (text/calendar alternative), which is what the
+ * mail clients pick up as an invitation, and as an invite.ics attachment for
+ * the rest.
+ */
+fn with_calendar(content: String, calendar: &str) -> Result<MultiPart, String> {
+    let content_type = format!("text/calendar; method={}; charset=UTF-8", ical::method_of(calendar));
+    let calendar_type = ContentType::parse(content_type.as_str()).map_err(|e| e.to_string())?;
+
+    let alternative = MultiPart::alternative()
+        .singlepart(SinglePart::plain(content))
+        .singlepart(SinglePart::builder().header(calendar_type.clone()).body(calendar.to_owned()));
+
+    let attachment = Attachment::new(String::from("invite.ics")).body(calendar.to_owned(), calendar_type);
+
+    Ok(MultiPart::mixed().multipart(alternative).singlepart(attachment))
+}
+
+fn as_mailbox(address: &str) -> Result<Mailbox, String> {
+    address.trim().parse::<Mailbox>().map_err(|e| format!("{}: {}", address, e))
+}
+
+/**
+ * None, when we have exhausted the attempts and hence give up on the mail.
+ */
+fn retry_on(attempts: i32, now: NaiveDateTime) -> Option<NaiveDateTime> {
+    if attempts >= MAX_ATTEMPTS {
+        return None;
+    }
+
+    let backoff = BASE_BACKOFF_IN_MINUTES * 2_i64.pow((attempts - 1).max(0) as u32);
+
+    Some(now + Duration::minutes(backoff))
+}
+
+fn build_transport() -> SmtpTransport {
+    let host = dotenv::var("SMTP_HOST").unwrap_or_else(|_| String::from("localhost"));
+    let tls = dotenv::var("SMTP_TLS").map(|tls| tls != "false").unwrap_or(true);
+
+    if !tls {
+        let port: u16 = dotenv::var("SMTP_PORT").ok().and_then(|port| port.parse().ok()).unwrap_or(25);
+        return SmtpTransport::builder_dangerous(host.as_str()).port(port).build();
+    }
+
+    let mut builder = SmtpTransport::relay(host.as_str()).expect("The SMTP_HOST should be a valid host");
+
+    if let Some(port) = dotenv::var("SMTP_PORT").ok().and_then(|port| port.parse().ok()) {
+        builder = builder.port(port);
+    }
+
+    if let (Ok(user), Ok(password)) = (dotenv::var("SMTP_USER"), dotenv::var("SMTP_PASSWORD")) {
+        builder = builder.credentials(Credentials::new(user, password));
+    }
+
+    builder.build()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn should_back_off_exponentially() {
+        let now = util::as_date("2021-02-14T10:00:00Z").unwrap();
+
+        assert_eq!(Some(now + Duration::minutes(1)), retry_on(1, now));
+        assert_eq!(Some(now + Duration::minutes(2)), retry_on(2, now));
+        assert_eq!(Some(now + Duration::minutes(16)), retry_on(5, now));
+    }
+
+    #[test]
+    fn should_give_up_after_max_attempts() {
+        let now = util::as_date("2021-02-14T10:00:00Z").unwrap();
+
+        assert_eq!(None, retry_on(MAX_ATTEMPTS, now));
+    }
+}
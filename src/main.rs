@@ -11,9 +11,11 @@ use actix_web::{web, App, Error, HttpRequest, HttpResponse, HttpServer};
 use juniper::http::graphiql::graphiql_source;
 use juniper::http::GraphQLRequest;
 
+mod auth_manager;
 mod commons;
 mod db_manager;
 mod file_manager;
+mod mail_manager;
 mod graphql_schema;
 mod models;
 mod schema;
@@ -23,6 +25,7 @@ mod services;
 mod service_tests;
 
 use actix_files::NamedFile;
+use auth_manager::{caller_of, Authentication};
 use db_manager::establish_connection;
 use file_manager::{
     fetch_board_file, fetch_list_of_boards, 
@@ -35,6 +38,9 @@ use file_manager::{
 };
 use graphql_schema::{create_gq_schema, DBContext, GQSchema};
 
+use crate::commons::authz::{authorize, Rule, NOT_AUTHORIZED};
+use crate::services::calendar_feeds::{render_feed, INVALID_FEED};
+use crate::services::diagrams::{render_gantt, render_plan, Format, PLAN_NOT_FOUND};
 use crate::services::discussions::get_pending_feed_count;
 
 async fn upload_notes_file(payload: Multipart) -> Result<HttpResponse, Error> {
@@ -76,6 +82,11 @@ async fn upload_user_content(_request: HttpRequest, payload: Multipart) -> Resul
 async fn count_feeds(_request: HttpRequest, ctx: web::Data<DBContext>) -> Result<HttpResponse, Error> {
 
     let user_id: String = _request.match_info().query("user_id").parse().unwrap();
+
+    let is_caller = caller_of(&_request).is_some_and(|caller| caller.user_id == user_id);
+    if !is_caller {
+        return Ok(HttpResponse::Forbidden().finish());
+    }
     
     let result = web::block(move || {
         let connection = ctx.db.get().unwrap();
@@ -93,6 +104,90 @@ async fn count_feeds(_request: HttpRequest, ctx: web::Data<DBContext>) -> Result
     Ok(HttpResponse::Ok().content_type("application/json").body(result))
 }
 
+/**
+ * The calendar apps subscribe to the feed without any login; the feed token
+ * in the URL is the credential, which the user may revoke any time.
+ */
+async fn calendar_feed(_request: HttpRequest, ctx: web::Data<DBContext>) -> Result<HttpResponse, Error> {
+    let feed_token: String = _request.match_info().query("feed_token").parse().unwrap();
+
+    let result = web::block(move || {
+        let connection = ctx.db.get().unwrap();
+        render_feed(&connection, feed_token.as_str())
+    })
+    .await;
+
+    match result {
+        Ok(ics) => Ok(HttpResponse::Ok().content_type("text/calendar; charset=utf-8").body(ics)),
+        Err(actix_web::error::BlockingError::Error(INVALID_FEED)) => Ok(HttpResponse::NotFound().finish()),
+        Err(e) => {
+            eprintln!("{}", e);
+            Ok(HttpResponse::InternalServerError().finish())
+        }
+    }
+}
+
+/**
+ * The graph of a master plan, for the coaches who may use the plan: DOT
+ * (graph.dot) or SVG (graph.svg).
+ */
+async fn plan_diagram(_request: HttpRequest, ctx: web::Data<DBContext>) -> Result<HttpResponse, Error> {
+    let master_plan_id: String = _request.match_info().query("master_plan_id").parse().unwrap();
+    let format = match Format::parse(_request.match_info().query("format")) {
+        Some(format) if format != Format::Mermaid => format,
+        _ => return Ok(HttpResponse::NotFound().finish()),
+    };
+    let caller = match caller_of(&_request) {
+        Some(caller) => caller,
+        None => return Ok(HttpResponse::Unauthorized().finish()),
+    };
+
+    let result = web::block(move || {
+        let connection = ctx.db.get().unwrap();
+        authorize(&connection, caller.user_id.as_str(), Rule::UsesMasterPlan(master_plan_id.as_str()))?;
+        render_plan(&connection, master_plan_id.as_str(), format)
+    })
+    .await;
+
+    as_diagram(result, format)
+}
+
+/**
+ * The tasks of an enrollment as a Gantt chart, for the coaches of the
+ * enrollment: SVG (gantt.svg) or Mermaid (gantt.mmd).
+ */
+async fn gantt_chart(_request: HttpRequest, ctx: web::Data<DBContext>) -> Result<HttpResponse, Error> {
+    let enrollment_id: String = _request.match_info().query("enrollment_id").parse().unwrap();
+    let format = match Format::parse(_request.match_info().query("format")) {
+        Some(format) if format != Format::Dot => format,
+        _ => return Ok(HttpResponse::NotFound().finish()),
+    };
+    let caller = match caller_of(&_request) {
+        Some(caller) => caller,
+        None => return Ok(HttpResponse::Unauthorized().finish()),
+    };
+
+    let result = web::block(move || {
+        let connection = ctx.db.get().unwrap();
+        authorize(&connection, caller.user_id.as_str(), Rule::CoachesEnrollment(enrollment_id.as_str()))?;
+        render_gantt(&connection, enrollment_id.as_str(), format)
+    })
+    .await;
+
+    as_diagram(result, format)
+}
+
+fn as_diagram(result: Result<String, actix_web::error::BlockingError<&'static str>>, format: Format) -> Result<HttpResponse, Error> {
+    match result {
+        Ok(diagram) => Ok(HttpResponse::Ok().content_type(format.content_type()).body(diagram)),
+        Err(actix_web::error::BlockingError::Error(NOT_AUTHORIZED)) => Ok(HttpResponse::Forbidden().finish()),
+        Err(actix_web::error::BlockingError::Error(PLAN_NOT_FOUND)) => Ok(HttpResponse::NotFound().finish()),
+        Err(e) => {
+            eprintln!("{}", e);
+            Ok(HttpResponse::InternalServerError().finish())
+        }
+    }
+}
 
 #[warn(unused_variables)]
 async fn index(_request: HttpRequest) -> HttpResponse {
@@ -112,7 +207,9 @@ async fn graphiql() -> HttpResponse {
  * will be blocked from accepting new connections.
  * 
  * */
-async fn graphql(ctx: web::Data<DBContext>, schema: web::Data<Arc<GQSchema>>, request: web::Json<GraphQLRequest>) -> Result<HttpResponse, Error> {
+async fn graphql(_request: HttpRequest, ctx: web::Data<DBContext>, schema: web::Data<Arc<GQSchema>>, request: web::Json<GraphQLRequest>) -> Result<HttpResponse, Error> {
+    let ctx = ctx.for_caller(caller_of(&_request));
+
     let result = web::block(move || {
         let res = request.execute(&schema, &ctx);
         let json_response = serde_json::to_string(&res)?;
@@ -140,7 +237,9 @@ async fn main() -> std::io::Result<()> {
     std::fs::create_dir_all(PLATFORM_ASSET_DIR).unwrap();
 
     let pool = establish_connection();
-    let db_context = DBContext { db: pool.clone() };
+    mail_manager::start_dispatcher(pool.clone());
+
+    let db_context = DBContext::new(pool.clone());
     let gq_schema = std::sync::Arc::new(create_gq_schema());
 
     let bind = dotenv::var("BIND").unwrap();
@@ -153,17 +252,28 @@ async fn main() -> std::io::Result<()> {
             .data(db_context.clone())
             .data(gq_schema.clone())
             .wrap(cors)
-            .route("graphql", web::post().to(graphql))
+            .service(web::resource("graphql").wrap(Authentication::optional()).route(web::post().to(graphql)))
             .route("graphiql", web::get().to(graphiql))
-            .route("assets/upload", web::post().to(upload_notes_file))
-            .route("assets/boards/{session_id}", web::get().to(list_of_boards))
-            .route("assets/boards/{session_id}/{filename}", web::get().to(offer_board_file))
-            .route("assets/users/{user_id}", web::post().to(upload_user_content))
-            .route("assets/users/{user_id}/{filename}", web::get().to(offer_user_content))
-            .route("assets/programs/{program_fuzzy_id}/{purpose}", web::post().to(upload_program_content))
-            .route("assets/programs/{program_fuzzy_id}/{purpose}/{filename}", web::get().to(offer_program_content))
-            .route("assets/platform/{filename}", web::get().to(offer_platform_content))
-            .route("feeds/{user_id}", web::get().to(count_feeds))
+            .service(
+                web::scope("assets")
+                    .wrap(Authentication::mandatory())
+                    .route("/upload", web::post().to(upload_notes_file))
+                    .route("/boards/{session_id}", web::get().to(list_of_boards))
+                    .route("/boards/{session_id}/{filename}", web::get().to(offer_board_file))
+                    .route("/users/{user_id}", web::post().to(upload_user_content))
+                    .route("/users/{user_id}/{filename}", web::get().to(offer_user_content))
+                    .route("/programs/{program_fuzzy_id}/{purpose}", web::post().to(upload_program_content))
+                    .route("/programs/{program_fuzzy_id}/{purpose}/{filename}", web::get().to(offer_program_content))
+                    .route("/platform/{filename}", web::get().to(offer_platform_content)),
+            )
+            .service(web::resource("feeds/{user_id}").wrap(Authentication::mandatory()).route(web::get().to(count_feeds)))
+            .route("calendar/{feed_token}.ics", web::get().to(calendar_feed))
+            .service(
+                web::scope("diagrams")
+                    .wrap(Authentication::mandatory())
+                    .route("/plans/{master_plan_id}/graph.{format}", web::get().to(plan_diagram))
+                    .route("/enrollments/{enrollment_id}/gantt.{format}", web::get().to(gantt_chart)),
+            )
             .route("/", web::get().to(index))
     })
     .bind(&bind)?
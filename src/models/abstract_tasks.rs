@@ -27,7 +27,6 @@ impl AbstractTask {
 #[derive(juniper::GraphQLInputObject)]
 pub struct NewAbstractTaskRequest {
     pub name: String,
-    pub coach_id: String,
 }
 
 impl NewAbstractTaskRequest {
@@ -38,10 +37,6 @@ impl NewAbstractTaskRequest {
             errors.push(ValidationError::new("name", "Name is a must."));
         }
 
-        if self.coach_id.trim().is_empty() {
-            errors.push(ValidationError::new("coach_id", "Coach is a must."));
-        }
-
         errors
     }
 }
@@ -55,18 +50,13 @@ pub struct NewAbstractTask {
 }
 
 impl NewAbstractTask {
-    pub fn from(request: &NewAbstractTaskRequest) -> NewAbstractTask {
+    pub fn from(request: &NewAbstractTaskRequest, coach_id: &str) -> NewAbstractTask {
         let fuzzy_id = util::fuzzy_id();
 
         NewAbstractTask {
             id: fuzzy_id,
             name: request.name.to_owned(),
-            coach_id: request.coach_id.to_owned(),
+            coach_id: coach_id.to_owned(),
         }
     }
 }
-
-#[derive(juniper::GraphQLInputObject)]
-pub struct AbstractTaskCriteria {
-    pub coach_id: String,
-}
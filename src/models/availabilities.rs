@@ -0,0 +1,393 @@
+use chrono::{DateTime, Datelike, NaiveDateTime, Timelike, Utc};
+use chrono_tz::Tz;
+
+use crate::commons::chassis::ValidationError;
+use crate::commons::util;
+use crate::schema::{blackouts, working_hours};
+
+const MINUTES_IN_A_DAY: i32 = 24 * 60;
+
+/**
+ * The weekly working hours of a coach. A day may have more than one range
+ * (say a morning and an evening). The day of week follows ISO 8601: Monday
+ * is 1 and Sunday is 7. The minutes are counted from the midnight, in the
+ * time zone of the coach, so that the hours hold across daylight saving.
+ */
+#[derive(Queryable, Debug, Clone)]
+pub struct WorkingHour {
+    pub id: String,
+    pub coach_id: String,
+    pub day_of_week: i32,
+    pub start_minute: i32,
+    pub end_minute: i32,
+    pub created_at: NaiveDateTime,
+}
+
+#[juniper::object]
+impl WorkingHour {
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    pub fn coach_id(&self) -> &str {
+        self.coach_id.as_str()
+    }
+
+    pub fn day_of_week(&self) -> i32 {
+        self.day_of_week
+    }
+
+    pub fn start_time(&self) -> String {
+        as_clock(self.start_minute)
+    }
+
+    pub fn end_time(&self) -> String {
+        as_clock(self.end_minute)
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        util::with_offset(self.created_at)
+    }
+}
+
+/**
+ * Whether the range (UTC) lies within the working hours of the coach (in the
+ * given zone). The ranges of a day and of the next one are merged where they
+ * meet, so a session may run across 12:00 from 09:00 - 12:00 into 12:00 -
+ * 17:00, or end at the midnight of a range till 24:00.
+ */
+pub fn covered_by(hours: &[WorkingHour], start_date: NaiveDateTime, end_date: NaiveDateTime, zone: Tz) -> bool {
+    let start_date = util::in_zone(start_date, zone).naive_local();
+    let end_date = util::in_zone(end_date, zone).naive_local();
+
+    let day = start_date.weekday().number_from_monday() as i32;
+    let next_day = day % 7 + 1;
+
+    // The minutes from the midnight the range starts on
+    let start = minute_of_day(start_date);
+    let end = (end_date.date() - start_date.date()).num_days() as i32 * MINUTES_IN_A_DAY + minute_of_day(end_date);
+
+    let mut ranges: Vec<(i32, i32)> = hours
+        .iter()
+        .filter_map(|hour| match hour.day_of_week {
+            d if d == day => Some((hour.start_minute, hour.end_minute)),
+            d if d == next_day => Some((hour.start_minute + MINUTES_IN_A_DAY, hour.end_minute + MINUTES_IN_A_DAY)),
+            _ => None,
+        })
+        .collect();
+    ranges.sort_unstable();
+
+    let mut merged: Vec<(i32, i32)> = Vec::new();
+    for (range_start, range_end) in ranges {
+        match merged.last_mut() {
+            Some(last) if range_start <= last.1 => last.1 = last.1.max(range_end),
+            _ => merged.push((range_start, range_end)),
+        }
+    }
+
+    merged.iter().any(|(range_start, range_end)| start >= *range_start && end <= *range_end)
+}
+
+/**
+ * A period (vacation, travel etc) when the coach is not available,
+ * regardless of the working hours.
+ */
+#[derive(Queryable, Debug, Clone)]
+pub struct Blackout {
+    pub id: String,
+    pub coach_id: String,
+    pub start_date: NaiveDateTime,
+    pub end_date: NaiveDateTime,
+    pub reason: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[juniper::object]
+impl Blackout {
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    pub fn start_date(&self) -> DateTime<Utc> {
+        util::with_offset(self.start_date)
+    }
+
+    pub fn end_date(&self) -> DateTime<Utc> {
+        util::with_offset(self.end_date)
+    }
+
+    pub fn reason(&self) -> Option<String> {
+        self.reason.clone()
+    }
+
+    pub fn coach_id(&self) -> &str {
+        self.coach_id.as_str()
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        util::with_offset(self.created_at)
+    }
+}
+
+impl Blackout {
+    pub fn overlaps(&self, start_date: NaiveDateTime, end_date: NaiveDateTime) -> bool {
+        self.start_date < end_date && start_date < self.end_date
+    }
+}
+
+pub struct Availability {
+    pub working_hours: Vec<WorkingHour>,
+    pub blackouts: Vec<Blackout>,
+}
+
+#[juniper::object]
+impl Availability {
+    pub fn working_hours(&self) -> &Vec<WorkingHour> {
+        &self.working_hours
+    }
+
+    pub fn blackouts(&self) -> &Vec<Blackout> {
+        &self.blackouts
+    }
+}
+
+/**
+ * A free slot common to the coach and the member. The lower the rank, the
+ * better the slot: a slot with a wider gap from the other engagements of the
+ * day ranks higher, the earlier one winning a tie.
+ */
+pub struct CandidateSlot {
+    pub start_date: NaiveDateTime,
+    pub end_date: NaiveDateTime,
+    pub rank: i32,
+}
+
+#[juniper::object]
+impl CandidateSlot {
+    pub fn start_date(&self) -> DateTime<Utc> {
+        util::with_offset(self.start_date)
+    }
+
+    pub fn end_date(&self) -> DateTime<Utc> {
+        util::with_offset(self.end_date)
+    }
+
+    pub fn rank(&self) -> i32 {
+        self.rank
+    }
+}
+
+#[derive(juniper::GraphQLInputObject)]
+pub struct WorkingHourInput {
+    pub day_of_week: i32,
+    pub start_time: String,
+    pub end_time: String,
+}
+
+/**
+ * The working hours are saved as a whole; the given hours replace the
+ * earlier ones. An empty list makes the coach available all the time.
+ */
+#[derive(juniper::GraphQLInputObject)]
+pub struct WorkingHoursRequest {
+    pub hours: Vec<WorkingHourInput>,
+}
+
+impl WorkingHoursRequest {
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors: Vec<ValidationError> = Vec::new();
+
+        for hour in &self.hours {
+            if hour.day_of_week < 1 || hour.day_of_week > 7 {
+                errors.push(ValidationError::new("day_of_week", "should be between 1 (Monday) and 7 (Sunday)."));
+            }
+
+            match (as_minute(hour.start_time.as_str()), as_minute(hour.end_time.as_str())) {
+                (Some(start), Some(end)) if start < end => {}
+                (Some(_), Some(_)) => errors.push(ValidationError::new("end_time", "should be after the start time.")),
+                _ => errors.push(ValidationError::new("start_time", "should be in HH:MM format.")),
+            }
+        }
+
+        errors
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "working_hours"]
+pub struct NewWorkingHour {
+    pub id: String,
+    pub coach_id: String,
+    pub day_of_week: i32,
+    pub start_minute: i32,
+    pub end_minute: i32,
+}
+
+impl NewWorkingHour {
+    pub fn from(input: &WorkingHourInput, the_coach_id: &str) -> NewWorkingHour {
+        let fuzzy_id = util::fuzzy_id();
+
+        NewWorkingHour {
+            id: fuzzy_id,
+            coach_id: the_coach_id.to_owned(),
+            day_of_week: input.day_of_week,
+            start_minute: as_minute(input.start_time.as_str()).unwrap_or(0),
+            end_minute: as_minute(input.end_time.as_str()).unwrap_or(0),
+        }
+    }
+}
+
+#[derive(juniper::GraphQLInputObject)]
+pub struct NewBlackoutRequest {
+    pub start_time: String,
+    pub end_time: String,
+    pub reason: Option<String>,
+}
+
+impl NewBlackoutRequest {
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors: Vec<ValidationError> = Vec::new();
+
+        let start_time = self.start_time.as_str();
+        let end_time = self.end_time.as_str();
+
+        if !util::is_valid_date(start_time) {
+            errors.push(ValidationError::new("start_time", "unparsable date."));
+        }
+
+        if !util::is_valid_date(end_time) {
+            errors.push(ValidationError::new("end_time", "unparsable date."));
+        }
+
+        if let (Ok(start_date), Ok(end_date)) = (util::as_date(start_time), util::as_date(end_time)) {
+            if end_date <= start_date {
+                errors.push(ValidationError::new("end_time", "should be after the start time."));
+            }
+        }
+
+        errors
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "blackouts"]
+pub struct NewBlackout {
+    pub id: String,
+    pub coach_id: String,
+    pub start_date: NaiveDateTime,
+    pub end_date: NaiveDateTime,
+    pub reason: Option<String>,
+}
+
+impl NewBlackout {
+    pub fn from(request: &NewBlackoutRequest, the_coach_id: &str) -> Result<NewBlackout, &'static str> {
+        let fuzzy_id = util::fuzzy_id();
+
+        Ok(NewBlackout {
+            id: fuzzy_id,
+            coach_id: the_coach_id.to_owned(),
+            start_date: util::as_date(request.start_time.as_str())?,
+            end_date: util::as_date(request.end_time.as_str())?,
+            reason: request.reason.clone(),
+        })
+    }
+}
+
+fn minute_of_day(date: NaiveDateTime) -> i32 {
+    (date.hour() * 60 + date.minute()) as i32
+}
+
+/**
+ * "09:30" is 570 minutes from the midnight; "24:00" marks the end of a day.
+ */
+fn as_minute(clock: &str) -> Option<i32> {
+    let mut parts = clock.trim().split(':');
+
+    let hour: i32 = parts.next()?.parse().ok()?;
+    let minute: i32 = parts.next()?.parse().ok()?;
+
+    if parts.next().is_some() || !(0..60).contains(&minute) {
+        return None;
+    }
+
+    let minutes = hour * 60 + minute;
+    if !(0..=MINUTES_IN_A_DAY).contains(&minutes) {
+        return None;
+    }
+
+    Some(minutes)
+}
+
+fn as_clock(minutes: i32) -> String {
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn hour(day_of_week: i32, start_minute: i32, end_minute: i32) -> WorkingHour {
+        WorkingHour {
+            id: util::fuzzy_id(),
+            coach_id: String::from("coach"),
+            day_of_week,
+            start_minute,
+            end_minute,
+            created_at: util::now(),
+        }
+    }
+
+    fn monday_morning() -> Vec<WorkingHour> {
+        vec![hour(1, 9 * 60, 12 * 60)]
+    }
+
+    #[test]
+    fn should_parse_clock_times() {
+        assert_eq!(Some(570), as_minute("09:30"));
+        assert_eq!(Some(MINUTES_IN_A_DAY), as_minute("24:00"));
+        assert_eq!(None, as_minute("9.30"));
+        assert_eq!(None, as_minute("10:75"));
+        assert_eq!("09:30", as_clock(570));
+    }
+
+    #[test]
+    fn should_cover_only_within_working_hours() {
+        let hours = monday_morning();
+
+        // 2021-02-22 is a Monday
+        assert!(covered_by(&hours, util::as_date("2021-02-22T09:00:00Z").unwrap(), util::as_date("2021-02-22T10:00:00Z").unwrap(), Tz::UTC));
+        assert!(!covered_by(&hours, util::as_date("2021-02-22T11:30:00Z").unwrap(), util::as_date("2021-02-22T12:30:00Z").unwrap(), Tz::UTC));
+        assert!(!covered_by(&hours, util::as_date("2021-02-23T09:00:00Z").unwrap(), util::as_date("2021-02-23T10:00:00Z").unwrap(), Tz::UTC));
+    }
+
+    #[test]
+    fn should_cover_in_the_zone_of_the_coach() {
+        let hours = monday_morning();
+        let zone = util::as_zone("Asia/Kolkata").unwrap();
+
+        // 09:00 - 10:00 in Kolkata is 03:30 - 04:30 UTC
+        assert!(covered_by(&hours, util::as_date("2021-02-22T03:30:00Z").unwrap(), util::as_date("2021-02-22T04:30:00Z").unwrap(), zone));
+        assert!(!covered_by(&hours, util::as_date("2021-02-22T09:00:00Z").unwrap(), util::as_date("2021-02-22T10:00:00Z").unwrap(), zone));
+    }
+
+    #[test]
+    fn should_cover_across_adjacent_ranges() {
+        let hours = vec![hour(1, 9 * 60, 12 * 60), hour(1, 12 * 60, 17 * 60)];
+
+        assert!(covered_by(&hours, util::as_date("2021-02-22T11:30:00Z").unwrap(), util::as_date("2021-02-22T12:30:00Z").unwrap(), Tz::UTC));
+        assert!(!covered_by(&hours, util::as_date("2021-02-22T16:30:00Z").unwrap(), util::as_date("2021-02-22T17:30:00Z").unwrap(), Tz::UTC));
+    }
+
+    #[test]
+    fn should_cover_till_the_midnight() {
+        let hours = vec![hour(1, 20 * 60, MINUTES_IN_A_DAY)];
+
+        assert!(covered_by(&hours, util::as_date("2021-02-22T23:00:00Z").unwrap(), util::as_date("2021-02-23T00:00:00Z").unwrap(), Tz::UTC));
+        assert!(!covered_by(&hours, util::as_date("2021-02-22T23:30:00Z").unwrap(), util::as_date("2021-02-23T00:30:00Z").unwrap(), Tz::UTC));
+
+        // On to the early hours of Tuesday, when those are working hours as well
+        let hours = vec![hour(1, 20 * 60, MINUTES_IN_A_DAY), hour(2, 0, 2 * 60)];
+        assert!(covered_by(&hours, util::as_date("2021-02-22T23:30:00Z").unwrap(), util::as_date("2021-02-23T00:30:00Z").unwrap(), Tz::UTC));
+    }
+}
@@ -0,0 +1,28 @@
+use crate::commons::util;
+use crate::schema::calendar_feeds;
+
+/**
+ * The calendar feed lets a user subscribe to the Ferris events from a
+ * calendar app, through a URL that carries a feed token in place of the
+ * login credentials. We persist only the digest of the token; a user may
+ * revoke the feed any time, which turns the shared URL useless.
+ */
+#[derive(Insertable)]
+#[table_name = "calendar_feeds"]
+pub struct NewCalendarFeed {
+    pub id: String,
+    pub user_id: String,
+    pub token_digest: String,
+}
+
+impl NewCalendarFeed {
+    pub fn from(the_user_id: &str, token: &str) -> NewCalendarFeed {
+        let fuzzy_id = util::fuzzy_id();
+
+        NewCalendarFeed {
+            id: fuzzy_id,
+            user_id: the_user_id.to_owned(),
+            token_digest: util::digest(token),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+use crate::commons::util;
+use crate::models::users::User;
+use crate::schema::coach_applications;
+
+pub const PENDING: &str = "pending";
+pub const APPROVED: &str = "approved";
+pub const REJECTED: &str = "rejected";
+
+/**
+ * A Member who wishes to offer programs applies to become a Coach.
+ * The application waits in the pending state till an admin approves
+ * or rejects it.
+ */
+#[derive(Queryable, Debug)]
+pub struct CoachApplication {
+    pub id: String,
+    pub user_id: String,
+    pub full_name: String,
+    pub email: String,
+    pub status: String,
+    pub reviewed_by_id: Option<String>,
+    pub reviewed_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[juniper::object(description = "The application of a member to become a Coach.")]
+impl CoachApplication {
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    pub fn user_id(&self) -> &str {
+        self.user_id.as_str()
+    }
+
+    pub fn name(&self) -> &str {
+        self.full_name.as_str()
+    }
+
+    pub fn email(&self) -> &str {
+        self.email.as_str()
+    }
+
+    pub fn status(&self) -> &str {
+        self.status.as_str()
+    }
+
+    pub fn reviewed_by_id(&self) -> Option<&str> {
+        self.reviewed_by_id.as_deref()
+    }
+
+    pub fn reviewed_at(&self) -> Option<DateTime<Utc>> {
+        self.reviewed_at.map(util::with_offset)
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        util::with_offset(self.created_at)
+    }
+
+    pub fn updated_at(&self) -> DateTime<Utc> {
+        util::with_offset(self.updated_at)
+    }
+}
+
+impl CoachApplication {
+    pub fn is_pending(&self) -> bool {
+        self.status == PENDING
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "coach_applications"]
+pub struct NewCoachApplication {
+    pub id: String,
+    pub user_id: String,
+    pub full_name: String,
+    pub email: String,
+    pub status: String,
+}
+
+impl NewCoachApplication {
+    pub fn from(user: &User) -> NewCoachApplication {
+        let fuzzy_id = util::fuzzy_id();
+
+        NewCoachApplication {
+            id: fuzzy_id,
+            user_id: user.id.to_owned(),
+            full_name: user.full_name.to_owned(),
+            email: user.email.to_owned(),
+            status: String::from(PENDING),
+        }
+    }
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(juniper::GraphQLEnum, PartialEq)]
+pub enum ApplicationTargetState {
+    APPROVE,
+    REJECT,
+}
+
+#[derive(juniper::GraphQLInputObject)]
+pub struct ReviewApplicationRequest {
+    pub id: String,
+    pub target_state: ApplicationTargetState,
+}
@@ -10,7 +10,6 @@ use crate::schema::users::dsl::*;
 
 #[derive(juniper::GraphQLInputObject)]
 pub struct CoachCriteria {
-    pub coach_id: String,
     pub program_id: Option<String>,
     pub desire: EnrollmentFilter,
 }
@@ -38,11 +37,11 @@ impl MemberRow {
 
 type EnrollmentType = (Enrollment, User, Program);
 
-pub fn get_coach_members(connection: &MysqlConnection, criteria: CoachCriteria) -> Result<Vec<MemberRow>, diesel::result::Error> {
+pub fn get_coach_members(connection: &MysqlConnection, the_coach_id: &str, criteria: CoachCriteria) -> Result<Vec<MemberRow>, diesel::result::Error> {
     let mut query = enrollments
         .inner_join(users)
         .inner_join(programs)
-        .filter(coach_id.eq(criteria.coach_id))
+        .filter(coach_id.eq(the_coach_id))
         .order_by(full_name.asc())
         .into_boxed();
 
@@ -1,5 +1,8 @@
 use chrono::NaiveDateTime;
 
+use crate::models::users::User;
+use crate::schema::coaches;
+
 #[derive(Queryable, Debug)]
 pub struct Coach {
     pub id: String,
@@ -37,3 +40,26 @@ impl Coach {
         self.token
     }
 }
+
+/**
+ * The id of the Coach is the id of the User, as find_coach_by_id expects.
+ */
+#[derive(Insertable)]
+#[table_name = "coaches"]
+pub struct NewCoach {
+    pub id: String,
+    pub user_id: String,
+    pub full_name: String,
+    pub email: String,
+}
+
+impl NewCoach {
+    pub fn from(user: &User) -> NewCoach {
+        NewCoach {
+            id: user.id.to_owned(),
+            user_id: user.id.to_owned(),
+            full_name: user.full_name.to_owned(),
+            email: user.email.to_owned(),
+        }
+    }
+}
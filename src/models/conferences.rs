@@ -2,7 +2,7 @@ use crate::commons::chassis::ValidationError;
 use crate::commons::util;
 use crate::schema::conferences;
 
-use chrono::{Duration, NaiveDateTime};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
 
 #[derive(Queryable,Identifiable,Debug)]
 pub struct Conference {
@@ -75,20 +75,20 @@ impl Conference {
         self.duration
     }
 
-    pub fn scheduleStart(&self) -> NaiveDateTime {
-        self.revised_start_date.unwrap_or(self.original_start_date)
+    pub fn scheduleStart(&self) -> DateTime<Utc> {
+        util::with_offset(self.revised_start_date.unwrap_or(self.original_start_date))
     }
 
-    pub fn scheduleEnd(&self) -> NaiveDateTime {
-        self.revised_end_date.unwrap_or(self.original_end_date)
+    pub fn scheduleEnd(&self) -> DateTime<Utc> {
+        util::with_offset(self.revised_end_date.unwrap_or(self.original_end_date))
     }
 
-    pub fn actualStart(&self) -> Option<NaiveDateTime> {
-        self.actual_start_date
+    pub fn actualStart(&self) -> Option<DateTime<Utc>> {
+        self.actual_start_date.map(util::with_offset)
     }
 
-    pub fn actualEnd(&self) -> Option<NaiveDateTime> {
-        self.actual_end_date
+    pub fn actualEnd(&self) -> Option<DateTime<Utc>> {
+        self.actual_end_date.map(util::with_offset)
     }
 
     pub fn closing_notes(&self) -> Option<String> {
@@ -113,10 +113,7 @@ impl NewConferenceRequest {
 
         if !util::is_valid_date(given_time) {
             errors.push(ValidationError::new("start_time", "unparsable date."));
-        }
-
-        let date = util::as_date(given_time);
-        if util::is_past_date(date) {
+        } else if util::as_date(given_time).is_ok_and(util::is_past_date) {
             errors.push(ValidationError::new("start_time", "should be a future date."));
         }
 
@@ -154,14 +151,14 @@ pub struct NewConference {
 }
 
 impl NewConference {
-    pub fn from(request: &NewConferenceRequest, people: String) -> NewConference {
-        let start_date = util::as_date(request.start_time.as_str());
+    pub fn from(request: &NewConferenceRequest, people: String) -> Result<NewConference, &'static str> {
+        let start_date = util::as_date(request.start_time.as_str())?;
         let duration = Duration::minutes(request.duration as i64);
         let end_date = start_date.checked_add_signed(duration);
 
         let fuzzy_id = util::fuzzy_id();
 
-        NewConference {
+        Ok(NewConference {
             id: fuzzy_id,
             name: request.name.to_owned(),
             description: request.description.to_owned(),
@@ -170,7 +167,7 @@ impl NewConference {
             duration: request.duration,
             original_start_date: start_date,
             original_end_date: end_date.unwrap_or(start_date),
-        }
+        })
     }
 }
 
@@ -1,8 +1,9 @@
-use serde::{Deserialize, Serialize};
-
 use chrono::NaiveDateTime;
+use chrono_tz::Tz;
 
 use crate::models::enrollments::ManagedEnrollmentRequest;
+use crate::models::session_series::SessionSeries;
+use crate::models::session_slots::SessionSlot;
 use crate::models::sessions::Session;
 use crate::models::users::User;
 use crate::models::programs::Program;
@@ -10,6 +11,7 @@ use crate::models::programs::Program;
 use crate::schema::correspondences;
 use crate::schema::mail_recipients;
 
+use crate::commons::ical::{Attendee, CalendarEvent, Method};
 use crate::commons::util;
 
 const SELF_ENROLLMENT_MESSAGE :&str = "The coach will schedule a meeting to discuss with you at the earliest. Alternatively, you can converse with the coach, if required, from the discussion option available from your enrolled program. Thank you."; 
@@ -18,8 +20,8 @@ const SELF_ENROLLMENT_MESSAGE :&str = "The coach will schedule a meeting to disc
 pub struct Correspondence {
     pub id: String,
     pub from_user_id: String,
-    pub program_id: String,
-    pub enrollment_id: String,
+    pub program_id: Option<String>,
+    pub enrollment_id: Option<String>,
     pub from_email: String,
     pub subject: String,
     pub content: Option<String>,
@@ -33,26 +35,43 @@ pub struct Correspondence {
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
     pub mail_type: String,
+    pub attempts: i32,
+    pub calendar: Option<String>,
 }
 
 const SCHEDULE_SENDER_ID: &str = "schedule@krscode.com";
 
 const OUT: &str = "out";
-const TO: &str = "to";
-const CC: &str = "cc";
+pub const TO: &str = "to";
+pub const CC: &str = "cc";
 
-const PENDING: &str = "pending";
+pub const PENDING: &str = "pending";
+pub const SENT: &str = "sent";
+pub const FAILED: &str = "failed";
 
 const NORMAL: &str = "normal";
 const EVENT: &str = "event";
+const PASSWORD_RESET: &str = "password_reset";
+const EMAIL_VERIFICATION: &str = "email_verification";
+
+/**
+ * The mails carrying a one-time link keep it only till they are delivered or
+ * given up on; the content is then replaced with REDACTED.
+ */
+pub const ONE_TIME_LINK_MAILS: [&str; 2] = [PASSWORD_RESET, EMAIL_VERIFICATION];
+pub const REDACTED: &str = "The one-time link of this mail is not kept after delivery.";
+
+const EMAIL_VERIFICATION_MESSAGE: &str = "Welcome! Please verify your email address by following the link below, so that you can login and enroll into the programs.";
+
+const PASSWORD_RESET_MESSAGE: &str = "We received a request to reset your password. Please use the link below to choose a new password. The link is valid for an hour and can be used only once. Kindly ignore this mail, if you have not requested for it.";
 
 #[derive(Insertable)]
 #[table_name = "correspondences"]
 pub struct MailOut {
     pub id: String,
     pub from_user_id: String,
-    pub program_id: String,
-    pub enrollment_id: String,
+    pub program_id: Option<String>,
+    pub enrollment_id: Option<String>,
     pub from_email: String,
     pub subject: String,
     pub content: Option<String>,
@@ -62,10 +81,11 @@ pub struct MailOut {
     pub error: String,
     pub to_send_on: NaiveDateTime,
     pub mail_type: String,
+    pub calendar: Option<String>,
 }
 
 impl MailOut {
-    fn new(from_user_id: String, program_id: String, enrollment_id: String, subject: String, content: String, mail_type: &str) -> MailOut {
+    fn new(from_user_id: String, program_id: Option<String>, enrollment_id: Option<String>, subject: String, content: String, mail_type: &str) -> MailOut {
         let fuzzy_id = util::fuzzy_id();
 
         MailOut {
@@ -82,14 +102,34 @@ impl MailOut {
             error: " ".to_owned(),
             to_send_on: util::now(),
             mail_type: mail_type.to_owned(),
+            calendar: None,
         }
     }
 
-    pub fn for_managed_enrollment(request: &ManagedEnrollmentRequest, enrollment_id: &str) -> MailOut {
+    /**
+     * The session mails carry the session as an iCalendar event, which is
+     * sent as a text/calendar part along with the content.
+     */
+    fn for_session_event(session: &Session, coach: &User, member: &User, content: String, method: Method) -> MailOut {
+        let mut mail_out = MailOut::new(
+            coach.id.to_owned(),
+            Some(session.program_id.to_owned()),
+            Some(session.enrollment_id.to_owned()),
+            session.name.to_owned(),
+            content,
+            EVENT,
+        );
+
+        mail_out.calendar = Some(session_event(session, coach, member, method).to_ics());
+
+        mail_out
+    }
+
+    pub fn for_managed_enrollment(request: &ManagedEnrollmentRequest, coach: &User, enrollment_id: &str) -> MailOut {
         MailOut::new(
-            request.coach_id.to_owned(),
-            request.program_id.to_owned(),
-            enrollment_id.to_owned(),
+            coach.id.to_owned(),
+            Some(request.program_id.to_owned()),
+            Some(enrollment_id.to_owned()),
             request.subject.to_owned(),
             request.message.to_owned(),
             NORMAL,
@@ -105,8 +145,8 @@ impl MailOut {
 
         MailOut::new(
             program.coach_id.to_owned(),
-            program.id.to_owned(),
-            enrollment_id.to_owned(),
+            Some(program.id.to_owned()),
+            Some(enrollment_id.to_owned()),
             subject,
             content,
             NORMAL,
@@ -114,28 +154,132 @@ impl MailOut {
     }
 
     pub fn for_new_session(session: &Session, coach: &User, member: &User) -> MailOut {
-        let content = FerrisEvent::new_session_event(session, coach, member);
+        let content = format!(
+            "{} has scheduled the session '{}' with you on {}.",
+            coach.full_name,
+            session.name,
+            as_readable(session.schedule_start(), session.schedule_end(), &[member.zone(), coach.zone()])
+        );
+
+        MailOut::for_session_event(session, coach, member, content, Method::Request)
+    }
 
+    /**
+     * The password reset mail is a system mail, not tied to any program
+     * or enrollment.
+     */
+    pub fn for_password_reset(user: &User, reset_link: &str) -> MailOut {
         MailOut::new(
-            coach.id.to_owned(),
-            session.program_id.to_owned(),
-            session.enrollment_id.to_owned(),
-            session.name.to_owned(),
-            content,
-            EVENT,
+            user.id.to_owned(),
+            None,
+            None,
+            String::from("Reset your password"),
+            format!("{} {}", PASSWORD_RESET_MESSAGE, reset_link),
+            PASSWORD_RESET,
+        )
+    }
+
+    pub fn for_email_verification(user: &User, verification_link: &str) -> MailOut {
+        MailOut::new(
+            user.id.to_owned(),
+            None,
+            None,
+            String::from("Verify your email address"),
+            format!("{} {}", EMAIL_VERIFICATION_MESSAGE, verification_link),
+            EMAIL_VERIFICATION,
         )
     }
 
     pub fn for_cancel_session(session: &Session, coach: &User, member: &User) -> MailOut {
-        let content = FerrisEvent::cancel_event(session, coach, member);
+        let content = format!(
+            "The session '{}' scheduled on {} has been cancelled. {}",
+            session.name,
+            as_readable(session.schedule_start(), session.schedule_end(), &[member.zone(), coach.zone()]),
+            session.closing_notes.clone().unwrap_or_default()
+        );
+
+        MailOut::for_session_event(session, coach, member, content, Method::Cancel)
+    }
+
+    /**
+     * The request goes to the coach, so the slots are in the zone of the coach.
+     */
+    pub fn for_session_request(session: &Session, member: &User, coach: &User, slots: &[SessionSlot]) -> MailOut {
+        let offered: Vec<String> = slots.iter().map(|slot| as_readable(slot.start_date, slot.end_date, &[coach.zone()])).collect();
+
+        let content = format!(
+            "{} has requested the session '{}' in one of the following slots: {}. Please accept a slot or decline the request.",
+            member.full_name,
+            session.name,
+            offered.join("; ")
+        );
+
+        MailOut::new(
+            member.id.to_owned(),
+            Some(session.program_id.to_owned()),
+            Some(session.enrollment_id.to_owned()),
+            format!("Session Request: {}", session.name),
+            content,
+            NORMAL,
+        )
+    }
+
+    pub fn for_declined_session(session: &Session, coach: &User) -> MailOut {
+        let content = format!(
+            "{} has declined your request for the session '{}'. {}",
+            coach.full_name,
+            session.name,
+            session.closing_notes.clone().unwrap_or_default()
+        );
 
         MailOut::new(
             coach.id.to_owned(),
-            session.program_id.to_owned(),
-            session.enrollment_id.to_owned(),
-            session.name.to_owned(),
+            Some(session.program_id.to_owned()),
+            Some(session.enrollment_id.to_owned()),
+            format!("Session Request Declined: {}", session.name),
             content,
-            EVENT,
+            NORMAL,
+        )
+    }
+
+    pub fn for_reschedule_session(session: &Session, coach: &User, member: &User, reason: Option<&str>) -> MailOut {
+        let content = format!(
+            "The session '{}' has been rescheduled to {}. {}",
+            session.name,
+            as_readable(session.schedule_start(), session.schedule_end(), &[member.zone(), coach.zone()]),
+            reason.unwrap_or_default()
+        );
+
+        MailOut::for_session_event(session, coach, member, content, Method::Request)
+    }
+
+    pub fn for_new_series(series: &SessionSeries, coach: &User, member: &User, sessions: &[Session]) -> MailOut {
+        let lead = format!("{} has scheduled the series '{}' with you, of {} sessions:", coach.full_name, series.name, sessions.len());
+
+        MailOut::for_series(series, coach, member, lead, sessions)
+    }
+
+    pub fn for_revised_series(series: &SessionSeries, coach: &User, member: &User, sessions: &[Session]) -> MailOut {
+        let lead = format!("{} has revised the series '{}'. The following sessions are scheduled from now on:", coach.full_name, series.name);
+
+        MailOut::for_series(series, coach, member, lead, sessions)
+    }
+
+    /**
+     * A series goes out as a single mail listing its sessions; the sessions
+     * reach the calendars through invitations of their own.
+     */
+    fn for_series(series: &SessionSeries, coach: &User, member: &User, lead: String, sessions: &[Session]) -> MailOut {
+        let zones = [member.zone(), coach.zone()];
+        let schedule: Vec<String> = sessions.iter().map(|session| as_readable(session.schedule_start(), session.schedule_end(), &zones)).collect();
+
+        MailOut::new(
+            coach.id.to_owned(),
+            Some(series.program_id.to_owned()),
+            Some(series.enrollment_id.to_owned()),
+            format!("Session Series: {}", series.name),
+            format!("{} {}.", lead, schedule.join("; ")),
+            NORMAL,
         )
     }
 }
@@ -171,48 +315,17 @@ impl MailRecipient {
 
         vec![to_record, cc_record]
     }
-}
-
-#[derive(juniper::GraphQLInputObject)]
-pub struct MailCriteria {
-    pub status: String,
-    pub in_out: String,
-}
-
-#[juniper::object]
-impl Correspondence {
-    pub fn id(&self) -> &str {
-        self.id.as_str()
-    }
-
-    pub fn from_email(&self) -> &str {
-        self.from_email.as_str()
-    }
-
-    pub fn subject(&self) -> &str {
-        self.subject.as_str()
-    }
-
-    pub fn content(&self) -> &str {
-        match &self.content {
-            Some(c) => c.as_str(),
-            None => " ",
-        }
-    }
-
-    pub fn mail_type(&self) -> &str {
-        self.mail_type.as_str()
-    }
-}
 
-#[juniper::object]
-impl MailRecipient {
-    pub fn to_type(&self) -> &str {
-        self.to_type.as_str()
-    }
+    pub fn build_recipient(user: &User, correspondence_id: &str) -> Vec<MailRecipient> {
+        let to_record = MailRecipient {
+            id: util::fuzzy_id(),
+            correspondence_id: correspondence_id.to_owned(),
+            to_user_id: Some(user.id.to_owned()),
+            to_email: user.email.to_owned(),
+            to_type: TO.to_owned(),
+        };
 
-    pub fn to_email(&self) -> &str {
-        self.to_email.as_str()
+        vec![to_record]
     }
 }
 
@@ -221,67 +334,68 @@ pub struct Mailable {
     pub receipients: Vec<MailRecipient>,
 }
 
-#[juniper::object]
-impl Mailable {
-    pub fn correspondence(&self) -> &Correspondence {
-        &self.correspondence
+/**
+ * The schedule as seen in each of the (distinct) time zones of the recipients,
+ * say "Mon, 22 Feb 2021 15:30 - 16:30 IST / Mon, 22 Feb 2021 11:00 - 12:00 CET".
+ */
+pub fn as_readable(start_date: NaiveDateTime, end_date: NaiveDateTime, zones: &[Tz]) -> String {
+    let mut distinct_zones: Vec<Tz> = Vec::new();
+    for zone in zones {
+        if !distinct_zones.contains(zone) {
+            distinct_zones.push(*zone);
+        }
     }
 
-    pub fn receipients(&self) -> &Vec<MailRecipient> {
-        &self.receipients
-    }
+    distinct_zones
+        .iter()
+        .map(|zone| {
+            let start = util::in_zone(start_date, *zone);
+            let end = util::in_zone(end_date, *zone);
+
+            if start.date() == end.date() {
+                format!("{} - {}", start.format("%a, %d %b %Y %H:%M"), end.format("%H:%M %Z"))
+            } else {
+                format!("{} - {}", start.format("%a, %d %b %Y %H:%M"), end.format("%a, %d %b %Y %H:%M %Z"))
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" / ")
 }
 
-#[allow(non_snake_case)]
-#[derive(Serialize, Deserialize, Debug)]
-pub struct FerrisEvent {
-    pub id: String,
-    pub description: Option<String>,
-    pub organizer: Option<String>,
-    pub attendee: Option<String>,
-    pub sequence: i32,
-    pub startDate: String,
-    pub endDate: String,
-    pub status: String,
-    pub method: String,
+fn session_event(session: &Session, coach: &User, member: &User, method: Method) -> CalendarEvent {
+    CalendarEvent {
+        uid: session.id.to_owned(),
+        sequence: session.sequence,
+        summary: session.name.to_owned(),
+        description: session.description.clone(),
+        organizer: Some(Attendee {
+            name: coach.full_name.to_owned(),
+            email: coach.email.to_owned(),
+        }),
+        attendees: vec![Attendee {
+            name: member.full_name.to_owned(),
+            email: member.email.to_owned(),
+        }],
+        start: session.schedule_start(),
+        end: session.schedule_end(),
+        method,
+    }
 }
 
-impl FerrisEvent {
-    fn new_session_event(session: &Session, coach: &User, member: &User) -> String {
-        let start_date = session.original_start_date;
-        let end_date = session.original_end_date;
-
-        let event = FerrisEvent {
-            id: session.id.to_owned(),
-            sequence: 1,
-            organizer: Some(coach.email.clone()),
-            attendee: Some(member.email.clone()),
-            description: session.description.clone(),
-            startDate: util::format_time(&start_date),
-            endDate: util::format_time(&end_date),
-            status: "CONFIRMED".to_owned(),
-            method: "REQUEST".to_owned(),
-        };
+#[cfg(test)]
+mod tests {
 
-        serde_json::to_string(&event).unwrap_or_else(|_|String::from(""))
-    }
+    use super::*;
 
-    fn cancel_event(session: &Session, coach: &User, member: &User) -> String {
-        let start_date = session.original_start_date;
-        let end_date = session.original_end_date;
-
-        let event = FerrisEvent {
-            id: session.id.to_owned(),
-            sequence: 99,
-            organizer: Some(coach.email.clone()),
-            attendee: Some(member.email.clone()),
-            description: session.closing_notes.clone(),
-            startDate: util::format_time(&start_date),
-            endDate: util::format_time(&end_date),
-            status: "CANCELLED".to_owned(),
-            method: "CANCEL".to_owned(),
-        };
+    #[test]
+    fn should_read_the_schedule_in_each_zone() {
+        let start_date = util::as_date("2021-02-22T10:00:00Z").unwrap();
+        let end_date = util::as_date("2021-02-22T11:00:00Z").unwrap();
+
+        let kolkata = util::as_zone("Asia/Kolkata").unwrap();
+        let paris = util::as_zone("Europe/Paris").unwrap();
 
-        serde_json::to_string(&event).unwrap_or_else(|_| String::from(""))
+        assert_eq!("Mon, 22 Feb 2021 15:30 - 16:30 IST / Mon, 22 Feb 2021 11:00 - 12:00 CET", as_readable(start_date, end_date, &[kolkata, paris]));
+        assert_eq!("Mon, 22 Feb 2021 10:00 - 11:00 UTC", as_readable(start_date, end_date, &[Tz::UTC, Tz::UTC]));
     }
 }
@@ -4,7 +4,9 @@ use crate::models::discussions::NewDiscussionRequest;
 
 use crate::models::users::User;
 
-use chrono::NaiveDateTime;
+use crate::commons::util;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
 
 #[derive(Queryable, Debug)]
 pub struct Feed {
@@ -28,8 +30,8 @@ impl Feed {
         self.id.as_str()
     }
 
-    pub fn created_at(&self) -> NaiveDateTime {
-        self.created_at
+    pub fn created_at(&self) -> DateTime<Utc> {
+        util::with_offset(self.created_at)
     }
 
     pub fn to_id(&self) -> &str {
@@ -1,7 +1,9 @@
 use crate::schema::discussions;
 
 use crate::commons::util;
-use chrono::NaiveDateTime;
+use crate::models::programs::Program;
+use crate::models::users::User;
+use chrono::{DateTime, NaiveDateTime, Utc};
 
 #[derive(Queryable, Debug)]
 pub struct Discussion {
@@ -31,8 +33,8 @@ impl Discussion {
         self.description.as_str()
     }
 
-    pub fn created_at(&self) -> NaiveDateTime {
-        self.created_at
+    pub fn created_at(&self) -> DateTime<Utc> {
+        util::with_offset(self.created_at)
     }
 }
 
@@ -40,7 +42,6 @@ impl Discussion {
 pub struct NewDiscussionRequest {
     pub enrollment_id: String,
     pub to_id: String,
-    pub created_by_id: String,
     pub description: String,
     pub program_id: String,
     pub program_name: String,
@@ -50,6 +51,27 @@ pub struct NewDiscussionRequest {
     pub member_name: String 
 }
 
+impl NewDiscussionRequest {
+    /**
+     * A notice that the platform posts into the discussion of an enrollment
+     * on behalf of the coach or the member, so that it reaches the pending
+     * feed of the other.
+     */
+    pub fn notice(program: &Program, enrollment_id: &str, coach: &User, member: &User, to_id: &str, description: String) -> NewDiscussionRequest {
+        NewDiscussionRequest {
+            enrollment_id: enrollment_id.to_owned(),
+            to_id: to_id.to_owned(),
+            description,
+            program_id: program.id.to_owned(),
+            program_name: program.name.to_owned(),
+            coach_id: coach.id.to_owned(),
+            coach_name: coach.full_name.to_owned(),
+            member_id: member.id.to_owned(),
+            member_name: member.full_name.to_owned(),
+        }
+    }
+}
+
 #[derive(Insertable)]
 #[table_name = "discussions"]
 pub struct NewDiscussion {
@@ -60,13 +82,13 @@ pub struct NewDiscussion {
 }
 
 impl NewDiscussion {
-    pub fn from(request: &NewDiscussionRequest) -> NewDiscussion {
+    pub fn from(request: &NewDiscussionRequest, created_by_id: &str) -> NewDiscussion {
         let fuzzy_id = util::fuzzy_id();
 
         NewDiscussion {
             id: fuzzy_id,
             enrollment_id: request.enrollment_id.to_owned(),
-            created_by_id: request.created_by_id.to_owned(),
+            created_by_id: created_by_id.to_owned(),
             description: request.description.to_owned(),
         }
     }
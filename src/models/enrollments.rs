@@ -1,4 +1,4 @@
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDateTime, Utc};
 
 use crate::models::programs::Program;
 use crate::models::users::User;
@@ -32,16 +32,14 @@ impl Enrollment {
     pub fn is_new(&self) -> bool {
         self.is_new
     }
-    pub fn created_at(&self) -> &NaiveDateTime {
-        &self.created_at
+    pub fn created_at(&self) -> DateTime<Utc> {
+        util::with_offset(self.created_at)
     }
 }
 
 #[derive(juniper::GraphQLInputObject)]
 pub struct NewEnrollmentRequest {
     pub program_id: String,
-    pub user_id: String,
-    pub coach_id: String,
 }
 
 impl NewEnrollmentRequest {
@@ -52,14 +50,6 @@ impl NewEnrollmentRequest {
             errors.push(ValidationError::new("program_id", "The Program id is invalid."));
         }
 
-        if self.user_id.trim().is_empty() {
-            errors.push(ValidationError::new("user_id", "The User id is invalid."));
-        }
-
-        if self.coach_id.trim().is_empty() {
-            errors.push(ValidationError::new("coach_id", "We need the coach id who offers the program."));
-        }
-
         errors
     }
 }
@@ -103,7 +93,6 @@ impl NewEnrollment {
 #[derive(juniper::GraphQLInputObject)]
 pub struct ManagedEnrollmentRequest {
     pub program_id: String,
-    pub coach_id: String,
     pub member_mail: String,
     pub subject: String,
     pub message: String
@@ -28,6 +28,9 @@ impl Ferror {
     pub fn push(&mut self,field:&str,message: &str) {
         self.errors.push(ValidationError::new(field,message));
     }
+    pub fn push_error(&mut self, error: ValidationError) {
+        self.errors.push(error);
+    }
     pub fn is_empty(&self) -> bool {
         self.errors.is_empty()
     }
@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::commons::chassis::ValidationError;
+use crate::commons::util;
+use crate::models::master_plans::{MasterTaskLink, NewMasterTaskLink};
+use crate::models::master_tasks::{MasterTask, NewMasterTask, TaskType};
+use crate::schema::master_plan_revisions;
+
+/**
+ * A revision keeps the plan (its tasks and links) as it was saved, so that
+ * the coach may go back to it later. The snapshot is a JSON document.
+ */
+#[derive(Queryable, Debug)]
+pub struct MasterPlanRevision {
+    pub id: String,
+    pub master_plan_id: String,
+    pub version: i32,
+    pub snapshot: String,
+    pub saved_by_id: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[juniper::object]
+impl MasterPlanRevision {
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    pub fn master_plan_id(&self) -> &str {
+        self.master_plan_id.as_str()
+    }
+
+    pub fn version(&self) -> i32 {
+        self.version
+    }
+
+    pub fn saved_by_id(&self) -> &str {
+        self.saved_by_id.as_str()
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        util::with_offset(self.created_at)
+    }
+
+    pub fn task_count(&self) -> i32 {
+        self.as_snapshot().map_or(0, |snapshot| snapshot.tasks.len() as i32)
+    }
+
+    pub fn link_count(&self) -> i32 {
+        self.as_snapshot().map_or(0, |snapshot| snapshot.links.len() as i32)
+    }
+}
+
+impl MasterPlanRevision {
+    pub fn as_snapshot(&self) -> Option<PlanSnapshot> {
+        serde_json::from_str(self.snapshot.as_str()).ok()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PlanSnapshot {
+    pub tasks: Vec<TaskSnapshot>,
+    pub links: Vec<LinkSnapshot>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TaskSnapshot {
+    pub id: String,
+    pub abstract_task_id: String,
+    pub duration: i32,
+    pub min: i32,
+    pub max: i32,
+    pub task_type: String,
+    pub coach_id: String,
+    pub role_id: String,
+    pub coordinates: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LinkSnapshot {
+    pub source_task_id: String,
+    pub target_task_id: String,
+    pub lead_time: i32,
+    pub coordinates: String,
+    pub priority: i32,
+    pub is_forward: bool,
+}
+
+impl TaskSnapshot {
+    pub fn of(task: &MasterTask) -> TaskSnapshot {
+        TaskSnapshot {
+            id: task.id.to_owned(),
+            abstract_task_id: task.abstract_task_id.to_owned(),
+            duration: task.duration,
+            min: task.min,
+            max: task.max,
+            task_type: task.task_type.to_owned(),
+            coach_id: task.coach_id.to_owned(),
+            role_id: task.role_id.to_owned(),
+            coordinates: task.coordinates.to_owned(),
+        }
+    }
+
+    pub fn is_a(&self, task_type: TaskType) -> bool {
+        self.task_type.eq_ignore_ascii_case(task_type.as_str())
+    }
+}
+
+impl PlanSnapshot {
+    pub fn of(plan_tasks: &[MasterTask], plan_links: &[MasterTaskLink]) -> PlanSnapshot {
+        let tasks = plan_tasks.iter().map(TaskSnapshot::of).collect();
+
+        let links = plan_links
+            .iter()
+            .map(|link| LinkSnapshot {
+                source_task_id: link.source_task_id.to_owned(),
+                target_task_id: link.target_task_id.to_owned(),
+                lead_time: link.lead_time,
+                coordinates: link.coordinates.to_owned(),
+                priority: link.priority,
+                is_forward: link.is_forward,
+            })
+            .collect();
+
+        PlanSnapshot { tasks, links }
+    }
+
+    /**
+     * The same plan for another coach: the tasks get fresh ids (with the
+     * links following them) and the abstract tasks are swapped as per the
+     * given map, for those the coach already has or got as copies.
+     */
+    pub fn rekeyed(&self, the_coach_id: &str, abstract_ids: &HashMap<String, String>) -> PlanSnapshot {
+        let task_ids: HashMap<&str, String> = self.tasks.iter().map(|task| (task.id.as_str(), util::fuzzy_id())).collect();
+        let new_id = |old_id: &String| task_ids.get(old_id.as_str()).cloned().unwrap_or_else(|| old_id.to_owned());
+
+        let tasks = self
+            .tasks
+            .iter()
+            .map(|task| TaskSnapshot {
+                id: new_id(&task.id),
+                abstract_task_id: abstract_ids.get(&task.abstract_task_id).cloned().unwrap_or_else(|| task.abstract_task_id.to_owned()),
+                duration: task.duration,
+                min: task.min,
+                max: task.max,
+                task_type: task.task_type.to_owned(),
+                coach_id: the_coach_id.to_owned(),
+                role_id: task.role_id.to_owned(),
+                coordinates: task.coordinates.to_owned(),
+            })
+            .collect();
+
+        let links = self
+            .links
+            .iter()
+            .map(|link| LinkSnapshot {
+                source_task_id: new_id(&link.source_task_id),
+                target_task_id: new_id(&link.target_task_id),
+                lead_time: link.lead_time,
+                coordinates: link.coordinates.to_owned(),
+                priority: link.priority,
+                is_forward: link.is_forward,
+            })
+            .collect();
+
+        PlanSnapshot { tasks, links }
+    }
+
+    pub fn as_new_tasks(&self, the_master_plan_id: &str) -> Vec<NewMasterTask> {
+        self.tasks
+            .iter()
+            .map(|task| NewMasterTask {
+                id: task.id.to_owned(),
+                master_plan_id: the_master_plan_id.to_owned(),
+                abstract_task_id: task.abstract_task_id.to_owned(),
+                duration: task.duration,
+                min: task.min,
+                max: task.max,
+                task_type: task.task_type.to_owned(),
+                coach_id: task.coach_id.to_owned(),
+                role_id: task.role_id.to_owned(),
+                coordinates: task.coordinates.to_owned(),
+            })
+            .collect()
+    }
+
+    pub fn as_new_links(&self, the_master_plan_id: &str) -> Vec<NewMasterTaskLink> {
+        self.links
+            .iter()
+            .map(|link| NewMasterTaskLink {
+                id: util::fuzzy_id(),
+                master_plan_id: the_master_plan_id.to_owned(),
+                source_task_id: link.source_task_id.to_owned(),
+                target_task_id: link.target_task_id.to_owned(),
+                lead_time: link.lead_time,
+                coordinates: link.coordinates.to_owned(),
+                priority: link.priority,
+                is_forward: link.is_forward,
+            })
+            .collect()
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "master_plan_revisions"]
+pub struct NewMasterPlanRevision {
+    pub id: String,
+    pub master_plan_id: String,
+    pub version: i32,
+    pub snapshot: String,
+    pub saved_by_id: String,
+}
+
+impl NewMasterPlanRevision {
+    pub fn from(the_master_plan_id: &str, version: i32, snapshot: &PlanSnapshot, the_saved_by_id: &str) -> NewMasterPlanRevision {
+        let fuzzy_id = util::fuzzy_id();
+
+        NewMasterPlanRevision {
+            id: fuzzy_id,
+            master_plan_id: the_master_plan_id.to_owned(),
+            version,
+            snapshot: serde_json::to_string(snapshot).unwrap_or_default(),
+            saved_by_id: the_saved_by_id.to_owned(),
+        }
+    }
+}
+
+/**
+ * Restores the plan to a revision; like a save, it carries the version of
+ * the plan it was based on.
+ */
+#[derive(juniper::GraphQLInputObject)]
+pub struct RestoreRevisionRequest {
+    pub master_plan_id: String,
+    pub revision_id: String,
+    pub version: i32,
+}
+
+impl RestoreRevisionRequest {
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors: Vec<ValidationError> = Vec::new();
+
+        if self.master_plan_id.trim().is_empty() {
+            errors.push(ValidationError::new("master_plan_id", "Master Plan Id is a must."));
+        }
+
+        if self.revision_id.trim().is_empty() {
+            errors.push(ValidationError::new("revision_id", "Revision Id is a must."));
+        }
+
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: &str, abstract_task_id: &str) -> TaskSnapshot {
+        TaskSnapshot {
+            id: id.to_owned(),
+            abstract_task_id: abstract_task_id.to_owned(),
+            duration: 8,
+            min: 4,
+            max: 16,
+            task_type: String::from("TASK"),
+            coach_id: String::from("owner"),
+            role_id: String::from("member"),
+            coordinates: String::from("{}"),
+        }
+    }
+
+    fn link(source_task_id: &str, target_task_id: &str) -> LinkSnapshot {
+        LinkSnapshot {
+            source_task_id: source_task_id.to_owned(),
+            target_task_id: target_task_id.to_owned(),
+            lead_time: 2,
+            coordinates: String::from("{}"),
+            priority: 1,
+            is_forward: true,
+        }
+    }
+
+    #[test]
+    fn rekeyed_plan_keeps_its_shape() {
+        let snapshot = PlanSnapshot {
+            tasks: vec![task("a", "write"), task("b", "review")],
+            links: vec![link("a", "b")],
+        };
+
+        let mut abstract_ids = HashMap::new();
+        abstract_ids.insert(String::from("write"), String::from("peer-write"));
+
+        let copy = snapshot.rekeyed("peer", &abstract_ids);
+
+        assert!(copy.tasks.iter().all(|task| task.id != "a" && task.id != "b"));
+        assert!(copy.tasks.iter().all(|task| task.coach_id == "peer"));
+        assert_eq!(copy.tasks[0].abstract_task_id, "peer-write");
+        assert_eq!(copy.tasks[1].abstract_task_id, "review");
+        assert_eq!(copy.links[0].source_task_id, copy.tasks[0].id);
+        assert_eq!(copy.links[0].target_task_id, copy.tasks[1].id);
+        assert_eq!(copy.links[0].lead_time, 2);
+    }
+}
@@ -0,0 +1,91 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+use crate::commons::chassis::ValidationError;
+use crate::commons::util;
+use crate::schema::master_plan_shares;
+
+/**
+ * A master plan published to the coaches of a parent program: the coach of
+ * the parent program and all its peer coaches may use the plan, say apply or
+ * clone it, but only the coach who drafted it may change it.
+ */
+#[derive(Queryable, Debug)]
+pub struct MasterPlanShare {
+    pub id: String,
+    pub master_plan_id: String,
+    pub program_id: String,
+    pub shared_by_id: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[juniper::object]
+impl MasterPlanShare {
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    pub fn master_plan_id(&self) -> &str {
+        self.master_plan_id.as_str()
+    }
+
+    #[graphql(description = "The parent program whose coaches may use the plan")]
+    pub fn program_id(&self) -> &str {
+        self.program_id.as_str()
+    }
+
+    pub fn shared_by_id(&self) -> &str {
+        self.shared_by_id.as_str()
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        util::with_offset(self.created_at)
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "master_plan_shares"]
+pub struct NewMasterPlanShare {
+    pub id: String,
+    pub master_plan_id: String,
+    pub program_id: String,
+    pub shared_by_id: String,
+}
+
+impl NewMasterPlanShare {
+    pub fn from(the_master_plan_id: &str, the_program_id: &str, the_shared_by_id: &str) -> NewMasterPlanShare {
+        let fuzzy_id = util::fuzzy_id();
+
+        NewMasterPlanShare {
+            id: fuzzy_id,
+            master_plan_id: the_master_plan_id.to_owned(),
+            program_id: the_program_id.to_owned(),
+            shared_by_id: the_shared_by_id.to_owned(),
+        }
+    }
+}
+
+/**
+ * The program may be the parent program or any of its peer programs; the
+ * plan is shared with the parent program either way.
+ */
+#[derive(juniper::GraphQLInputObject)]
+pub struct SharePlanRequest {
+    pub master_plan_id: String,
+    pub program_id: String,
+}
+
+impl SharePlanRequest {
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors: Vec<ValidationError> = Vec::new();
+
+        if self.master_plan_id.trim().is_empty() {
+            errors.push(ValidationError::new("master_plan_id", "Master Plan Id is a must."));
+        }
+
+        if self.program_id.trim().is_empty() {
+            errors.push(ValidationError::new("program_id", "Program Id is a must."));
+        }
+
+        errors
+    }
+}
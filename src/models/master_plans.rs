@@ -1,5 +1,10 @@
+use std::collections::VecDeque;
+
 use crate::commons::chassis::ValidationError;
+use crate::commons::scheduler::{self, Activity, Dependency};
 use crate::commons::util;
+use crate::models::master_plan_revisions::{LinkSnapshot, TaskSnapshot};
+use crate::models::master_tasks::{MasterTask, TaskType};
 use crate::schema::master_plans;
 use crate::schema::master_task_links;
 
@@ -9,6 +14,7 @@ pub struct MasterPlan {
     pub name: String,
     pub description: String,
     pub coach_id: String,
+    pub version: i32,
 }
 
 #[juniper::object]
@@ -28,13 +34,17 @@ impl MasterPlan {
     pub fn coach_id(&self) -> &str {
         self.coach_id.as_str()
     }
+
+    #[graphql(description = "The version to send along with the next save of the plan")]
+    pub fn version(&self) -> i32 {
+        self.version
+    }
 }
 
 #[derive(juniper::GraphQLInputObject)]
 pub struct NewMasterPlanRequest {
     pub name: String,
     pub description: String,
-    pub coach_id: String,
 }
 
 impl NewMasterPlanRequest {
@@ -49,10 +59,6 @@ impl NewMasterPlanRequest {
             errors.push(ValidationError::new("description", "Description is a must."));
         }
 
-        if self.coach_id.trim().is_empty() {
-            errors.push(ValidationError::new("coach_id", "Coach is a must."));
-        }
-
         errors
     }
 }
@@ -67,26 +73,108 @@ pub struct NewMasterPlan {
 }
 
 impl NewMasterPlan {
-    pub fn from(request: &NewMasterPlanRequest) -> NewMasterPlan {
+    pub fn from(request: &NewMasterPlanRequest, coach_id: &str) -> NewMasterPlan {
         let fuzzy_id = util::fuzzy_id();
 
         NewMasterPlan {
             id: fuzzy_id,
             name: request.name.to_owned(),
-            coach_id: request.coach_id.to_owned(),
+            coach_id: coach_id.to_owned(),
             description: request.description.to_owned(),
         }
     }
+
+    pub fn copy_of(plan: &MasterPlan, coach_id: &str) -> NewMasterPlan {
+        NewMasterPlan {
+            id: util::fuzzy_id(),
+            name: plan.name.to_owned(),
+            coach_id: coach_id.to_owned(),
+            description: plan.description.to_owned(),
+        }
+    }
 }
 
+/**
+ * A deep copy of the plan for the target coach, who may be the caller or a
+ * coach the plan is shared with.
+ */
 #[derive(juniper::GraphQLInputObject)]
-pub struct MasterPlanCriteria {
-    pub coach_id: String,
+pub struct ClonePlanRequest {
+    pub master_plan_id: String,
+    pub target_coach_id: String,
+}
+
+impl ClonePlanRequest {
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors: Vec<ValidationError> = Vec::new();
+
+        if self.master_plan_id.trim().is_empty() {
+            errors.push(ValidationError::new("master_plan_id", "Master Plan Id is a must."));
+        }
+
+        if self.target_coach_id.trim().is_empty() {
+            errors.push(ValidationError::new("target_coach_id", "Target Coach Id is a must."));
+        }
+
+        errors
+    }
+}
+
+#[derive(Queryable, Debug)]
+pub struct MasterTaskLink {
+    pub id: String,
+    pub source_task_id: String,
+    pub target_task_id: String,
+    pub lead_time: i32,
+    pub coordinates: String,
+    pub priority: i32,
+    pub is_forward: bool,
+    pub master_plan_id: String,
 }
 
+/**
+ * Applies the plan to an enrollment, as the concrete tasks of the member,
+ * beginning at the given date.
+ */
+#[derive(juniper::GraphQLInputObject)]
+pub struct ApplyMasterPlanRequest {
+    pub enrollment_id: String,
+    pub master_plan_id: String,
+    pub start_date: String,
+}
+
+impl ApplyMasterPlanRequest {
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors: Vec<ValidationError> = Vec::new();
+
+        if self.enrollment_id.trim().is_empty() {
+            errors.push(ValidationError::new("enrollment_id", "Enrollment Id is a must."));
+        }
+
+        if self.master_plan_id.trim().is_empty() {
+            errors.push(ValidationError::new("master_plan_id", "Master Plan Id is a must."));
+        }
+
+        let given_time = self.start_date.as_str();
+
+        if !util::is_valid_date(given_time) {
+            errors.push(ValidationError::new("start_date", "unparsable date."));
+        } else if util::as_date(given_time).is_ok_and(util::is_in_past) {
+            errors.push(ValidationError::new("start_date", "should be a future date."));
+        }
+
+        errors
+    }
+}
+
+/**
+ * The save carries the version of the plan it was based on; the save fails
+ * when someone else saved the plan in the meantime.
+ */
 #[derive(juniper::GraphQLInputObject)]
 pub struct UpdateMasterPlanRequest {
     pub master_plan_id: String,
+    pub version: i32,
     pub tasks: Vec<TaskUnit>,
     pub links: Vec<LinkUnit>,
 }
@@ -104,9 +192,31 @@ pub struct LinkUnit {
     pub coordinates: String,
     pub priority: i32,
     pub is_forward: bool,
+    /** In hours; none for no lead time. */
+    pub lead_time: Option<i32>,
 }
 
 impl UpdateMasterPlanRequest {
+    /**
+     * The tasks of the save should be those of the plan, and together with
+     * the links they should make a sound graph (see validate_graph).
+     */
+    pub fn validate_graph(&self, plan_tasks: &[MasterTask]) -> Vec<ValidationError> {
+        let mut errors: Vec<ValidationError> = Vec::new();
+
+        let strangers: Vec<String> = self.tasks.iter().filter(|task| !plan_tasks.iter().any(|p| p.id == task.id)).map(|task| task.id.to_owned()).collect();
+        if !strangers.is_empty() {
+            errors.push(ValidationError::with_ids("tasks", "should belong to the plan.", strangers));
+        }
+
+        let nodes: Vec<TaskSnapshot> = plan_tasks.iter().filter(|p| self.tasks.iter().any(|task| task.id == p.id)).map(TaskSnapshot::of).collect();
+        let links: Vec<LinkSnapshot> = self.links.iter().map(LinkUnit::as_snapshot).collect();
+
+        errors.extend(validate_graph(&nodes, &links));
+
+        errors
+    }
+
     pub fn as_master_task_links(&self) -> Vec<NewMasterTaskLink> {
         let mut new_links = Vec::new();
 
@@ -119,6 +229,133 @@ impl UpdateMasterPlanRequest {
     }
 }
 
+impl LinkUnit {
+    pub fn as_snapshot(&self) -> LinkSnapshot {
+        LinkSnapshot {
+            source_task_id: self.source_id.to_owned(),
+            target_task_id: self.target_id.to_owned(),
+            lead_time: self.lead_time.unwrap_or(0),
+            coordinates: self.coordinates.to_owned(),
+            priority: self.priority,
+            is_forward: self.is_forward,
+        }
+    }
+}
+
+/**
+ * The plan should make a sound graph: the links stay within the tasks, a
+ * single START and a single STOP task, every task on a path from the START
+ * to the STOP (over the forward links), no cycle among the forward links,
+ * and at least two ways out of a DECISION.
+ */
+pub fn validate_graph(nodes: &[TaskSnapshot], plan_links: &[LinkSnapshot]) -> Vec<ValidationError> {
+    let mut errors: Vec<ValidationError> = Vec::new();
+
+    let is_node = |the_id: &str| nodes.iter().any(|node| node.id == the_id);
+
+    for link in plan_links {
+        if link.source_task_id == link.target_task_id {
+            errors.push(ValidationError::with_ids("links", "should not loop back into the same task.", vec![link.source_task_id.to_owned()]));
+        } else if !is_node(link.source_task_id.as_str()) || !is_node(link.target_task_id.as_str()) {
+            errors.push(ValidationError::with_ids("links", "should join the tasks of the plan.", vec![link.source_task_id.to_owned(), link.target_task_id.to_owned()]));
+        }
+    }
+
+    let links: Vec<&LinkSnapshot> = plan_links
+        .iter()
+        .filter(|link| link.source_task_id != link.target_task_id && is_node(link.source_task_id.as_str()) && is_node(link.target_task_id.as_str()))
+        .collect();
+
+    let starts = ids_of(nodes, TaskType::START);
+    if starts.len() != 1 {
+        errors.push(ValidationError::with_ids("tasks", "should have a single START task.", starts.clone()));
+    }
+
+    let stops = ids_of(nodes, TaskType::STOP);
+    if stops.len() != 1 {
+        errors.push(ValidationError::with_ids("tasks", "should have a single STOP task.", stops.clone()));
+    }
+
+    for decision in ids_of(nodes, TaskType::DECISION) {
+        if links.iter().filter(|link| link.source_task_id == decision).count() < 2 {
+            errors.push(ValidationError::with_ids("tasks", "should have at least two links out of a DECISION.", vec![decision]));
+        }
+    }
+
+    let forward_links: Vec<&LinkSnapshot> = links.iter().filter(|link| link.is_forward).copied().collect();
+
+    let activities: Vec<Activity> = nodes
+        .iter()
+        .map(|node| Activity {
+            id: node.id.to_owned(),
+            duration: node.duration as i64,
+            not_before: None,
+        })
+        .collect();
+
+    let dependencies: Vec<Dependency> = forward_links
+        .iter()
+        .map(|link| Dependency {
+            source_id: link.source_task_id.to_owned(),
+            target_id: link.target_task_id.to_owned(),
+            lead_time: 0,
+        })
+        .collect();
+
+    let cyclic: Vec<String> = scheduler::cyclic_activities(&activities, &dependencies)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|i| activities[i].id.to_owned())
+        .collect();
+
+    if !cyclic.is_empty() {
+        errors.push(ValidationError::with_ids("links", "should not form a cycle of forward links.", cyclic));
+    }
+
+    if let ([start], [stop]) = (starts.as_slice(), stops.as_slice()) {
+        let from_start = reachable(start, &forward_links, |link| (&link.source_task_id, &link.target_task_id));
+        let to_stop = reachable(stop, &forward_links, |link| (&link.target_task_id, &link.source_task_id));
+
+        let unreachable: Vec<String> = nodes.iter().filter(|node| !from_start.contains(&&node.id)).map(|node| node.id.to_owned()).collect();
+        if !unreachable.is_empty() {
+            errors.push(ValidationError::with_ids("tasks", "should be reachable from the START task.", unreachable));
+        }
+
+        let dead_ends: Vec<String> = nodes.iter().filter(|node| !to_stop.contains(&&node.id)).map(|node| node.id.to_owned()).collect();
+        if !dead_ends.is_empty() {
+            errors.push(ValidationError::with_ids("tasks", "should lead to the STOP task.", dead_ends));
+        }
+    }
+
+    errors
+}
+
+fn ids_of(nodes: &[TaskSnapshot], task_type: TaskType) -> Vec<String> {
+    nodes.iter().filter(|node| node.is_a(task_type)).map(|node| node.id.to_owned()).collect()
+}
+
+/**
+ * The ids reachable from the given one, following the links along the
+ * given direction (from, to).
+ */
+fn reachable<'a>(the_id: &'a String, links: &[&'a LinkSnapshot], direction: impl Fn(&'a LinkSnapshot) -> (&'a String, &'a String)) -> Vec<&'a String> {
+    let mut visited: Vec<&String> = vec![the_id];
+    let mut pending: VecDeque<&String> = VecDeque::from(vec![the_id]);
+
+    while let Some(current) = pending.pop_front() {
+        for link in links {
+            let (from, to) = direction(link);
+
+            if from == current && !visited.contains(&to) {
+                visited.push(to);
+                pending.push_back(to);
+            }
+        }
+    }
+
+    visited
+}
+
 #[derive(Insertable)]
 #[table_name = "master_task_links"]
 pub struct NewMasterTaskLink {
@@ -126,6 +363,7 @@ pub struct NewMasterTaskLink {
     pub master_plan_id: String,
     pub source_task_id: String,
     pub target_task_id: String,
+    pub lead_time: i32,
     pub coordinates: String,
     pub priority: i32,
     pub is_forward: bool,
@@ -140,9 +378,125 @@ impl NewMasterTaskLink {
             master_plan_id: plan_id.to_owned(),
             source_task_id: link.source_id.to_owned(),
             target_task_id: link.target_id.to_owned(),
+            lead_time: link.lead_time.unwrap_or(0),
             coordinates: link.coordinates.to_owned(),
             priority: link.priority,
             is_forward: link.is_forward,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn task(id: &str, task_type: &str) -> MasterTask {
+        MasterTask {
+            id: String::from(id),
+            master_plan_id: String::from("plan"),
+            abstract_task_id: String::from("abstract"),
+            duration: 1,
+            min: 1,
+            max: 1,
+            task_type: String::from(task_type),
+            created_at: util::now(),
+            updated_at: util::now(),
+            coach_id: String::from("coach"),
+            role_id: String::from("member"),
+            coordinates: String::new(),
+        }
+    }
+
+    fn link(source_id: &str, target_id: &str, is_forward: bool) -> LinkUnit {
+        LinkUnit {
+            source_id: String::from(source_id),
+            target_id: String::from(target_id),
+            coordinates: String::new(),
+            priority: 1,
+            is_forward,
+            lead_time: None,
+        }
+    }
+
+    fn request(ids: &[&str], links: Vec<LinkUnit>) -> UpdateMasterPlanRequest {
+        UpdateMasterPlanRequest {
+            master_plan_id: String::from("plan"),
+            version: 0,
+            tasks: ids
+                .iter()
+                .map(|id| TaskUnit {
+                    id: String::from(*id),
+                    coordinates: String::new(),
+                })
+                .collect(),
+            links,
+        }
+    }
+
+    fn plan() -> Vec<MasterTask> {
+        vec![task("start", "start"), task("ask", "decision"), task("yes", "activity"), task("no", "activity"), task("stop", "stop")]
+    }
+
+    #[test]
+    fn should_accept_a_sound_plan() {
+        // A rework loop (backward link) from "no" back to "ask" is fine
+        let links = vec![
+            link("start", "ask", true),
+            link("ask", "yes", true),
+            link("ask", "no", true),
+            link("yes", "stop", true),
+            link("no", "stop", true),
+            link("no", "ask", false),
+        ];
+
+        assert!(request(&["start", "ask", "yes", "no", "stop"], links).validate_graph(&plan()).is_empty());
+    }
+
+    #[test]
+    fn should_reject_a_broken_plan() {
+        let links = vec![
+            link("start", "ask", true),
+            link("ask", "yes", true),
+            link("yes", "ask", true),
+            link("yes", "yes", true),
+            link("yes", "elsewhere", true),
+            link("yes", "stop", true),
+        ];
+
+        let errors = request(&["start", "ask", "yes", "no", "stop", "elsewhere"], links).validate_graph(&plan());
+        let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+
+        assert!(messages.contains(&"should belong to the plan."));
+        assert!(messages.contains(&"should not loop back into the same task."));
+        assert!(messages.contains(&"should join the tasks of the plan."));
+        assert!(messages.contains(&"should have at least two links out of a DECISION."));
+        assert!(messages.contains(&"should not form a cycle of forward links."));
+
+        let unreachable = errors.iter().find(|e| e.message == "should be reachable from the START task.").unwrap();
+        assert_eq!(vec!["no"], unreachable.ids);
+    }
+
+    #[test]
+    fn should_have_a_single_start_and_stop() {
+        let errors = request(&["ask", "yes", "no"], Vec::new()).validate_graph(&plan());
+
+        assert!(errors.iter().any(|e| e.message == "should have a single START task."));
+        assert!(errors.iter().any(|e| e.message == "should have a single STOP task."));
+    }
+
+    #[test]
+    fn should_validate_the_snapshot_of_a_plan() {
+        // As an imported plan comes: no saved tasks to check against, only the snapshot
+        let nodes: Vec<TaskSnapshot> = plan().iter().map(TaskSnapshot::of).collect();
+        let links: Vec<LinkSnapshot> = [link("start", "ask", true), link("ask", "yes", true), link("ask", "no", true), link("yes", "stop", true)]
+            .iter()
+            .map(LinkUnit::as_snapshot)
+            .collect();
+
+        let errors = validate_graph(&nodes, &links);
+
+        let dead_ends = errors.iter().find(|e| e.message == "should lead to the STOP task.").unwrap();
+        assert_eq!(vec!["no"], dead_ends.ids);
+    }
+}
@@ -2,7 +2,7 @@ use crate::commons::chassis::ValidationError;
 use crate::commons::util;
 use crate::schema::master_tasks;
 
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDateTime, Utc};
 
 #[derive(Queryable, Debug, Identifiable)]
 pub struct MasterTask {
@@ -20,8 +20,8 @@ pub struct MasterTask {
     pub coordinates: String,
 }
 
-#[derive(juniper::GraphQLEnum)]
-enum TaskType {
+#[derive(juniper::GraphQLEnum, Clone, Copy, PartialEq)]
+pub enum TaskType {
     START,
     STOP,
     DECISION,
@@ -30,6 +30,26 @@ enum TaskType {
     PROCEDURE,
 }
 
+impl TaskType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TaskType::START => "START",
+            TaskType::STOP => "STOP",
+            TaskType::DECISION => "DECISION",
+            TaskType::ACTIVITY => "ACTIVITY",
+            TaskType::BUFFER => "BUFFER",
+            TaskType::PROCEDURE => "PROCEDURE",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<TaskType> {
+        [TaskType::START, TaskType::STOP, TaskType::DECISION, TaskType::ACTIVITY, TaskType::BUFFER, TaskType::PROCEDURE]
+            .iter()
+            .copied()
+            .find(|task_type| task_type.as_str() == name)
+    }
+}
+
 #[juniper::object]
 impl MasterTask {
     pub fn id(&self) -> &str {
@@ -56,8 +76,8 @@ impl MasterTask {
         self.max
     }
 
-    pub fn createdAt(&self) -> NaiveDateTime {
-        self.created_at
+    pub fn createdAt(&self) -> DateTime<Utc> {
+        util::with_offset(self.created_at)
     }
 
     pub fn coach_id(&self) -> &str {
@@ -77,15 +97,25 @@ impl MasterTask {
     }
 }
 
+impl MasterTask {
+    pub fn is_a(&self, task_type: TaskType) -> bool {
+        self.task_type.eq_ignore_ascii_case(task_type.as_str())
+    }
+}
+
+/**
+ * Adding a task is a save of the plan; it carries the version of the plan
+ * it was based on.
+ */
 #[derive(juniper::GraphQLInputObject)]
 pub struct NewMasterTaskRequest {
     pub master_plan_id: String,
+    pub version: i32,
     pub abstract_task_id: String,
     pub duration: i32,
     pub min: i32,
     pub max: i32,
     pub task_type: String,
-    pub coach_id: String,
     pub role_id: String,
     pub coordinates: String,
 }
@@ -106,9 +136,7 @@ impl NewMasterTaskRequest {
             errors.push(ValidationError::new("duration", "should be a minimum of 1 minute."));
         }
 
-        if self.coach_id.trim().is_empty() {
-            errors.push(ValidationError::new("coach_id", "Coach Id is a must"));
-        }
+        errors.extend(validate_estimates(self.min, self.duration, self.max));
 
         if self.role_id.trim().is_empty() {
             errors.push(ValidationError::new("role_id", "Role Id is a must"));
@@ -129,6 +157,8 @@ pub struct NewMasterTask {
     pub master_plan_id: String,
     pub abstract_task_id: String,
     pub duration: i32,
+    pub min: i32,
+    pub max: i32,
     pub task_type: String,
     pub coach_id: String,
     pub role_id: String,
@@ -136,7 +166,7 @@ pub struct NewMasterTask {
 }
 
 impl NewMasterTask {
-    pub fn from(request: &NewMasterTaskRequest) -> NewMasterTask {
+    pub fn from(request: &NewMasterTaskRequest, coach_id: &str) -> NewMasterTask {
         let fuzzy_id = util::fuzzy_id();
 
         NewMasterTask {
@@ -144,8 +174,10 @@ impl NewMasterTask {
             master_plan_id: request.master_plan_id.to_owned(),
             abstract_task_id: request.abstract_task_id.to_owned(),
             duration: request.duration,
+            min: request.min,
+            max: request.max,
             task_type: request.task_type.to_owned(),
-            coach_id: request.coach_id.to_owned(),
+            coach_id: coach_id.to_owned(),
             role_id: request.role_id.to_owned(),
             coordinates: request.coordinates.to_owned(),
         }
@@ -163,9 +195,14 @@ pub struct UpdateMasterTask {
     pub coordinates: String,
 }
 
+/**
+ * Editing a task is a save of its plan; it carries the version of the plan
+ * it was based on.
+ */
 #[derive(juniper::GraphQLInputObject)]
 pub struct UpdateMasterTaskRequest {
     pub id: String,
+    pub version: i32,
     pub duration: i32,
     pub min: i32,
     pub max: i32,
@@ -190,11 +227,49 @@ impl UpdateMasterTaskRequest {
             errors.push(ValidationError::new("duration", "should be a minimum of 1 minute."));
         }
 
+        errors.extend(validate_estimates(self.min, self.duration, self.max));
+
         errors
     }
 }
 
+/**
+ * The min and the max are the optimistic and the pessimistic estimates of
+ * the duration (PERT), so the duration lies between them.
+ */
+pub fn validate_estimates(min: i32, duration: i32, max: i32) -> Vec<ValidationError> {
+    let mut errors: Vec<ValidationError> = Vec::new();
+
+    if min <= 0 {
+        errors.push(ValidationError::new("min", "should be a minimum of 1."));
+    }
+
+    if min > duration {
+        errors.push(ValidationError::new("min", "should not be more than the duration."));
+    }
+
+    if max < duration {
+        errors.push(ValidationError::new("max", "should not be less than the duration."));
+    }
+
+    errors
+}
+
 #[derive(juniper::GraphQLInputObject)]
 pub struct MasterTaskCriteria {
     pub master_plan_id: String,
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn should_keep_the_duration_within_the_estimates() {
+        assert!(validate_estimates(2, 4, 9).is_empty());
+        assert!(validate_estimates(4, 4, 4).is_empty());
+        assert_eq!("min", validate_estimates(5, 4, 9)[0].field);
+        assert_eq!("max", validate_estimates(2, 4, 3)[0].field);
+    }
+}
@@ -1,15 +1,28 @@
 pub mod abstract_tasks;
+pub mod availabilities;
+pub mod calendar_feeds;
+pub mod coach_applications;
 pub mod coaches;
 pub mod enrollments;
+pub mod master_plan_revisions;
+pub mod master_plan_shares;
 pub mod master_plans;
 pub mod master_tasks;
 pub mod notes;
 pub mod objectives;
 pub mod observations;
 pub mod options;
+pub mod password_resets;
+pub mod plan_documents;
+pub mod program_plans;
 pub mod programs;
+pub mod schedules;
+pub mod session_series;
+pub mod session_slots;
 pub mod session_users;
 pub mod sessions;
+pub mod task_links;
+pub mod task_moves;
 pub mod tasks;
 pub mod user_events;
 pub mod user_programs;
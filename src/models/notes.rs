@@ -5,7 +5,7 @@ use crate::schema::session_notes;
 
 use crate::commons::chassis::ValidationError;
 use crate::commons::util;
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDateTime, Utc};
 
 #[derive(Queryable, Debug)]
 pub struct Note {
@@ -34,11 +34,11 @@ impl Note {
     pub fn is_private(&self) -> bool {
         self.is_private
     }
-    pub fn remind_at(&self) -> Option<NaiveDateTime> {
-        self.remind_at
+    pub fn remind_at(&self) -> Option<DateTime<Utc>> {
+        self.remind_at.map(util::with_offset)
     }
-    pub fn updated_at(&self) -> NaiveDateTime {
-        self.updated_at
+    pub fn updated_at(&self) -> DateTime<Utc> {
+        util::with_offset(self.updated_at)
     }
 }
 
@@ -70,6 +70,12 @@ impl NewNoteRequest {
             errors.push(ValidationError::new("desciption", "Description of the note is a must."));
         }
 
+        if let Some(value) = &self.remind_at {
+            if !util::is_valid_date(value.as_str()) {
+                errors.push(ValidationError::new("remind_at", "unparsable date."));
+            }
+        }
+
         errors
     }
 }
@@ -86,10 +92,10 @@ pub struct NewNote {
 }
 
 impl NewNote {
-    pub fn from(request: &NewNoteRequest, session_user: SessionUser) -> NewNote {
+    pub fn from(request: &NewNoteRequest, session_user: SessionUser) -> Result<NewNote, &'static str> {
         let remind_at = match &request.remind_at {
             Some(value) => {
-                let date = util::as_date(value.as_str());
+                let date = util::as_date(value.as_str())?;
                 Some(date)
             }
             None => None,
@@ -97,14 +103,14 @@ impl NewNote {
 
         let fuzzy_id = util::fuzzy_id();
 
-        NewNote {
+        Ok(NewNote {
             id: fuzzy_id,
             session_id: session_user.session_id,
             created_by_id: session_user.user_id,
             description: request.description.to_owned(),
             session_user_id: session_user.id,
             remind_at,
-        }
+        })
     }
 }
 
@@ -2,7 +2,7 @@ use crate::commons::chassis::ValidationError;
 use crate::commons::util;
 use crate::schema::objectives;
 
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDateTime, Utc};
 
 #[derive(Queryable, Debug, Identifiable)]
 pub struct Objective {
@@ -44,16 +44,16 @@ impl Objective {
         self.duration
     }
 
-    pub fn scheduleStart(&self) -> NaiveDateTime {
-        self.revised_start_date.unwrap_or(self.original_start_date)
+    pub fn scheduleStart(&self) -> DateTime<Utc> {
+        util::with_offset(self.revised_start_date.unwrap_or(self.original_start_date))
     }
 
-    pub fn scheduleEnd(&self) -> NaiveDateTime {
-        self.revised_end_date.unwrap_or(self.original_end_date)
+    pub fn scheduleEnd(&self) -> DateTime<Utc> {
+        util::with_offset(self.revised_end_date.unwrap_or(self.original_end_date))
     }
 
-    pub fn createdAt(&self) -> NaiveDateTime {
-        self.created_at
+    pub fn createdAt(&self) -> DateTime<Utc> {
+        util::with_offset(self.created_at)
     }
 
     pub fn status(&self) -> Status {
@@ -105,19 +105,13 @@ impl UpdateObjectiveRequest {
 
         if !util::is_valid_date(given_start_time) {
             errors.push(ValidationError::new("start_time", "unparsable date."));
-        }
-
-        let date = util::as_date(given_start_time);
-        if util::is_in_past(date) {
+        } else if util::as_date(given_start_time).is_ok_and(util::is_in_past) {
             errors.push(ValidationError::new("start_time", "should be a future date."));
         }
 
         if !util::is_valid_date(given_end_time) {
             errors.push(ValidationError::new("end_time", "unparsable date."));
-        }
-
-        let date = util::as_date(given_end_time);
-        if util::is_in_past(date) {
+        } else if util::as_date(given_end_time).is_ok_and(util::is_in_past) {
             errors.push(ValidationError::new("end_time", "should be a future date."));
         }
 
@@ -146,19 +140,13 @@ impl NewObjectiveRequest {
 
         if !util::is_valid_date(given_start_time) {
             errors.push(ValidationError::new("start_time", "unparsable date."));
-        }
-
-        let date = util::as_date(given_start_time);
-        if util::is_in_past(date) {
+        } else if util::as_date(given_start_time).is_ok_and(util::is_in_past) {
             errors.push(ValidationError::new("start_time", "should be a future date."));
         }
 
         if !util::is_valid_date(given_end_time) {
             errors.push(ValidationError::new("end_time", "unparsable date."));
-        }
-
-        let date = util::as_date(given_end_time);
-        if util::is_in_past(date) {
+        } else if util::as_date(given_end_time).is_ok_and(util::is_in_past) {
             errors.push(ValidationError::new("end_time", "should be a future date."));
         }
 
@@ -183,20 +171,20 @@ pub struct NewObjective {
 }
 
 impl NewObjective {
-    pub fn from(request: &NewObjectiveRequest) -> NewObjective {
-        let start_date = util::as_date(request.start_time.as_str());
-        let end_date = util::as_date(request.end_time.as_str());
+    pub fn from(request: &NewObjectiveRequest) -> Result<NewObjective, &'static str> {
+        let start_date = util::as_date(request.start_time.as_str())?;
+        let end_date = util::as_date(request.end_time.as_str())?;
 
         let fuzzy_id = util::fuzzy_id();
 
-        NewObjective {
+        Ok(NewObjective {
             id: fuzzy_id,
             enrollment_id: request.enrollment_id.to_owned(),
             duration: 1,
             original_start_date: start_date,
             original_end_date: end_date,
             description: request.description.to_owned(),
-        }
+        })
     }
 }
 
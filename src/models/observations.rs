@@ -2,7 +2,7 @@ use crate::commons::chassis::ValidationError;
 use crate::commons::util;
 use crate::schema::observations;
 
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDateTime, Utc};
 
 #[derive(Queryable, Debug, Identifiable)]
 pub struct Observation {
@@ -31,8 +31,8 @@ impl Observation {
         value
     }
 
-    pub fn createdAt(&self) -> NaiveDateTime {
-        self.created_at
+    pub fn createdAt(&self) -> DateTime<Utc> {
+        util::with_offset(self.created_at)
     }
 }
 
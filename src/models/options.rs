@@ -2,7 +2,7 @@ use crate::commons::chassis::ValidationError;
 use crate::commons::util;
 use crate::schema::options;
 
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDateTime, Utc};
 
 #[derive(Queryable, Debug)]
 pub struct Constraint {
@@ -31,8 +31,8 @@ impl Constraint {
         value
     }
 
-    pub fn createdAt(&self) -> NaiveDateTime {
-        self.created_at
+    pub fn createdAt(&self) -> DateTime<Utc> {
+        util::with_offset(self.created_at)
     }
 }
 
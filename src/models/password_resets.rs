@@ -0,0 +1,71 @@
+use chrono::{Duration, NaiveDateTime};
+
+use crate::commons::chassis::ValidationError;
+use crate::commons::util;
+use crate::models::users::User;
+use crate::schema::password_resets;
+
+const VALIDITY_IN_MINUTES: i64 = 60;
+
+/**
+ * A request to reset a forgotten password. We persist only the digest of
+ * the token that was mailed out, which can be used once before it expires.
+ * The reset is looked up by that digest, so it is read without it.
+ */
+#[derive(Queryable, Debug)]
+pub struct PasswordReset {
+    pub id: String,
+    pub user_id: String,
+    pub expires_at: NaiveDateTime,
+    pub used_at: Option<NaiveDateTime>,
+}
+
+impl PasswordReset {
+    pub fn is_usable(&self) -> bool {
+        self.used_at.is_none() && self.expires_at > util::now()
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "password_resets"]
+pub struct NewPasswordReset {
+    pub id: String,
+    pub user_id: String,
+    pub token_digest: String,
+    pub expires_at: NaiveDateTime,
+}
+
+impl NewPasswordReset {
+    pub fn from(user: &User, token: &str) -> NewPasswordReset {
+        let fuzzy_id = util::fuzzy_id();
+
+        NewPasswordReset {
+            id: fuzzy_id,
+            user_id: user.id.to_owned(),
+            token_digest: util::digest(token),
+            expires_at: util::now() + Duration::minutes(VALIDITY_IN_MINUTES),
+        }
+    }
+}
+
+#[derive(juniper::GraphQLInputObject)]
+pub struct CompletePasswordResetRequest {
+    pub token: String,
+    pub new_password: String,
+}
+
+impl CompletePasswordResetRequest {
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors: Vec<ValidationError> = Vec::new();
+
+        if self.token.trim().is_empty() {
+            errors.push(ValidationError::new("token", "The reset token is a must."));
+        }
+
+        if self.new_password.trim().is_empty() {
+            errors.push(ValidationError::new("new_password", "New password cannot be blank."));
+        }
+
+        errors
+    }
+}
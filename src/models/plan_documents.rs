@@ -0,0 +1,290 @@
+use serde::{Deserialize, Serialize};
+
+use crate::commons::chassis::ValidationError;
+use crate::models::abstract_tasks::AbstractTask;
+use crate::models::master_plan_revisions::{LinkSnapshot, PlanSnapshot, TaskSnapshot};
+use crate::models::master_plans::{MasterPlan, MasterTaskLink};
+use crate::models::master_tasks::{validate_estimates, MasterTask, TaskType};
+
+pub const FORMAT: &str = "ferris/master-plan";
+pub const FORMAT_VERSION: i32 = 1;
+
+/**
+ * The portable form of a master plan, to move it between instances or to
+ * keep it under version control. A document looks like:
+ *
+ * {
+ *   "format": "ferris/master-plan",
+ *   "version": 1,
+ *   "name": "Onboarding",
+ *   "description": "The first four weeks",
+ *   "roles": ["coach", "member"],
+ *   "abstract_tasks": [{ "id": "t1", "name": "Kick-off" }],
+ *   "tasks": [{ "id": "n1", "abstract_task_id": "t1", "task_type": "START", "role_id": "coach",
+ *               "duration": 2, "min": 1, "max": 4, "coordinates": "{}" }],
+ *   "links": [{ "source_task_id": "n1", "target_task_id": "n2", "lead_time": 0,
+ *               "coordinates": "{}", "priority": 1, "is_forward": true }]
+ * }
+ *
+ * The ids are local to the document; the import gives every record a fresh
+ * id. Durations and lead times are in hours. A format change that old
+ * readers can not take bumps the version.
+ */
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PlanDocument {
+    pub format: String,
+    pub version: i32,
+    pub name: String,
+    pub description: String,
+    pub roles: Vec<String>,
+    pub abstract_tasks: Vec<AbstractTaskEntry>,
+    pub tasks: Vec<TaskEntry>,
+    pub links: Vec<LinkEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AbstractTaskEntry {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TaskEntry {
+    pub id: String,
+    pub abstract_task_id: String,
+    pub task_type: String,
+    pub role_id: String,
+    pub duration: i32,
+    pub min: i32,
+    pub max: i32,
+    pub coordinates: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LinkEntry {
+    pub source_task_id: String,
+    pub target_task_id: String,
+    pub lead_time: i32,
+    pub coordinates: String,
+    pub priority: i32,
+    pub is_forward: bool,
+}
+
+impl PlanDocument {
+    /**
+     * The estimates of the older tasks may lack a minimum or a maximum; the
+     * document widens them to take in the duration, as the forecast does.
+     */
+    pub fn of(plan: &MasterPlan, plan_tasks: &[MasterTask], plan_links: &[MasterTaskLink], plan_abstract_tasks: &[AbstractTask]) -> PlanDocument {
+        let mut roles: Vec<String> = plan_tasks.iter().map(|task| task.role_id.to_owned()).collect();
+        roles.sort();
+        roles.dedup();
+
+        let abstract_tasks = plan_abstract_tasks
+            .iter()
+            .map(|abstract_task| AbstractTaskEntry {
+                id: abstract_task.id.to_owned(),
+                name: abstract_task.name.to_owned(),
+            })
+            .collect();
+
+        let tasks = plan_tasks
+            .iter()
+            .map(|task| TaskEntry {
+                id: task.id.to_owned(),
+                abstract_task_id: task.abstract_task_id.to_owned(),
+                task_type: task.task_type.to_owned(),
+                role_id: task.role_id.to_owned(),
+                duration: task.duration,
+                min: task.min.max(1).min(task.duration),
+                max: task.max.max(task.duration),
+                coordinates: task.coordinates.to_owned(),
+            })
+            .collect();
+
+        let links = plan_links
+            .iter()
+            .map(|link| LinkEntry {
+                source_task_id: link.source_task_id.to_owned(),
+                target_task_id: link.target_task_id.to_owned(),
+                lead_time: link.lead_time,
+                coordinates: link.coordinates.to_owned(),
+                priority: link.priority,
+                is_forward: link.is_forward,
+            })
+            .collect();
+
+        PlanDocument {
+            format: String::from(FORMAT),
+            version: FORMAT_VERSION,
+            name: plan.name.to_owned(),
+            description: plan.description.to_owned(),
+            roles,
+            abstract_tasks,
+            tasks,
+            links,
+        }
+    }
+
+    pub fn parse(json: &str) -> Result<PlanDocument, ValidationError> {
+        serde_json::from_str(json).map_err(|e| ValidationError::new("document", format!("is not a plan document: {}", e).as_str()))
+    }
+
+    /**
+     * The document should be of a format we read, and hang together: the
+     * tasks refer to the abstract tasks and the roles of the document, and
+     * the links to its tasks. The graph itself is checked only when the
+     * coach saves the plan, for a draft may well be exported.
+     */
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors: Vec<ValidationError> = Vec::new();
+
+        if self.format != FORMAT {
+            errors.push(ValidationError::new("format", "should be ferris/master-plan."));
+        }
+
+        if self.version != FORMAT_VERSION {
+            errors.push(ValidationError::new("version", "is not a version we can read."));
+        }
+
+        if self.name.trim().is_empty() {
+            errors.push(ValidationError::new("name", "Name is a must."));
+        }
+
+        let mut task_ids: Vec<&str> = self.tasks.iter().map(|task| task.id.as_str()).collect();
+        task_ids.sort_unstable();
+        let count = task_ids.len();
+        task_ids.dedup();
+        if task_ids.len() != count {
+            errors.push(ValidationError::new("tasks", "should have unique ids."));
+        }
+
+        for task in &self.tasks {
+            let ids = vec![task.id.to_owned()];
+
+            if !self.abstract_tasks.iter().any(|abstract_task| abstract_task.id == task.abstract_task_id) {
+                errors.push(ValidationError::with_ids("tasks", "should refer to an abstract task of the document.", ids.clone()));
+            }
+
+            if !self.roles.contains(&task.role_id) {
+                errors.push(ValidationError::with_ids("tasks", "should refer to a role of the document.", ids.clone()));
+            }
+
+            if TaskType::parse(task.task_type.as_str()).is_none() {
+                errors.push(ValidationError::with_ids("tasks", "has an unknown task type.", ids.clone()));
+            }
+
+            for error in validate_estimates(task.min, task.duration, task.max) {
+                errors.push(ValidationError::with_ids(error.field.as_str(), error.message.as_str(), ids.clone()));
+            }
+        }
+
+        let dangling: Vec<String> = self
+            .links
+            .iter()
+            .filter(|link| task_ids.binary_search(&link.source_task_id.as_str()).is_err() || task_ids.binary_search(&link.target_task_id.as_str()).is_err())
+            .map(|link| format!("{}-{}", link.source_task_id, link.target_task_id))
+            .collect();
+
+        if !dangling.is_empty() {
+            errors.push(ValidationError::with_ids("links", "should link the tasks of the document.", dangling));
+        }
+
+        errors
+    }
+
+    /**
+     * The plan of the document for the coach, still with the ids of the
+     * document; see PlanSnapshot::rekeyed.
+     */
+    pub fn as_snapshot(&self, the_coach_id: &str) -> PlanSnapshot {
+        let tasks = self
+            .tasks
+            .iter()
+            .map(|task| TaskSnapshot {
+                id: task.id.to_owned(),
+                abstract_task_id: task.abstract_task_id.to_owned(),
+                duration: task.duration,
+                min: task.min,
+                max: task.max,
+                task_type: task.task_type.to_owned(),
+                coach_id: the_coach_id.to_owned(),
+                role_id: task.role_id.to_owned(),
+                coordinates: task.coordinates.to_owned(),
+            })
+            .collect();
+
+        let links = self
+            .links
+            .iter()
+            .map(|link| LinkSnapshot {
+                source_task_id: link.source_task_id.to_owned(),
+                target_task_id: link.target_task_id.to_owned(),
+                lead_time: link.lead_time,
+                coordinates: link.coordinates.to_owned(),
+                priority: link.priority,
+                is_forward: link.is_forward,
+            })
+            .collect();
+
+        PlanSnapshot { tasks, links }
+    }
+}
+
+/**
+ * Imports the document as a new plan of the target coach; the caller by
+ * default, another coach only for the administrators.
+ */
+#[derive(juniper::GraphQLInputObject)]
+pub struct ImportPlanRequest {
+    pub document: String,
+    pub target_coach_id: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn document() -> PlanDocument {
+        let json = r#"{
+            "format": "ferris/master-plan",
+            "version": 1,
+            "name": "Onboarding",
+            "description": "The first four weeks",
+            "roles": ["coach", "member"],
+            "abstract_tasks": [{ "id": "t1", "name": "Kick-off" }, { "id": "t2", "name": "Wrap-up" }],
+            "tasks": [
+                { "id": "n1", "abstract_task_id": "t1", "task_type": "START", "role_id": "coach", "duration": 2, "min": 1, "max": 4, "coordinates": "{}" },
+                { "id": "n2", "abstract_task_id": "t2", "task_type": "STOP", "role_id": "member", "duration": 2, "min": 2, "max": 2, "coordinates": "{}" }
+            ],
+            "links": [{ "source_task_id": "n1", "target_task_id": "n2", "lead_time": 0, "coordinates": "{}", "priority": 1, "is_forward": true }]
+        }"#;
+
+        PlanDocument::parse(json).unwrap()
+    }
+
+    #[test]
+    fn accepts_a_sound_document() {
+        assert!(document().validate().is_empty());
+    }
+
+    #[test]
+    fn rejects_broken_references() {
+        let mut broken = document();
+        broken.version = 2;
+        broken.tasks[0].role_id = String::from("mentor");
+        broken.links[0].target_task_id = String::from("n3");
+
+        let errors = broken.validate();
+        let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+
+        assert_eq!(fields, vec!["version", "tasks", "links"]);
+        assert_eq!(errors[1].ids, vec!["n1"]);
+    }
+
+    #[test]
+    fn rejects_what_is_not_a_document() {
+        assert!(PlanDocument::parse("{\"name\": \"Onboarding\"}").is_err());
+    }
+}
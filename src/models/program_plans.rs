@@ -0,0 +1,104 @@
+use crate::commons::chassis::ValidationError;
+use crate::commons::util;
+use crate::schema::program_plans;
+
+/**
+ * A master plan the coach offers in a program. The default plan of the
+ * program is applied to every new enrollment, so that each member starts
+ * with the same task graph.
+ */
+#[derive(Queryable, Debug, Clone)]
+pub struct ProgramPlan {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub master_plan_id: String,
+    pub program_id: String,
+    pub is_default: bool,
+}
+
+#[juniper::object]
+impl ProgramPlan {
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn description(&self) -> &str {
+        self.description.as_str()
+    }
+
+    pub fn master_plan_id(&self) -> &str {
+        self.master_plan_id.as_str()
+    }
+
+    pub fn program_id(&self) -> &str {
+        self.program_id.as_str()
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.is_default
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "program_plans"]
+pub struct NewProgramPlan {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub master_plan_id: String,
+    pub program_id: String,
+    pub is_default: bool,
+}
+
+impl NewProgramPlan {
+    pub fn from(request: &AttachPlanRequest) -> NewProgramPlan {
+        NewProgramPlan {
+            id: util::fuzzy_id(),
+            name: request.name.to_owned(),
+            description: request.description.to_owned(),
+            master_plan_id: request.master_plan_id.to_owned(),
+            program_id: request.program_id.to_owned(),
+            is_default: request.is_default,
+        }
+    }
+}
+
+#[derive(juniper::GraphQLInputObject)]
+pub struct AttachPlanRequest {
+    pub program_id: String,
+    pub master_plan_id: String,
+    pub name: String,
+    pub description: String,
+    pub is_default: bool,
+}
+
+impl AttachPlanRequest {
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors: Vec<ValidationError> = Vec::new();
+
+        if self.program_id.trim().is_empty() {
+            errors.push(ValidationError::new("program_id", "Program Id is a must."));
+        }
+
+        if self.master_plan_id.trim().is_empty() {
+            errors.push(ValidationError::new("master_plan_id", "Master Plan Id is a must."));
+        }
+
+        if self.name.trim().is_empty() {
+            errors.push(ValidationError::new("name", "Name is a must."));
+        }
+
+        errors
+    }
+}
+
+#[derive(juniper::GraphQLInputObject)]
+pub struct ProgramPlanRequest {
+    pub program_id: String,
+    pub program_plan_id: String,
+}
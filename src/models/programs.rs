@@ -98,7 +98,6 @@ impl Program {
 #[derive(juniper::GraphQLInputObject)]
 pub struct NewProgramRequest {
     pub name: String,
-    pub coach_id: String,
     pub description: String,
     pub is_private: bool,
     pub genre_id: Option<String>,
@@ -116,10 +115,6 @@ impl NewProgramRequest {
             errors.push(ValidationError::new("name", "name of the program is a must."));
         }
 
-        if self.coach_id.trim().is_empty() {
-            errors.push(ValidationError::new("coach_id", "coach id is a must"));
-        }
-
         if self.description.trim().is_empty() {
             errors.push(ValidationError::new("description", "description of the program is a must."));
         }
@@ -207,7 +202,6 @@ pub struct ChangeProgramStateRequest {
 pub struct AssociateCoachRequest {
     pub peer_coach_email: String,
     pub program_id: String,
-    pub admin_coach_id: String,
 }
 
 
@@ -0,0 +1,141 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+use crate::commons::scheduler::{Forecast, Timing, PERCENTILES};
+use crate::commons::util;
+use crate::models::tasks::Task;
+
+/**
+ * The place of a task in the critical path schedule of its enrollment. A
+ * delayed task with no slack pushes out the end of the programme.
+ */
+pub struct TaskTiming {
+    pub task: Task,
+    pub timing: Timing,
+    pub is_delayed: bool,
+}
+
+#[juniper::object]
+impl TaskTiming {
+    pub fn task(&self) -> &Task {
+        &self.task
+    }
+
+    pub fn earliest_start(&self) -> DateTime<Utc> {
+        util::with_offset(self.timing.earliest.start_date)
+    }
+
+    pub fn earliest_end(&self) -> DateTime<Utc> {
+        util::with_offset(self.timing.earliest.end_date)
+    }
+
+    pub fn latest_start(&self) -> DateTime<Utc> {
+        util::with_offset(self.timing.latest.start_date)
+    }
+
+    pub fn latest_end(&self) -> DateTime<Utc> {
+        util::with_offset(self.timing.latest.end_date)
+    }
+
+    #[graphql(description = "How many hours the task may slip without delaying the end")]
+    pub fn slack(&self) -> i32 {
+        self.timing.slack().num_hours() as i32
+    }
+
+    pub fn is_critical(&self) -> bool {
+        self.timing.is_critical()
+    }
+
+    pub fn is_delayed(&self) -> bool {
+        self.is_delayed
+    }
+
+    pub fn pushes_out_end(&self) -> bool {
+        self.is_delayed && self.timing.is_critical()
+    }
+}
+
+pub struct EnrollmentSchedule {
+    pub enrollment_id: String,
+    pub planned_end_date: Option<NaiveDateTime>,
+    pub projected_end_date: Option<NaiveDateTime>,
+    pub timings: Vec<TaskTiming>,
+}
+
+#[juniper::object]
+impl EnrollmentSchedule {
+    pub fn enrollment_id(&self) -> &str {
+        self.enrollment_id.as_str()
+    }
+
+    #[graphql(description = "The end of the last task, as scheduled")]
+    pub fn planned_end_date(&self) -> Option<DateTime<Utc>> {
+        self.planned_end_date.map(util::with_offset)
+    }
+
+    #[graphql(description = "The end of the last task, given the delays so far")]
+    pub fn projected_end_date(&self) -> Option<DateTime<Utc>> {
+        self.projected_end_date.map(util::with_offset)
+    }
+
+    pub fn tasks(&self) -> &Vec<TaskTiming> {
+        &self.timings
+    }
+
+    #[graphql(description = "The ids of the critical tasks, in the order of their start")]
+    pub fn critical_path(&self) -> Vec<&str> {
+        let mut critical: Vec<&TaskTiming> = self.timings.iter().filter(|timing| timing.timing.is_critical()).collect();
+        critical.sort_by_key(|timing| timing.timing.earliest.start_date);
+
+        critical.iter().map(|timing| timing.task.id.as_str()).collect()
+    }
+}
+
+/**
+ * The PERT forecast of a master plan, in hours from the beginning of the
+ * plan.
+ */
+pub struct PlanForecast {
+    pub master_plan_id: String,
+    pub forecast: Forecast,
+}
+
+#[derive(juniper::GraphQLObject)]
+pub struct Percentile {
+    #[graphql(description = "The chance (in percent) to end within the hours")]
+    pub percent: i32,
+    pub hours: f64,
+}
+
+#[juniper::object]
+impl PlanForecast {
+    pub fn master_plan_id(&self) -> &str {
+        self.master_plan_id.as_str()
+    }
+
+    #[graphql(description = "The ids of the master tasks on the critical path, in order")]
+    pub fn critical_path(&self) -> &Vec<String> {
+        &self.forecast.critical_path
+    }
+
+    pub fn expected_duration(&self) -> f64 {
+        self.forecast.expected
+    }
+
+    pub fn variance(&self) -> f64 {
+        self.forecast.variance
+    }
+
+    pub fn standard_deviation(&self) -> f64 {
+        self.forecast.standard_deviation()
+    }
+
+    pub fn percentiles(&self) -> Vec<Percentile> {
+        PERCENTILES
+            .iter()
+            .map(|(percent, z_score)| Percentile {
+                percent: *percent,
+                hours: self.forecast.within(*z_score),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,346 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+
+use crate::commons::chassis::ValidationError;
+use crate::commons::recurrence::{Recurrence, MAX_OCCURRENCES};
+use crate::commons::util;
+use crate::models::sessions::Session;
+use crate::schema::session_series;
+
+/**
+ * A series of sessions between a coach and a member, say every Tuesday 10:00
+ * for 12 weeks. The series is expanded into the sessions on its creation;
+ * each session carries the series id and lives on its own from then on.
+ *
+ * The exceptions are the (UTC) start dates of the cancelled occurrences, so
+ * that a revision of the series does not bring them back.
+ */
+#[derive(Queryable, Debug, Clone)]
+pub struct SessionSeries {
+    pub id: String,
+    pub program_id: String,
+    pub enrollment_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub duration: i32,
+    pub start_date: NaiveDateTime,
+    pub rule: String,
+    pub exceptions: Option<String>,
+    pub time_zone: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[juniper::object]
+impl SessionSeries {
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    pub fn program_id(&self) -> &str {
+        self.program_id.as_str()
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn duration(&self) -> i32 {
+        self.duration
+    }
+
+    pub fn start_date(&self) -> DateTime<Utc> {
+        util::with_offset(self.start_date)
+    }
+
+    pub fn rule(&self) -> &str {
+        self.rule.as_str()
+    }
+
+    pub fn exceptions(&self) -> Vec<DateTime<Utc>> {
+        self.exception_dates().into_iter().map(util::with_offset).collect()
+    }
+
+    pub fn time_zone(&self) -> &str {
+        self.time_zone.as_str()
+    }
+}
+
+impl SessionSeries {
+    pub fn recurrence(&self) -> Result<Recurrence, String> {
+        Recurrence::parse(self.rule.as_str())
+    }
+
+    pub fn zone(&self) -> Tz {
+        util::as_zone(self.time_zone.as_str()).unwrap_or(Tz::UTC)
+    }
+
+    pub fn exception_dates(&self) -> Vec<NaiveDateTime> {
+        as_dates(self.exceptions.as_deref())
+    }
+
+    /**
+     * The start dates of the sessions, less the exceptions.
+     */
+    pub fn occurrences(&self) -> Result<Vec<NaiveDateTime>, String> {
+        Ok(self.recurrence()?.expand(self.start_date, self.zone(), &self.exception_dates()))
+    }
+}
+
+pub struct SeriesRow {
+    pub series: SessionSeries,
+    pub sessions: Vec<Session>,
+}
+
+#[juniper::object]
+impl SeriesRow {
+    pub fn series(&self) -> &SessionSeries {
+        &self.series
+    }
+
+    pub fn sessions(&self) -> &Vec<Session> {
+        &self.sessions
+    }
+}
+
+#[derive(juniper::GraphQLInputObject)]
+pub struct NewSessionSeriesRequest {
+    pub program_id: String,
+    pub member_id: String,
+    pub name: String,
+    pub description: String,
+    pub duration: i32,
+    pub start_time: String,
+    pub rule: String,
+    pub exceptions: Option<Vec<String>>,
+}
+
+impl NewSessionSeriesRequest {
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors: Vec<ValidationError> = Vec::new();
+
+        let given_time = self.start_time.as_str();
+
+        if !util::is_valid_date(given_time) {
+            errors.push(ValidationError::new("start_time", "unparsable date."));
+        } else if util::as_date(given_time).is_ok_and(util::is_past_date) {
+            errors.push(ValidationError::new("start_time", "should be a future date."));
+        }
+
+        if self.duration < 15 {
+            errors.push(ValidationError::new("duration", "should be a minimum of 15 minutes"));
+        }
+
+        if self.program_id.trim().is_empty() {
+            errors.push(ValidationError::new("program_id", "Program fuzzy id is a must."));
+        }
+
+        if self.member_id.trim().is_empty() {
+            errors.push(ValidationError::new("member_id", "Member fuzzy id is a must."));
+        }
+
+        if self.name.trim().is_empty() {
+            errors.push(ValidationError::new("name", "name of the session is a must."));
+        }
+
+        if self.description.trim().is_empty() {
+            errors.push(ValidationError::new("description", "description of the session is a must."));
+        }
+
+        let exceptions = self.exceptions.clone().unwrap_or_default();
+        if exceptions.iter().any(|exception| !util::is_valid_date(exception)) {
+            errors.push(ValidationError::new("exceptions", "unparsable date."));
+        }
+
+        validate_rule(self.rule.as_str(), &mut errors);
+
+        errors
+    }
+
+    pub fn exception_dates(&self) -> Result<Vec<NaiveDateTime>, &'static str> {
+        self.exceptions.iter().flatten().map(|exception| util::as_date(exception)).collect()
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "session_series"]
+pub struct NewSessionSeries {
+    pub id: String,
+    pub program_id: String,
+    pub enrollment_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub duration: i32,
+    pub start_date: NaiveDateTime,
+    pub rule: String,
+    pub exceptions: Option<String>,
+    pub time_zone: String,
+}
+
+impl NewSessionSeries {
+    /**
+     * The series follows the time zone of the coach.
+     */
+    pub fn from(request: &NewSessionSeriesRequest, enrollment_id: String, time_zone: String) -> Result<NewSessionSeries, &'static str> {
+        let fuzzy_id = util::fuzzy_id();
+        let rule = Recurrence::parse(request.rule.as_str()).map(|recurrence| recurrence.to_rule()).unwrap_or_default();
+
+        Ok(NewSessionSeries {
+            id: fuzzy_id,
+            program_id: request.program_id.to_owned(),
+            enrollment_id,
+            name: request.name.to_owned(),
+            description: Some(request.description.to_owned()),
+            duration: request.duration,
+            start_date: util::as_date(request.start_time.as_str())?,
+            rule,
+            exceptions: as_exceptions(&request.exception_dates()?),
+            time_zone,
+        })
+    }
+
+    /**
+     * The revision of the given series from one of its occurrences on.
+     */
+    pub fn revise(series: &SessionSeries, request: &UpdateSeriesRequest, start_date: NaiveDateTime, rule: String, exceptions: &[NaiveDateTime]) -> NewSessionSeries {
+        let fuzzy_id = util::fuzzy_id();
+
+        NewSessionSeries {
+            id: fuzzy_id,
+            program_id: series.program_id.to_owned(),
+            enrollment_id: series.enrollment_id.to_owned(),
+            name: request.name.clone().unwrap_or_else(|| series.name.to_owned()),
+            description: request.description.clone().or_else(|| series.description.clone()),
+            duration: request.duration.unwrap_or(series.duration),
+            start_date,
+            rule,
+            exceptions: as_exceptions(exceptions),
+            time_zone: series.time_zone.to_owned(),
+        }
+    }
+}
+
+/**
+ * Edits an occurrence and all the following ones of a series. The given
+ * fields replace those of the series; the start time moves the occurrence
+ * (and so the following ones) and the rule replaces the recurrence from the
+ * occurrence on.
+ */
+#[derive(juniper::GraphQLInputObject)]
+pub struct UpdateSeriesRequest {
+    pub session_id: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub duration: Option<i32>,
+    pub start_time: Option<String>,
+    pub rule: Option<String>,
+}
+
+impl UpdateSeriesRequest {
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors: Vec<ValidationError> = Vec::new();
+
+        if self.session_id.trim().is_empty() {
+            errors.push(ValidationError::new("session_id", "session id is a must."));
+        }
+
+        if let Some(name) = &self.name {
+            if name.trim().is_empty() {
+                errors.push(ValidationError::new("name", "name of the session cannot be blank."));
+            }
+        }
+
+        if let Some(minutes) = self.duration {
+            if minutes < 15 {
+                errors.push(ValidationError::new("duration", "should be a minimum of 15 minutes"));
+            }
+        }
+
+        if let Some(given_time) = &self.start_time {
+            if !util::is_valid_date(given_time) {
+                errors.push(ValidationError::new("start_time", "unparsable date."));
+            } else if util::as_date(given_time).is_ok_and(util::is_past_date) {
+                errors.push(ValidationError::new("start_time", "should be a future date."));
+            }
+        }
+
+        if let Some(rule) = &self.rule {
+            validate_rule(rule.as_str(), &mut errors);
+        }
+
+        errors
+    }
+}
+
+#[derive(juniper::GraphQLInputObject)]
+pub struct CancelOccurrenceRequest {
+    pub session_id: String,
+    pub reason: String,
+}
+
+impl CancelOccurrenceRequest {
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors: Vec<ValidationError> = Vec::new();
+
+        if self.session_id.trim().is_empty() {
+            errors.push(ValidationError::new("session_id", "session id is a must."));
+        }
+
+        if self.reason.trim().is_empty() {
+            errors.push(ValidationError::new("reason", "reason for the cancellation is a must."));
+        }
+
+        errors
+    }
+}
+
+fn validate_rule(rule: &str, errors: &mut Vec<ValidationError>) {
+    match Recurrence::parse(rule) {
+        Err(e) => errors.push(ValidationError::new("rule", e.as_str())),
+        // The zone hardly changes the count, so UTC will do for the check
+        Ok(recurrence) if recurrence.occurrences(util::now(), Tz::UTC).len() > MAX_OCCURRENCES => {
+            errors.push(ValidationError::new("rule", "should not exceed 104 occurrences."));
+        }
+        Ok(_) => {}
+    }
+}
+
+/**
+ * The exceptions are persisted as a comma separated list of dates.
+ */
+pub fn as_exceptions(dates: &[NaiveDateTime]) -> Option<String> {
+    if dates.is_empty() {
+        return None;
+    }
+
+    Some(dates.iter().map(|date| util::with_offset(*date).to_rfc3339()).collect::<Vec<String>>().join(","))
+}
+
+fn as_dates(exceptions: Option<&str>) -> Vec<NaiveDateTime> {
+    exceptions
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|date| util::as_date(date).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn should_persist_and_read_the_exceptions() {
+        let dates = vec![util::as_date("2021-03-02T10:00:00Z").unwrap(), util::as_date("2021-03-09T10:00:00Z").unwrap()];
+
+        let exceptions = as_exceptions(&dates);
+
+        assert_eq!(dates, as_dates(exceptions.as_deref()));
+        assert_eq!(None, as_exceptions(&[]));
+        assert!(as_dates(None).is_empty());
+    }
+}
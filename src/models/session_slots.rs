@@ -0,0 +1,58 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+use crate::commons::util;
+use crate::schema::session_slots;
+
+/**
+ * A time slot offered by a member while requesting a session. The coach
+ * accepts one of them, which then becomes the schedule of the session.
+ */
+#[derive(Queryable, Debug, Clone)]
+pub struct SessionSlot {
+    pub id: String,
+    pub session_id: String,
+    pub start_date: NaiveDateTime,
+    pub end_date: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+}
+
+#[juniper::object]
+impl SessionSlot {
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    pub fn session_id(&self) -> &str {
+        self.session_id.as_str()
+    }
+
+    pub fn start_date(&self) -> DateTime<Utc> {
+        util::with_offset(self.start_date)
+    }
+
+    pub fn end_date(&self) -> DateTime<Utc> {
+        util::with_offset(self.end_date)
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "session_slots"]
+pub struct NewSessionSlot {
+    pub id: String,
+    pub session_id: String,
+    pub start_date: NaiveDateTime,
+    pub end_date: NaiveDateTime,
+}
+
+impl NewSessionSlot {
+    pub fn from(the_session_id: &str, start_date: NaiveDateTime, end_date: NaiveDateTime) -> NewSessionSlot {
+        let fuzzy_id = util::fuzzy_id();
+
+        NewSessionSlot {
+            id: fuzzy_id,
+            session_id: the_session_id.to_owned(),
+            start_date,
+            end_date,
+        }
+    }
+}
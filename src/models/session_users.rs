@@ -8,7 +8,7 @@ use crate::schema::sessions;
 use crate::schema::sessions::dsl::*;
 use crate::schema::users::dsl::*;
 
-use crate::models::sessions::Session;
+use crate::models::sessions::{NewSession, Session};
 use crate::models::users::User;
 
 #[derive(Clone, Queryable, Debug, Identifiable)]
@@ -59,6 +59,17 @@ impl NewSessionUser {
             user_type: String::from(session_user_type),
         }
     }
+
+    pub fn for_new_session(new_session: &NewSession, user: &User, session_user_type: &str) -> NewSessionUser {
+        let fuzzy_id = util::fuzzy_id();
+
+        NewSessionUser {
+            id: fuzzy_id,
+            session_id: new_session.id.to_owned(),
+            user_id: user.id.to_owned(),
+            user_type: String::from(session_user_type),
+        }
+    }
 }
 
 #[derive(juniper::GraphQLInputObject)]
@@ -1,8 +1,9 @@
 use crate::commons::chassis::ValidationError;
 use crate::commons::util;
+use crate::models::session_series::SessionSeries;
 use crate::schema::sessions;
 
-use chrono::{Duration, NaiveDateTime};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
 
 // The Order of the fiels are very important
 #[derive(Queryable, Debug, Identifiable, Clone)]
@@ -30,8 +31,11 @@ pub struct Session {
     pub is_request: bool,
     pub conference_id: Option<String>,
     pub session_type: String,
+    pub sequence: i32,
+    pub series_id: Option<String>,
 }
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(juniper::GraphQLEnum)]
 enum Status {
     DONE,
@@ -40,6 +44,7 @@ enum Status {
     READY,
     OVERDUE,
     PLANNED,
+    REQUESTED,
 }
 
 // Fields that we can safely expose to APIs
@@ -83,20 +88,20 @@ impl Session {
         self.duration
     }
 
-    pub fn scheduleStart(&self) -> NaiveDateTime {
-        self.revised_start_date.unwrap_or(self.original_start_date)
+    pub fn scheduleStart(&self) -> DateTime<Utc> {
+        util::with_offset(self.revised_start_date.unwrap_or(self.original_start_date))
     }
 
-    pub fn scheduleEnd(&self) -> NaiveDateTime {
-        self.revised_end_date.unwrap_or(self.original_end_date)
+    pub fn scheduleEnd(&self) -> DateTime<Utc> {
+        util::with_offset(self.revised_end_date.unwrap_or(self.original_end_date))
     }
 
-    pub fn actualStart(&self) -> Option<NaiveDateTime> {
-        self.actual_start_date
+    pub fn actualStart(&self) -> Option<DateTime<Utc>> {
+        self.actual_start_date.map(util::with_offset)
     }
 
-    pub fn actualEnd(&self) -> Option<NaiveDateTime> {
-        self.actual_end_date
+    pub fn actualEnd(&self) -> Option<DateTime<Utc>> {
+        self.actual_end_date.map(util::with_offset)
     }
 
     pub fn isClosed(&self) -> bool {
@@ -119,6 +124,11 @@ impl Session {
         if self.actual_end_date.is_some() {
             return Status::DONE;
         }
+
+        if self.is_request {
+            return Status::REQUESTED;
+        }
+
         if self.actual_start_date.is_some() {
             return Status::PROGRESS;
         }
@@ -147,6 +157,22 @@ impl Session {
     pub fn conference_id(&self) -> Option<String> {
         self.conference_id.clone()
     }
+
+    pub fn is_request(&self) -> bool {
+        self.is_request
+    }
+
+    pub fn offeredStart(&self) -> Option<DateTime<Utc>> {
+        self.offered_start_date.map(util::with_offset)
+    }
+
+    pub fn offeredEnd(&self) -> Option<DateTime<Utc>> {
+        self.offered_end_date.map(util::with_offset)
+    }
+
+    pub fn series_id(&self) -> Option<&str> {
+        self.series_id.as_deref()
+    }
 }
 
 impl Session {
@@ -170,6 +196,24 @@ impl Session {
     pub fn is_conference(&self) -> bool {
         self.session_type.eq("multi")
     }
+
+    // A request awaits the coach to accept or to decline
+    pub fn is_pending_request(&self) -> bool {
+        self.is_request && self.cancelled_at.is_none()
+    }
+
+    // A session can be moved till it starts
+    pub fn can_reschedule(&self) -> bool {
+        !self.is_request && self.cancelled_at.is_none() && self.actual_start_date.is_none() && self.actual_end_date.is_none()
+    }
+
+    pub fn schedule_start(&self) -> NaiveDateTime {
+        self.revised_start_date.unwrap_or(self.original_start_date)
+    }
+
+    pub fn schedule_end(&self) -> NaiveDateTime {
+        self.revised_end_date.unwrap_or(self.original_end_date)
+    }
 }
 
 #[derive(juniper::GraphQLInputObject)]
@@ -190,10 +234,7 @@ impl NewSessionRequest {
 
         if !util::is_valid_date(given_time) {
             errors.push(ValidationError::new("start_time", "unparsable date."));
-        }
-
-        let date = util::as_date(given_time);
-        if util::is_past_date(date) {
+        } else if util::as_date(given_time).is_ok_and(util::is_past_date) {
             errors.push(ValidationError::new("start_time", "should be a future date."));
         }
 
@@ -234,20 +275,26 @@ pub struct NewSession {
     pub duration: i32,
     pub original_start_date: NaiveDateTime,
     pub original_end_date: NaiveDateTime,
+    pub revised_start_date: Option<NaiveDateTime>,
+    pub revised_end_date: Option<NaiveDateTime>,
     pub conference_id: Option<String>,
     pub session_type: String,
     pub is_ready: bool,
+    pub is_request: bool,
+    pub offered_start_date: Option<NaiveDateTime>,
+    pub offered_end_date: Option<NaiveDateTime>,
+    pub series_id: Option<String>,
 }
 
 impl NewSession {
-    pub fn from(request: &NewSessionRequest, enrollment_id: String, people: String) -> NewSession {
-        let start_date = util::as_date(request.start_time.as_str());
+    pub fn from(request: &NewSessionRequest, enrollment_id: String, people: String) -> Result<NewSession, &'static str> {
+        let start_date = util::as_date(request.start_time.as_str())?;
         let duration = Duration::minutes(request.duration as i64);
         let end_date = start_date.checked_add_signed(duration);
 
         let fuzzy_id = util::fuzzy_id();
 
-        NewSession {
+        Ok(NewSession {
             id: fuzzy_id,
             name: request.name.to_owned(),
             description: request.description.to_owned(),
@@ -257,10 +304,162 @@ impl NewSession {
             duration: request.duration,
             original_start_date: start_date,
             original_end_date: end_date.unwrap_or(start_date),
+            revised_start_date: None,
+            revised_end_date: None,
             conference_id: None,
             session_type: util::MONO.to_owned(),
             is_ready:false,
+            is_request: false,
+            offered_start_date: None,
+            offered_end_date: None,
+            series_id: None,
+        })
+    }
+
+    /**
+     * A requested session is tentatively placed on the first offered slot,
+     * till the coach accepts one of the slots.
+     */
+    pub fn from_request(request: &MemberSessionRequest, enrollment_id: String, people: String) -> Result<NewSession, &'static str> {
+        let (start_date, end_date) = request.slots_dates()?[0];
+
+        let fuzzy_id = util::fuzzy_id();
+
+        Ok(NewSession {
+            id: fuzzy_id,
+            name: request.name.to_owned(),
+            description: request.description.to_owned(),
+            program_id: request.program_id.to_owned(),
+            enrollment_id,
+            people,
+            duration: request.duration,
+            original_start_date: start_date,
+            original_end_date: end_date,
+            revised_start_date: None,
+            revised_end_date: None,
+            conference_id: None,
+            session_type: util::MONO.to_owned(),
+            is_ready: false,
+            is_request: true,
+            offered_start_date: None,
+            offered_end_date: None,
+            series_id: None,
+        })
+    }
+
+    /**
+     * An occurrence of a session series, starting at the given date.
+     */
+    pub fn for_series(series: &SessionSeries, start_date: NaiveDateTime, people: String) -> NewSession {
+        let end_date = start_date.checked_add_signed(Duration::minutes(series.duration as i64));
+
+        let fuzzy_id = util::fuzzy_id();
+
+        NewSession {
+            id: fuzzy_id,
+            name: series.name.to_owned(),
+            description: series.description.clone().unwrap_or_default(),
+            program_id: series.program_id.to_owned(),
+            enrollment_id: series.enrollment_id.to_owned(),
+            people,
+            duration: series.duration,
+            original_start_date: start_date,
+            original_end_date: end_date.unwrap_or(start_date),
+            revised_start_date: None,
+            revised_end_date: None,
+            conference_id: None,
+            session_type: util::MONO.to_owned(),
+            is_ready: false,
+            is_request: false,
+            offered_start_date: None,
+            offered_end_date: None,
+            series_id: Some(series.id.to_owned()),
+        }
+    }
+}
+
+/**
+ * A member requests a session, offering one or more slots (start times)
+ * to the coach of the program.
+ */
+#[derive(juniper::GraphQLInputObject)]
+pub struct MemberSessionRequest {
+    pub program_id: String,
+    pub name: String,
+    pub description: String,
+    pub duration: i32,
+    pub slots: Vec<String>,
+}
+
+impl MemberSessionRequest {
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors: Vec<ValidationError> = Vec::new();
+
+        if self.slots.is_empty() {
+            errors.push(ValidationError::new("slots", "offer at least one slot."));
+        }
+
+        for slot in &self.slots {
+            if !util::is_valid_date(slot.as_str()) {
+                errors.push(ValidationError::new("slots", "unparsable date."));
+            } else if util::as_date(slot.as_str()).is_ok_and(util::is_past_date) {
+                errors.push(ValidationError::new("slots", "should be a future date."));
+            }
+        }
+
+        if self.duration < 15 {
+            errors.push(ValidationError::new("duration", "should be a minimum of 15 minutes"));
         }
+
+        if self.program_id.trim().is_empty() {
+            errors.push(ValidationError::new("program_id", "Program fuzzy id is a must."));
+        }
+
+        if self.name.trim().is_empty() {
+            errors.push(ValidationError::new("name", "name of the session is a must."));
+        }
+
+        if self.description.trim().is_empty() {
+            errors.push(ValidationError::new("description", "description of the session is a must."));
+        }
+
+        errors
+    }
+
+    pub fn slots_dates(&self) -> Result<Vec<(NaiveDateTime, NaiveDateTime)>, &'static str> {
+        let duration = Duration::minutes(self.duration as i64);
+
+        self.slots
+            .iter()
+            .map(|slot| {
+                let start_date = util::as_date(slot.as_str())?;
+                Ok((start_date, start_date.checked_add_signed(duration).unwrap_or(start_date)))
+            })
+            .collect()
+    }
+}
+
+#[derive(juniper::GraphQLInputObject)]
+pub struct AcceptSessionRequest {
+    pub id: String,
+    pub slot_id: String,
+}
+
+#[derive(juniper::GraphQLInputObject)]
+pub struct DeclineSessionRequest {
+    pub id: String,
+    pub reason: String,
+}
+
+impl DeclineSessionRequest {
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors: Vec<ValidationError> = Vec::new();
+
+        if self.reason.trim().is_empty() {
+            errors.push(ValidationError::new("reason", "reason to decline is a must."));
+        }
+
+        errors
     }
 }
 
@@ -278,3 +477,53 @@ pub struct ChangeSessionStateRequest {
     pub target_state: TargetState,
     pub closing_notes: Option<String>,
 }
+
+/**
+ * Moves a session (or all the sessions of a conference, when the id is
+ * that of a conference) to a new start time. The duration is retained
+ * unless a new one is given.
+ */
+#[derive(juniper::GraphQLInputObject)]
+pub struct RescheduleRequest {
+    pub id: String,
+    pub start_time: String,
+    pub duration: Option<i32>,
+    pub reason: Option<String>,
+}
+
+impl RescheduleRequest {
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors: Vec<ValidationError> = Vec::new();
+
+        let given_time = self.start_time.as_str();
+
+        if !util::is_valid_date(given_time) {
+            errors.push(ValidationError::new("start_time", "unparsable date."));
+        } else if util::as_date(given_time).is_ok_and(util::is_past_date) {
+            errors.push(ValidationError::new("start_time", "should be a future date."));
+        }
+
+        if let Some(minutes) = self.duration {
+            if minutes < 15 {
+                errors.push(ValidationError::new("duration", "should be a minimum of 15 minutes"));
+            }
+        }
+
+        if self.id.trim().is_empty() {
+            errors.push(ValidationError::new("id", "id is a must."));
+        }
+
+        errors
+    }
+
+    /**
+     * The revised start and end dates, for the given current duration.
+     */
+    pub fn revised_dates(&self, current_duration: i32) -> Result<(NaiveDateTime, NaiveDateTime), &'static str> {
+        let start_date = util::as_date(self.start_time.as_str())?;
+        let minutes = self.duration.unwrap_or(current_duration);
+        let end_date = start_date.checked_add_signed(Duration::minutes(minutes as i64));
+
+        Ok((start_date, end_date.unwrap_or(start_date)))
+    }
+}
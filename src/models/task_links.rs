@@ -0,0 +1,269 @@
+use crate::commons::chassis::ValidationError;
+use crate::commons::scheduler::{self, Activity, Dependency};
+use crate::commons::util;
+use crate::models::master_plans::{LinkUnit, MasterTaskLink};
+use crate::models::tasks::Task;
+use crate::schema::task_links;
+
+#[derive(Queryable, Debug)]
+pub struct TaskLink {
+    pub id: String,
+    pub source_task_id: String,
+    pub target_task_id: String,
+    pub lead_time: i32,
+    pub coordinates: String,
+    pub priority: i32,
+    pub is_forward: bool,
+    pub enrollment_id: String,
+}
+
+#[juniper::object]
+impl TaskLink {
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    pub fn source_task_id(&self) -> &str {
+        self.source_task_id.as_str()
+    }
+
+    pub fn target_task_id(&self) -> &str {
+        self.target_task_id.as_str()
+    }
+
+    #[graphql(description = "In hours")]
+    pub fn lead_time(&self) -> i32 {
+        self.lead_time
+    }
+
+    pub fn coordinates(&self) -> &str {
+        self.coordinates.as_str()
+    }
+
+    pub fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    pub fn is_forward(&self) -> bool {
+        self.is_forward
+    }
+
+    pub fn enrollment_id(&self) -> &str {
+        self.enrollment_id.as_str()
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "task_links"]
+pub struct NewTaskLink {
+    pub id: String,
+    pub source_task_id: String,
+    pub target_task_id: String,
+    pub lead_time: i32,
+    pub coordinates: String,
+    pub priority: i32,
+    pub is_forward: bool,
+    pub enrollment_id: String,
+}
+
+impl NewTaskLink {
+    /**
+     * The link between the tasks instantiated from the ends of a link of
+     * the master plan.
+     */
+    pub fn for_plan(link: &MasterTaskLink, the_source_id: &str, the_target_id: &str, the_enrollment_id: &str) -> NewTaskLink {
+        let fuzzy_id = util::fuzzy_id();
+
+        NewTaskLink {
+            id: fuzzy_id,
+            source_task_id: the_source_id.to_owned(),
+            target_task_id: the_target_id.to_owned(),
+            lead_time: link.lead_time,
+            coordinates: link.coordinates.to_owned(),
+            priority: link.priority,
+            is_forward: link.is_forward,
+            enrollment_id: the_enrollment_id.to_owned(),
+        }
+    }
+
+    pub fn from(link: &LinkUnit, the_enrollment_id: &str) -> NewTaskLink {
+        let fuzzy_id = util::fuzzy_id();
+
+        NewTaskLink {
+            id: fuzzy_id,
+            source_task_id: link.source_id.to_owned(),
+            target_task_id: link.target_id.to_owned(),
+            lead_time: link.lead_time.unwrap_or(0),
+            coordinates: link.coordinates.to_owned(),
+            priority: link.priority,
+            is_forward: link.is_forward,
+            enrollment_id: the_enrollment_id.to_owned(),
+        }
+    }
+}
+
+/**
+ * The links between the tasks of an enrollment, saved all at once as in a
+ * master plan: the links missing from the request are removed.
+ */
+#[derive(juniper::GraphQLInputObject)]
+pub struct UpdateTaskLinksRequest {
+    pub enrollment_id: String,
+    pub links: Vec<LinkUnit>,
+}
+
+impl UpdateTaskLinksRequest {
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors: Vec<ValidationError> = Vec::new();
+
+        if self.enrollment_id.trim().is_empty() {
+            errors.push(ValidationError::new("enrollment_id", "Enrollment Id is a must."));
+        }
+
+        errors
+    }
+
+    /**
+     * The links should stay within the tasks of the enrollment, and the
+     * forward links should not make a cycle, or the tasks can not be
+     * scheduled. The backward links (rework) may go anywhere.
+     */
+    pub fn validate_graph(&self, enrollment_tasks: &[Task]) -> Vec<ValidationError> {
+        let mut errors: Vec<ValidationError> = Vec::new();
+
+        let is_task = |task_id: &str| enrollment_tasks.iter().any(|task| task.id == task_id);
+
+        let mut strangers: Vec<String> = self
+            .links
+            .iter()
+            .flat_map(|link| vec![link.source_id.to_owned(), link.target_id.to_owned()])
+            .filter(|task_id| !is_task(task_id.as_str()))
+            .collect();
+        strangers.sort();
+        strangers.dedup();
+
+        if !strangers.is_empty() {
+            errors.push(ValidationError::with_ids("links", "should link the tasks of the enrollment.", strangers));
+        }
+
+        let loops: Vec<String> = self.links.iter().filter(|link| link.source_id == link.target_id).map(|link| link.source_id.to_owned()).collect();
+
+        if !loops.is_empty() {
+            errors.push(ValidationError::with_ids("links", "should not link a task to itself.", loops));
+        }
+
+        if !errors.is_empty() {
+            return errors;
+        }
+
+        let activities: Vec<Activity> = enrollment_tasks
+            .iter()
+            .map(|task| Activity {
+                id: task.id.to_owned(),
+                duration: task.duration as i64,
+                not_before: None,
+            })
+            .collect();
+
+        let dependencies: Vec<Dependency> = self
+            .links
+            .iter()
+            .filter(|link| link.is_forward)
+            .map(|link| Dependency {
+                source_id: link.source_id.to_owned(),
+                target_id: link.target_id.to_owned(),
+                lead_time: link.lead_time.unwrap_or(0) as i64,
+            })
+            .collect();
+
+        let cyclic: Vec<String> = scheduler::cyclic_activities(&activities, &dependencies)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|i| activities[i].id.to_owned())
+            .collect();
+
+        if !cyclic.is_empty() {
+            errors.push(ValidationError::with_ids("links", "should not form a cycle of forward links.", cyclic));
+        }
+
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use chrono::Duration;
+
+    fn task(id: &str) -> Task {
+        let start_date = util::as_date("2021-03-15T09:00:00Z").unwrap();
+
+        Task {
+            id: String::from(id),
+            enrollment_id: String::from("enrollment"),
+            actor_id: String::from("member"),
+            name: String::from(id),
+            duration: 8,
+            min: 8,
+            max: 8,
+            original_start_date: start_date,
+            original_end_date: start_date + Duration::hours(8),
+            revised_start_date: None,
+            revised_end_date: None,
+            offered_start_date: None,
+            offered_end_date: None,
+            actual_start_date: None,
+            actual_end_date: None,
+            locked: false,
+            created_at: start_date,
+            updated_at: start_date,
+            description: None,
+            closing_notes: None,
+            response: None,
+            approved_at: None,
+            cancelled_at: None,
+            responded_date: None,
+            master_plan_id: None,
+        }
+    }
+
+    fn link(source_id: &str, target_id: &str, is_forward: bool) -> LinkUnit {
+        LinkUnit {
+            source_id: String::from(source_id),
+            target_id: String::from(target_id),
+            coordinates: String::from("{}"),
+            priority: 1,
+            is_forward,
+            lead_time: None,
+        }
+    }
+
+    fn request(links: Vec<LinkUnit>) -> UpdateTaskLinksRequest {
+        UpdateTaskLinksRequest {
+            enrollment_id: String::from("enrollment"),
+            links,
+        }
+    }
+
+    #[test]
+    fn accepts_rework_loops() {
+        let tasks = vec![task("a"), task("b"), task("c")];
+        let errors = request(vec![link("a", "b", true), link("b", "c", true), link("c", "a", false)]).validate_graph(&tasks);
+
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn rejects_strangers_and_cycles() {
+        let tasks = vec![task("a"), task("b")];
+
+        let errors = request(vec![link("a", "x", true)]).validate_graph(&tasks);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "should link the tasks of the enrollment.");
+
+        let errors = request(vec![link("a", "b", true), link("b", "a", true)]).validate_graph(&tasks);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "should not form a cycle of forward links.");
+    }
+}
@@ -0,0 +1,102 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+use crate::commons::scheduler::Schedule;
+use crate::commons::util;
+use crate::models::tasks::Task;
+use crate::schema::task_moves;
+
+/**
+ * A successor moved to revised dates, as a task it depends on (the cause)
+ * slipped. The original dates of the successor stay as they were planned.
+ */
+#[derive(Queryable, Debug)]
+pub struct TaskMove {
+    pub id: String,
+    pub enrollment_id: String,
+    pub task_id: String,
+    pub cause_task_id: String,
+    pub from_start_date: NaiveDateTime,
+    pub from_end_date: NaiveDateTime,
+    pub to_start_date: NaiveDateTime,
+    pub to_end_date: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+}
+
+#[juniper::object]
+impl TaskMove {
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    pub fn enrollment_id(&self) -> &str {
+        self.enrollment_id.as_str()
+    }
+
+    pub fn task_id(&self) -> &str {
+        self.task_id.as_str()
+    }
+
+    pub fn cause_task_id(&self) -> &str {
+        self.cause_task_id.as_str()
+    }
+
+    pub fn from_start_date(&self) -> DateTime<Utc> {
+        util::with_offset(self.from_start_date)
+    }
+
+    pub fn from_end_date(&self) -> DateTime<Utc> {
+        util::with_offset(self.from_end_date)
+    }
+
+    pub fn to_start_date(&self) -> DateTime<Utc> {
+        util::with_offset(self.to_start_date)
+    }
+
+    pub fn to_end_date(&self) -> DateTime<Utc> {
+        util::with_offset(self.to_end_date)
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        util::with_offset(self.created_at)
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "task_moves"]
+pub struct NewTaskMove {
+    pub id: String,
+    pub enrollment_id: String,
+    pub task_id: String,
+    pub cause_task_id: String,
+    pub from_start_date: NaiveDateTime,
+    pub from_end_date: NaiveDateTime,
+    pub to_start_date: NaiveDateTime,
+    pub to_end_date: NaiveDateTime,
+}
+
+impl NewTaskMove {
+    pub fn from(task: &Task, the_cause_id: &str, schedule: &Schedule) -> NewTaskMove {
+        let fuzzy_id = util::fuzzy_id();
+
+        NewTaskMove {
+            id: fuzzy_id,
+            enrollment_id: task.enrollment_id.to_owned(),
+            task_id: task.id.to_owned(),
+            cause_task_id: the_cause_id.to_owned(),
+            from_start_date: task.revised_start_date.unwrap_or(task.original_start_date),
+            from_end_date: task.revised_end_date.unwrap_or(task.original_end_date),
+            to_start_date: schedule.start_date,
+            to_end_date: schedule.end_date,
+        }
+    }
+}
+
+/**
+ * Sweeps the delayed tasks of the enrollment and moves their successors;
+ * the actors of the moved tasks get a notice in the discussion when asked.
+ */
+#[derive(juniper::GraphQLInputObject)]
+pub struct PropagateDelaysRequest {
+    pub enrollment_id: String,
+    pub notify_actors: Option<bool>,
+}
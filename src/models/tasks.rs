@@ -1,8 +1,10 @@
 use crate::commons::chassis::ValidationError;
+use crate::commons::scheduler::Schedule;
 use crate::commons::util;
+use crate::models::master_tasks::MasterTask;
 use crate::schema::tasks;
 
-use chrono::{Duration, NaiveDateTime};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
 
 #[derive(Queryable, Debug, Identifiable)]
 pub struct Task {
@@ -30,6 +32,7 @@ pub struct Task {
     pub approved_at: Option<NaiveDateTime>,
     pub cancelled_at: Option<NaiveDateTime>,
     pub responded_date: Option<NaiveDateTime>,
+    pub master_plan_id: Option<String>,
 }
 
 #[derive(juniper::GraphQLEnum)]
@@ -81,20 +84,20 @@ impl Task {
         self.max
     }
 
-    pub fn scheduleStart(&self) -> NaiveDateTime {
-        self.revised_start_date.unwrap_or(self.original_start_date)
+    pub fn scheduleStart(&self) -> DateTime<Utc> {
+        util::with_offset(self.revised_start_date.unwrap_or(self.original_start_date))
     }
 
-    pub fn scheduleEnd(&self) -> NaiveDateTime {
-        self.revised_end_date.unwrap_or(self.original_end_date)
+    pub fn scheduleEnd(&self) -> DateTime<Utc> {
+        util::with_offset(self.revised_end_date.unwrap_or(self.original_end_date))
     }
 
-    pub fn createdAt(&self) -> NaiveDateTime {
-        self.created_at
+    pub fn createdAt(&self) -> DateTime<Utc> {
+        util::with_offset(self.created_at)
     }
 
-    pub fn actualStart(&self) -> Option<NaiveDateTime> {
-        self.actual_start_date
+    pub fn actualStart(&self) -> Option<DateTime<Utc>> {
+        self.actual_start_date.map(util::with_offset)
     }
 
     pub fn response(&self) -> &str {
@@ -105,8 +108,8 @@ impl Task {
         value
     }
     
-    pub fn respondedDate(&self) -> Option<NaiveDateTime> {
-        self.responded_date
+    pub fn respondedDate(&self) -> Option<DateTime<Utc>> {
+        self.responded_date.map(util::with_offset)
     }
 
     pub fn closingNotes(&self) -> &str {
@@ -117,12 +120,12 @@ impl Task {
         value
     }
 
-    pub fn actualEnd(&self) -> Option<NaiveDateTime> {
-        self.actual_end_date
+    pub fn actualEnd(&self) -> Option<DateTime<Utc>> {
+        self.actual_end_date.map(util::with_offset)
     }
 
-    pub fn cancelledDate(&self) -> Option<NaiveDateTime> {
-        self.cancelled_at
+    pub fn cancelledDate(&self) -> Option<DateTime<Utc>> {
+        self.cancelled_at.map(util::with_offset)
     }
 
    
@@ -228,10 +231,7 @@ impl NewTaskRequest {
 
         if !util::is_valid_date(given_time) {
             errors.push(ValidationError::new("start_time", "unparsable date."));
-        }
-
-        let date = util::as_date(given_time);
-        if util::is_in_past(date) {
+        } else if util::as_date(given_time).is_ok_and(util::is_in_past) {
             errors.push(ValidationError::new("start_time", "should be a future date."));
         }
 
@@ -254,6 +254,8 @@ pub struct UpdateTaskRequest {
     pub description: String,
     pub start_time: String,
     pub duration: i32,
+    /** Whether the actors of the successors moved by the edit get a notice. */
+    pub notify_actors: Option<bool>,
 }
 
 impl UpdateTaskRequest {
@@ -267,10 +269,7 @@ impl UpdateTaskRequest {
 
         if !util::is_valid_date(given_time) {
             errors.push(ValidationError::new("start_time", "unparsable date."));
-        }
-
-        let date = util::as_date(given_time);
-        if util::is_in_past(date) {
+        } else if util::as_date(given_time).is_ok_and(util::is_in_past) {
             errors.push(ValidationError::new("start_time", "should be a future date."));
         }
 
@@ -289,29 +288,56 @@ pub struct NewTask {
     pub enrollment_id: String,
     pub actor_id: String,
     pub duration: i32,
+    pub min: i32,
+    pub max: i32,
     pub original_start_date: NaiveDateTime,
     pub original_end_date: NaiveDateTime,
     pub description: String,
     pub name: String,
+    pub master_plan_id: Option<String>,
 }
 
 impl NewTask {
-    pub fn from(request: &NewTaskRequest) -> NewTask {
-        let start_date = util::as_date(request.start_time.as_str());
+    pub fn from(request: &NewTaskRequest) -> Result<NewTask, &'static str> {
+        let start_date = util::as_date(request.start_time.as_str())?;
         let duration = Duration::hours(request.duration as i64);
         let end_date = start_date.checked_add_signed(duration);
 
         let fuzzy_id = util::fuzzy_id();
 
-        NewTask {
+        Ok(NewTask {
             id: fuzzy_id,
             enrollment_id: request.enrollment_id.to_owned(),
             actor_id: request.actor_id.to_owned(),
             duration: request.duration,
+            min: request.duration,
+            max: request.duration,
             original_start_date: start_date,
             original_end_date: end_date.unwrap_or(start_date),
             description: request.description.to_owned(),
             name: request.name.to_owned(),
+            master_plan_id: None,
+        })
+    }
+
+    /**
+     * A task of the enrollment, as an instance of a task of the master plan.
+     */
+    pub fn for_plan(master_task: &MasterTask, the_enrollment_id: &str, the_actor_id: &str, name: &str, description: &str, schedule: &Schedule) -> NewTask {
+        let fuzzy_id = util::fuzzy_id();
+
+        NewTask {
+            id: fuzzy_id,
+            enrollment_id: the_enrollment_id.to_owned(),
+            actor_id: the_actor_id.to_owned(),
+            duration: master_task.duration,
+            min: master_task.min,
+            max: master_task.max,
+            original_start_date: schedule.start_date,
+            original_end_date: schedule.end_date,
+            description: description.to_owned(),
+            name: name.to_owned(),
+            master_plan_id: Some(master_task.master_plan_id.to_owned()),
         }
     }
 }
@@ -81,13 +81,13 @@ impl BoardRow {
 
 type Row = (Enrollment, Session);
 
-pub fn get_boards(connection: &MysqlConnection, criteria: EventCriteria) -> Result<Vec<BoardRow>, diesel::result::Error> {
+pub fn get_boards(connection: &MysqlConnection, the_user_id: &str, criteria: EventCriteria) -> Result<Vec<BoardRow>, diesel::result::Error> {
     let prog_id = criteria.program_id.unwrap();
 
     let rows: Vec<Row> = enrollments
         .inner_join(sessions)
         .filter(enrollments::program_id.eq(&prog_id))
-        .filter(enrollments::member_id.eq(&criteria.user_id))
+        .filter(enrollments::member_id.eq(the_user_id))
         .order_by(sessions::updated_at.asc())
         .load(connection)?;
 
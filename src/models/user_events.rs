@@ -1,3 +1,4 @@
+use chrono_tz::Tz;
 use diesel::prelude::*;
 
 use crate::commons::util;
@@ -30,9 +31,12 @@ use crate::schema::users::dsl::*;
 
 pub const BAD_QUERY: &str = "Error in executing the query";
 
+/**
+ * The dates (YYYY-MM-DD) of the criteria are the days as seen by the
+ * user, in his/her time zone.
+ */
 #[derive(juniper::GraphQLInputObject)]
 pub struct EventCriteria {
-    pub user_id: String,
     pub program_id: Option<String>,
     pub start_date: Option<String>,
     pub end_date: Option<String>,
@@ -61,11 +65,13 @@ impl EventRow {
 
 type SessionProgram = (Session, Program, SessionUser);
 
-pub fn get_events(connection: &MysqlConnection, criteria: EventCriteria) -> Result<Vec<EventRow>, QueryError> {
+pub fn get_events(connection: &MysqlConnection, the_user_id: &str, criteria: EventCriteria) -> Result<Vec<EventRow>, QueryError> {
+    let zone = find_zone(connection, the_user_id);
+
     let mut query = sessions
         .inner_join(programs)
         .inner_join(session_users)
-        .filter(session_users::user_id.eq(criteria.user_id))
+        .filter(session_users::user_id.eq(the_user_id))
         .order_by(sessions::original_start_date.asc())
         .into_boxed();
 
@@ -74,12 +80,12 @@ pub fn get_events(connection: &MysqlConnection, criteria: EventCriteria) -> Resu
     }
 
     if let Some(date) = criteria.start_date {
-        let start_date = util::as_start_date(date.as_str())?;
+        let start_date = util::as_start_date(date.as_str(), zone)?;
         query = query.filter(sessions::original_start_date.ge(start_date))
     }
 
     if let Some(date) = criteria.end_date {
-        let end_date = util::as_end_date(date.as_str())?;
+        let end_date = util::as_end_date(date.as_str(), zone)?;
         query = query.filter(sessions::original_start_date.le(end_date));
     }
 
@@ -122,20 +128,20 @@ impl PlanRow {
 }
 
 type TaskRowType = (Task, (Enrollment, Program));
-fn get_task_events(connection: &MysqlConnection, criteria: &EventCriteria) -> Result<Vec<TaskRowType>, String> {
+fn get_task_events(connection: &MysqlConnection, the_user_id: &str, criteria: &EventCriteria, zone: Tz) -> Result<Vec<TaskRowType>, String> {
     let mut query = tasks
         .inner_join(enrollments.inner_join(programs))
-        .filter(member_id.eq(&criteria.user_id))
+        .filter(member_id.eq(the_user_id))
         .order_by(tasks::original_start_date.asc())
         .into_boxed();
 
     if let Some(date) = &criteria.start_date {
-        let start_date = util::as_start_date(date)?;
+        let start_date = util::as_start_date(date, zone)?;
         query = query.filter(tasks::original_start_date.ge(start_date));
     } 
 
     if let Some(date) = &criteria.end_date {
-        let end_date = util::as_end_date(date)?;
+        let end_date = util::as_end_date(date, zone)?;
         query = query.filter(tasks::original_start_date.le(end_date));
     }
 
@@ -153,21 +159,21 @@ fn get_task_events(connection: &MysqlConnection, criteria: &EventCriteria) -> Re
 }
 
 type ObjectiveRowType = (Objective, (Enrollment, Program));
-fn get_objective_events(connection: &MysqlConnection, criteria: &EventCriteria) -> Result<Vec<ObjectiveRowType>, String> {
+fn get_objective_events(connection: &MysqlConnection, the_user_id: &str, criteria: &EventCriteria, zone: Tz) -> Result<Vec<ObjectiveRowType>, String> {
     let mut query = objectives
         .inner_join(enrollments.inner_join(programs))
-        .filter(member_id.eq(&criteria.user_id))
+        .filter(member_id.eq(the_user_id))
         .order_by(objectives::original_start_date.asc())
         .into_boxed();
 
     if criteria.start_date.is_some() {
         let start_date = criteria.start_date.as_ref().unwrap().as_str();
-        let date = util::as_start_date(start_date)?;
+        let date = util::as_start_date(start_date, zone)?;
         query = query.filter(objectives::original_start_date.ge(date));
     }
     if criteria.end_date.is_some() {
         let end_date = criteria.end_date.as_ref().unwrap().as_str();
-        let date = util::as_end_date(end_date)?;
+        let date = util::as_end_date(end_date, zone)?;
         query = query.filter(objectives::original_start_date.le(date));
     }
 
@@ -181,21 +187,21 @@ fn get_objective_events(connection: &MysqlConnection, criteria: &EventCriteria)
 }
 
 type NoteRowType = (Note, (Session, Program));
-fn get_notes_events(connection: &MysqlConnection, criteria: &EventCriteria) -> Result<Vec<NoteRowType>, String> {
+fn get_notes_events(connection: &MysqlConnection, the_user_id: &str, criteria: &EventCriteria, zone: Tz) -> Result<Vec<NoteRowType>, String> {
     let mut query = session_notes
         .inner_join(sessions.inner_join(programs))
-        .filter(created_by_id.eq(&criteria.user_id))
+        .filter(created_by_id.eq(the_user_id))
         .filter(remind_at.is_not_null())
         .into_boxed();
 
     if criteria.start_date.is_some() {
         let start_date = criteria.start_date.as_ref().unwrap().as_str();
-        let date = util::as_start_date(start_date)?;
+        let date = util::as_start_date(start_date, zone)?;
         query = query.filter(session_notes::remind_at.ge(date))
     }
     if criteria.end_date.is_some() {
         let end_date = criteria.end_date.as_ref().unwrap().as_str();
-        let date = util::as_end_date(end_date)?;
+        let date = util::as_end_date(end_date, zone)?;
         query = query.filter(session_notes::remind_at.le(date))
     }
 
@@ -208,12 +214,13 @@ fn get_notes_events(connection: &MysqlConnection, criteria: &EventCriteria) -> R
     Ok(result.unwrap())
 }
 
-pub fn get_plan_events(connection: &MysqlConnection, criteria: EventCriteria) -> Result<Vec<PlanRow>, String> {
+pub fn get_plan_events(connection: &MysqlConnection, the_user_id: &str, criteria: EventCriteria) -> Result<Vec<PlanRow>, String> {
+    let zone = find_zone(connection, the_user_id);
     let mut plan_rows: Vec<PlanRow> = Vec::new();
 
-    let objective_rows: Vec<ObjectiveRowType> = get_objective_events(connection, &criteria)?;
-    let task_rows: Vec<TaskRowType> = get_task_events(connection, &criteria)?;
-    let note_rows: Vec<NoteRowType> = get_notes_events(connection, &criteria)?;
+    let objective_rows: Vec<ObjectiveRowType> = get_objective_events(connection, the_user_id, &criteria, zone)?;
+    let task_rows: Vec<TaskRowType> = get_task_events(connection, the_user_id, &criteria, zone)?;
+    let note_rows: Vec<NoteRowType> = get_notes_events(connection, the_user_id, &criteria, zone)?;
 
     for row in objective_rows {
         plan_rows.push(PlanRow {
@@ -251,17 +258,17 @@ pub fn get_plan_events(connection: &MysqlConnection, criteria: EventCriteria) ->
  *
  * We consider the end date as a reference point
  */
-fn get_member_due_tasks(connection: &MysqlConnection, criteria: &EventCriteria) -> Result<Vec<TaskRowType>, String> {
+fn get_member_due_tasks(connection: &MysqlConnection, the_user_id: &str, criteria: &EventCriteria, zone: Tz) -> Result<Vec<TaskRowType>, String> {
     let mut query = tasks
         .inner_join(enrollments.inner_join(programs))
-        .filter(member_id.eq(&criteria.user_id))
+        .filter(member_id.eq(the_user_id))
         .filter(tasks::responded_date.is_null())
         .order_by(tasks::original_start_date.asc())
         .into_boxed();
 
     if criteria.end_date.is_some() {
         let end_date = criteria.end_date.as_ref().unwrap().as_str();
-        let date = util::as_end_date(end_date)?;
+        let date = util::as_end_date(end_date, zone)?;
         query = query.filter(tasks::original_end_date.le(date));
     }
 
@@ -283,11 +290,11 @@ fn get_member_due_tasks(connection: &MysqlConnection, criteria: &EventCriteria)
  */
 
 type CoachTaskRowType = (Task, User, (Enrollment, Program));
-fn get_coach_due_tasks(connection: &MysqlConnection, criteria: &EventCriteria) -> Result<Vec<CoachTaskRowType>, String> {
+fn get_coach_due_tasks(connection: &MysqlConnection, the_user_id: &str, criteria: &EventCriteria, zone: Tz) -> Result<Vec<CoachTaskRowType>, String> {
     let mut query = tasks
         .inner_join(users)
         .inner_join(enrollments.inner_join(programs))
-        .filter(coach_id.eq(&criteria.user_id))
+        .filter(coach_id.eq(the_user_id))
         .filter(tasks::responded_date.is_not_null())
         .filter(tasks::actual_end_date.is_null())
         .order_by(tasks::original_start_date.asc())
@@ -295,7 +302,7 @@ fn get_coach_due_tasks(connection: &MysqlConnection, criteria: &EventCriteria) -
 
     if criteria.end_date.is_some() {
         let end_date = criteria.end_date.as_ref().unwrap().as_str();
-        let date = util::as_end_date(end_date)?;
+        let date = util::as_end_date(end_date, zone)?;
         query = query.filter(tasks::original_end_date.le(date));
     }
 
@@ -328,9 +335,10 @@ impl ToDo {
     }
 }
 
-pub fn get_to_dos(connection: &MysqlConnection, criteria: EventCriteria) -> Result<Vec<ToDo>, String> {
-    let member_tasks = get_member_due_tasks(connection, &criteria)?;
-    let coach_tasks = get_coach_due_tasks(connection, &criteria)?;
+pub fn get_to_dos(connection: &MysqlConnection, the_user_id: &str, criteria: EventCriteria) -> Result<Vec<ToDo>, String> {
+    let zone = find_zone(connection, the_user_id);
+    let member_tasks = get_member_due_tasks(connection, the_user_id, &criteria, zone)?;
+    let coach_tasks = get_coach_due_tasks(connection, the_user_id, &criteria, zone)?;
 
     let mut to_dos: Vec<ToDo> = Vec::new();
 
@@ -354,3 +362,9 @@ pub fn get_to_dos(connection: &MysqlConnection, criteria: EventCriteria) -> Resu
 
     Ok(to_dos)
 }
+
+fn find_zone(connection: &MysqlConnection, the_user_id: &str) -> Tz {
+    let result: QueryResult<User> = users.find(the_user_id).first(connection);
+
+    result.map(|user| user.zone()).unwrap_or(Tz::UTC)
+}
@@ -1,11 +1,14 @@
 use diesel::prelude::*;
 
+use crate::commons::authz::{authorize, Rule};
 use crate::models::coaches::Coach;
 use crate::models::enrollments::Enrollment;
+use crate::models::program_plans::ProgramPlan;
 use crate::models::programs::Program;
 
 use crate::schema::coaches::dsl::*;
 use crate::schema::enrollments::dsl::*;
+use crate::schema::program_plans;
 use crate::schema::programs;
 use crate::schema::programs::dsl::*;
 
@@ -19,7 +22,6 @@ pub enum Desire {
 
 #[derive(juniper::GraphQLInputObject)]
 pub struct ProgramCriteria {
-    user_id: String,
     program_id: String,
     desire: Desire,
 }
@@ -36,6 +38,7 @@ pub struct ProgramRow {
     pub coach: Coach,
     pub enrollment_id: String,
     pub enrollment_status: EnrollmentStatus,
+    pub plans: Vec<ProgramPlan>,
 }
 
 #[juniper::object]
@@ -55,29 +58,59 @@ impl ProgramRow {
     pub fn enrollment_id(&self) -> &str {
         &self.enrollment_id
     }
+
+    pub fn plans(&self) -> &Vec<ProgramPlan> {
+        &self.plans
+    }
 }
 
 type ProgramType = (Program, Coach);
 
 pub type ProgramResult = Result<Vec<ProgramRow>, diesel::result::Error>;
 
-pub fn get_programs(connection: &MysqlConnection, criteria: &ProgramCriteria) -> ProgramResult {
-    match &criteria.desire {
+pub fn get_programs(connection: &MysqlConnection, the_user_id: &str, criteria: &ProgramCriteria) -> ProgramResult {
+    let rows = match &criteria.desire {
         Desire::EXPLORE => get_latest_programs(connection),
-        Desire::ENROLLED => get_enrolled_programs(connection, criteria),
-        Desire::YOURS => get_coach_programs(connection, criteria),
-        Desire::SINGLE => find_program(connection, criteria),
+        Desire::ENROLLED => get_enrolled_programs(connection, the_user_id),
+        Desire::YOURS => get_coach_programs(connection, the_user_id),
+        Desire::SINGLE => find_program(connection, the_user_id, criteria),
+    }?;
+
+    with_plans(connection, the_user_id, rows)
+}
+
+/**
+ * Attaches the master plans of each program to its row, only for the
+ * coaches of the program (as with get_program_plans).
+ */
+fn with_plans(connection: &MysqlConnection, the_user_id: &str, mut rows: Vec<ProgramRow>) -> ProgramResult {
+    let program_ids: Vec<&str> = rows
+        .iter()
+        .map(|row| row.program.id.as_str())
+        .filter(|the_program_id| authorize(connection, the_user_id, Rule::CoachesProgram(the_program_id)).is_ok())
+        .collect();
+
+    if program_ids.is_empty() {
+        return Ok(rows);
+    }
+
+    let plans: Vec<ProgramPlan> = program_plans::table.filter(program_plans::program_id.eq_any(program_ids)).order_by(program_plans::name.asc()).load(connection)?;
+
+    for row in rows.iter_mut() {
+        row.plans = plans.iter().filter(|plan| plan.program_id == row.program.id).cloned().collect();
     }
+
+    Ok(rows)
 }
 
 /**
  * The enrollment may be directly in the parent program or in one of the children
  */
-fn get_enrollment(connection: &MysqlConnection, criteria: &ProgramCriteria, _parent_program_id: &str) -> Option<Enrollment> {
+fn get_enrollment(connection: &MysqlConnection, the_user_id: &str, _parent_program_id: &str) -> Option<Enrollment> {
 
     let prog_query = programs.filter(parent_program_id.eq(_parent_program_id)).select(programs::id);
 
-    let enrollment_data: QueryResult<Enrollment> = enrollments.filter(member_id.eq(&criteria.user_id)).filter(program_id.eq_any(prog_query)).first(connection);
+    let enrollment_data: QueryResult<Enrollment> = enrollments.filter(member_id.eq(the_user_id)).filter(program_id.eq_any(prog_query)).first(connection);
 
     match enrollment_data {
         Ok(enrollment) => Some(enrollment),
@@ -99,7 +132,7 @@ fn get_enrollment(connection: &MysqlConnection, criteria: &ProgramCriteria, _par
  * If None of the above
  *    Return allway the Parent Program
  */
-fn find_program(connection: &MysqlConnection, criteria: &ProgramCriteria) -> ProgramResult {
+fn find_program(connection: &MysqlConnection, the_user_id: &str, criteria: &ProgramCriteria) -> ProgramResult {
 
     // Grep the Program by the given Id
     let result: (Program, Coach) = programs.inner_join(coaches).filter(programs::id.eq(&criteria.program_id)).first(connection)?;
@@ -107,7 +140,7 @@ fn find_program(connection: &MysqlConnection, criteria: &ProgramCriteria) -> Pro
     let coach = result.1;
 
     // Check is an enrolled Member
-    let enrollment_result = get_enrollment(connection, criteria, program.coalesce_parent_id());
+    let enrollment_result = get_enrollment(connection, the_user_id, program.coalesce_parent_id());
     if let Some(enrollment) = enrollment_result {
         return find_enrolled_program(connection, &enrollment);
     }
@@ -117,6 +150,7 @@ fn find_program(connection: &MysqlConnection, criteria: &ProgramCriteria) -> Pro
         coach,
         enrollment_id: String::from(""),
         enrollment_status: EnrollmentStatus::NO,
+        plans: Vec::new(),
     };
 
     Ok(vec![program_row])
@@ -133,15 +167,16 @@ fn find_enrolled_program(connection: &MysqlConnection, enrollment: &Enrollment)
         coach:result.1,
         enrollment_id: enrollment.id.to_owned(),
         enrollment_status: EnrollmentStatus::YES,
+        plans: Vec::new(),
     };
 
     Ok(vec![program_row])
 }
 
-fn get_enrolled_programs(connection: &MysqlConnection, criteria: &ProgramCriteria) -> ProgramResult {
+fn get_enrolled_programs(connection: &MysqlConnection, the_user_id: &str) -> ProgramResult {
     type Row = (Enrollment, ProgramType);
 
-    let data: Vec<Row> = enrollments.inner_join(programs.inner_join(coaches)).filter(member_id.eq(&criteria.user_id)).load(connection)?;
+    let data: Vec<Row> = enrollments.inner_join(programs.inner_join(coaches)).filter(member_id.eq(the_user_id)).load(connection)?;
 
     let mut rows: Vec<ProgramRow> = Vec::new();
 
@@ -153,16 +188,17 @@ fn get_enrolled_programs(connection: &MysqlConnection, criteria: &ProgramCriteri
             coach: pc.1,
             enrollment_id: enrollment.id,
             enrollment_status: EnrollmentStatus::YES,
+            plans: Vec::new(),
         });
     }
 
     Ok(rows)
 }
 
-fn get_coach_programs(connection: &MysqlConnection, criteria: &ProgramCriteria) -> ProgramResult {
+fn get_coach_programs(connection: &MysqlConnection, the_user_id: &str) -> ProgramResult {
     use crate::schema::coaches::dsl::id;
 
-    let data: Vec<ProgramType> = programs.inner_join(coaches).filter(id.eq(&criteria.user_id)).order_by(name.asc()).load(connection)?;
+    let data: Vec<ProgramType> = programs.inner_join(coaches).filter(id.eq(the_user_id)).order_by(name.asc()).load(connection)?;
 
     Ok(to_program_rows(data))
 }
@@ -191,6 +227,7 @@ fn to_program_rows(data: Vec<ProgramType>) -> Vec<ProgramRow> {
             coach: pc.1,
             enrollment_id: String::from(""),
             enrollment_status: EnrollmentStatus::UNKNOWN,
+            plans: Vec::new(),
         });
     }
 
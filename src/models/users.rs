@@ -3,6 +3,7 @@
 // The users table houses all the users of this platform.
 
 use chrono::NaiveDateTime;
+use chrono_tz::Tz;
 
 use super::ferror::{Ferror};
 
@@ -10,6 +11,12 @@ use crate::commons::chassis::{ValidationError};
 use crate::commons::util;
 use crate::schema::users;
 
+// The account_status of a user. A new user stays unverified till the email
+// address is verified through the link mailed out during the registration.
+pub const UNVERIFIED: &str = "unverified";
+pub const ACTIVE: &str = "active";
+pub const BLOCKED: &str = "blocked";
+
 // The Order of the fiels are very important
 // The User struct is purely for internal consumption.
 // See the Juniper:object for the fields we exposed to outside
@@ -23,6 +30,8 @@ pub struct User {
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
     pub password: String,
+    pub account_status: String,
+    pub time_zone: String,
 }
 
 // Fields that we can safely expose to APIs
@@ -43,6 +52,29 @@ impl User {
     pub fn user_type(&self) -> &str {
         self.user_type.as_str()
     }
+
+    pub fn account_status(&self) -> &str {
+        self.account_status.as_str()
+    }
+
+    pub fn time_zone(&self) -> &str {
+        self.time_zone.as_str()
+    }
+}
+
+impl User {
+    pub fn is_verified(&self) -> bool {
+        self.account_status != UNVERIFIED
+    }
+
+    pub fn is_blocked(&self) -> bool {
+        self.blocked || self.account_status == BLOCKED
+    }
+
+    // The time zone is validated before it is saved; UTC covers the rest.
+    pub fn zone(&self) -> Tz {
+        util::as_zone(self.time_zone.as_str()).unwrap_or(Tz::UTC)
+    }
 }
 
 // Registration represents the fields we obtain from user
@@ -52,6 +84,7 @@ pub struct Registration {
     pub full_name: String,
     pub email: String,
     pub password: String,
+    pub time_zone: Option<String>,
 }
 
 impl Registration {
@@ -60,6 +93,8 @@ impl Registration {
 
         if self.email.trim().is_empty() {
             errors.push("email", "email is a must for registration");
+        } else if !is_valid_email(self.email.trim()) {
+            errors.push("email", "email address is not valid");
         }
 
         if self.password.trim().is_empty() {
@@ -70,6 +105,12 @@ impl Registration {
             errors.push("full_name", "Full name of the user is a must for registration");
         }
 
+        if let Some(zone_name) = &self.time_zone {
+            if !util::is_valid_zone(zone_name) {
+                errors.push("time_zone", "should be an IANA time zone, say Asia/Kolkata");
+            }
+        }
+
         if errors.is_empty() {
            return Ok(()); 
         }
@@ -78,6 +119,18 @@ impl Registration {
     }
 }
 
+// A light check on the shape of the address; the verification mail
+// confirms that the address really belongs to the user.
+fn is_valid_email(email: &str) -> bool {
+    let parts: Vec<&str> = email.split('@').collect();
+    if parts.len() != 2 || parts[0].is_empty() || email.contains(char::is_whitespace) {
+        return false;
+    }
+
+    let domain = parts[1];
+    domain.contains('.') && !domain.starts_with('.') && !domain.ends_with('.')
+}
+
 // Fields we require to persist into the users table
 #[derive(Insertable)]
 #[table_name = "users"]
@@ -87,6 +140,8 @@ pub struct NewUser {
     pub email: String,
     pub user_type: String,
     pub password: String,
+    pub account_status: String,
+    pub time_zone: String,
 }
 
 // A way to transform the inbound registration request into the persistable
@@ -104,6 +159,8 @@ impl NewUser {
             email: registration.email.to_owned(),
             user_type: String::from(util::MEMBER),
             password: util::hash(registration.password.as_str()),
+            account_status: String::from(UNVERIFIED),
+            time_zone: registration.time_zone.clone().unwrap_or_else(|| String::from(util::UTC_ZONE)),
         }
     }
 }
@@ -115,6 +172,24 @@ pub struct LoginRequest {
     pub password: String,
 }
 
+// The authenticated User along with the session token to be offered
+// through the Authorization header for the subsequent requests.
+pub struct Login {
+    pub user: User,
+    pub token: String,
+}
+
+#[juniper::object(description = "The User and the session token issued after a successful login.")]
+impl Login {
+    pub fn user(&self) -> &User {
+        &self.user
+    }
+
+    pub fn token(&self) -> &str {
+        self.token.as_str()
+    }
+}
+
 #[derive(juniper::GraphQLInputObject)]
 pub struct ResetPasswordRequest {
     pub email: String,
@@ -142,7 +217,26 @@ impl ResetPasswordRequest {
     }
 }
 
+// The dates in the mails and the day ranges of the queries follow this zone
+#[derive(juniper::GraphQLInputObject)]
+pub struct TimeZoneRequest {
+    pub time_zone: String,
+}
+
+impl TimeZoneRequest {
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors: Vec<ValidationError> = Vec::new();
+
+        if !util::is_valid_zone(self.time_zone.as_str()) {
+            errors.push(ValidationError::new("time_zone", "should be an IANA time zone, say Asia/Kolkata."));
+        }
+
+        errors
+    }
+}
+
 #[derive(juniper::GraphQLInputObject)]
 pub struct UserCriteria {
-    pub id: String,
+    /** None for the caller. */
+    pub id: Option<String>,
 }
\ No newline at end of file
@@ -6,6 +6,41 @@ table! {
     }
 }
 
+table! {
+    blackouts (id) {
+        id -> Varchar,
+        coach_id -> Varchar,
+        start_date -> Datetime,
+        end_date -> Datetime,
+        reason -> Nullable<Varchar>,
+        created_at -> Datetime,
+    }
+}
+
+table! {
+    calendar_feeds (id) {
+        id -> Varchar,
+        user_id -> Varchar,
+        token_digest -> Varchar,
+        revoked_at -> Nullable<Datetime>,
+        created_at -> Datetime,
+    }
+}
+
+table! {
+    coach_applications (id) {
+        id -> Varchar,
+        user_id -> Varchar,
+        full_name -> Varchar,
+        email -> Varchar,
+        status -> Varchar,
+        reviewed_by_id -> Nullable<Varchar>,
+        reviewed_at -> Nullable<Datetime>,
+        created_at -> Datetime,
+        updated_at -> Datetime,
+    }
+}
+
 table! {
     coaches (id) {
         id -> Varchar,
@@ -46,8 +81,8 @@ table! {
     correspondences (id) {
         id -> Varchar,
         from_user_id -> Varchar,
-        program_id -> Varchar,
-        enrollment_id -> Varchar,
+        program_id -> Nullable<Varchar>,
+        enrollment_id -> Nullable<Varchar>,
         from_email -> Varchar,
         subject -> Varchar,
         content -> Nullable<Text>,
@@ -61,6 +96,8 @@ table! {
         created_at -> Datetime,
         updated_at -> Datetime,
         mail_type -> Varchar,
+        attempts -> Integer,
+        calendar -> Nullable<Text>,
     }
 }
 
@@ -113,12 +150,34 @@ table! {
     }
 }
 
+table! {
+    master_plan_revisions (id) {
+        id -> Varchar,
+        master_plan_id -> Varchar,
+        version -> Integer,
+        snapshot -> Text,
+        saved_by_id -> Varchar,
+        created_at -> Datetime,
+    }
+}
+
+table! {
+    master_plan_shares (id) {
+        id -> Varchar,
+        master_plan_id -> Varchar,
+        program_id -> Varchar,
+        shared_by_id -> Varchar,
+        created_at -> Datetime,
+    }
+}
+
 table! {
     master_plans (id) {
         id -> Varchar,
         name -> Varchar,
         description -> Text,
         coach_id -> Varchar,
+        version -> Integer,
     }
 }
 
@@ -190,6 +249,17 @@ table! {
     }
 }
 
+table! {
+    password_resets (id) {
+        id -> Varchar,
+        user_id -> Varchar,
+        token_digest -> Varchar,
+        expires_at -> Datetime,
+        used_at -> Nullable<Datetime>,
+        created_at -> Datetime,
+    }
+}
+
 table! {
     platform_roles (id) {
         id -> Varchar,
@@ -209,6 +279,7 @@ table! {
         description -> Text,
         master_plan_id -> Varchar,
         program_id -> Varchar,
+        is_default -> Bool,
     }
 }
 
@@ -256,6 +327,33 @@ table! {
     }
 }
 
+table! {
+    session_series (id) {
+        id -> Varchar,
+        program_id -> Varchar,
+        enrollment_id -> Varchar,
+        name -> Varchar,
+        description -> Nullable<Text>,
+        duration -> Integer,
+        start_date -> Datetime,
+        rule -> Varchar,
+        exceptions -> Nullable<Text>,
+        time_zone -> Varchar,
+        created_at -> Datetime,
+        updated_at -> Datetime,
+    }
+}
+
+table! {
+    session_slots (id) {
+        id -> Varchar,
+        session_id -> Varchar,
+        start_date -> Datetime,
+        end_date -> Datetime,
+        created_at -> Datetime,
+    }
+}
+
 table! {
     session_users (id) {
         id -> Varchar,
@@ -290,6 +388,8 @@ table! {
         is_request -> Bool,
         conference_id -> Nullable<Varchar>,
         session_type -> Char,
+        sequence -> Integer,
+        series_id -> Nullable<Varchar>,
     }
 }
 
@@ -306,6 +406,20 @@ table! {
     }
 }
 
+table! {
+    task_moves (id) {
+        id -> Varchar,
+        enrollment_id -> Varchar,
+        task_id -> Varchar,
+        cause_task_id -> Varchar,
+        from_start_date -> Datetime,
+        from_end_date -> Datetime,
+        to_start_date -> Datetime,
+        to_end_date -> Datetime,
+        created_at -> Datetime,
+    }
+}
+
 table! {
     tasks (id) {
         id -> Varchar,
@@ -332,6 +446,7 @@ table! {
         approved_at -> Nullable<Datetime>,
         cancelled_at -> Nullable<Datetime>,
         responded_date -> Nullable<Datetime>,
+        master_plan_id -> Nullable<Varchar>,
     }
 }
 
@@ -345,10 +460,26 @@ table! {
         created_at -> Datetime,
         updated_at -> Datetime,
         password -> Varchar,
+        account_status -> Varchar,
+        time_zone -> Varchar,
+    }
+}
+
+table! {
+    working_hours (id) {
+        id -> Varchar,
+        coach_id -> Varchar,
+        day_of_week -> Integer,
+        start_minute -> Integer,
+        end_minute -> Integer,
+        created_at -> Datetime,
     }
 }
 
 joinable!(abstract_tasks -> coaches (coach_id));
+joinable!(blackouts -> coaches (coach_id));
+joinable!(calendar_feeds -> users (user_id));
+joinable!(coach_applications -> users (user_id));
 joinable!(coaches -> users (user_id));
 joinable!(conferences -> programs (program_id));
 joinable!(correspondences -> enrollments (enrollment_id));
@@ -363,6 +494,11 @@ joinable!(enrollments -> programs (program_id));
 joinable!(enrollments -> users (member_id));
 joinable!(mail_recipients -> correspondences (correspondence_id));
 joinable!(mail_recipients -> users (to_user_id));
+joinable!(master_plan_revisions -> master_plans (master_plan_id));
+joinable!(master_plan_revisions -> users (saved_by_id));
+joinable!(master_plan_shares -> master_plans (master_plan_id));
+joinable!(master_plan_shares -> programs (program_id));
+joinable!(master_plan_shares -> users (shared_by_id));
 joinable!(master_plans -> coaches (coach_id));
 joinable!(master_task_links -> master_plans (master_plan_id));
 joinable!(master_tasks -> abstract_tasks (abstract_task_id));
@@ -372,6 +508,7 @@ joinable!(master_tasks -> platform_roles (role_id));
 joinable!(objectives -> enrollments (enrollment_id));
 joinable!(observations -> enrollments (enrollment_id));
 joinable!(options -> enrollments (enrollment_id));
+joinable!(password_resets -> users (user_id));
 joinable!(program_plans -> master_plans (master_plan_id));
 joinable!(program_plans -> programs (program_id));
 joinable!(programs -> coaches (coach_id));
@@ -380,17 +517,28 @@ joinable!(session_files -> session_notes (session_note_id));
 joinable!(session_notes -> session_users (session_user_id));
 joinable!(session_notes -> sessions (session_id));
 joinable!(session_notes -> users (created_by_id));
+joinable!(session_series -> enrollments (enrollment_id));
+joinable!(session_series -> programs (program_id));
+joinable!(session_slots -> sessions (session_id));
 joinable!(session_users -> sessions (session_id));
 joinable!(session_users -> users (user_id));
 joinable!(sessions -> conferences (conference_id));
 joinable!(sessions -> enrollments (enrollment_id));
 joinable!(sessions -> programs (program_id));
+joinable!(sessions -> session_series (series_id));
 joinable!(task_links -> enrollments (enrollment_id));
+joinable!(task_moves -> enrollments (enrollment_id));
+joinable!(task_moves -> tasks (task_id));
 joinable!(tasks -> enrollments (enrollment_id));
+joinable!(tasks -> master_plans (master_plan_id));
 joinable!(tasks -> users (actor_id));
+joinable!(working_hours -> coaches (coach_id));
 
 allow_tables_to_appear_in_same_query!(
     abstract_tasks,
+    blackouts,
+    calendar_feeds,
+    coach_applications,
     coaches,
     conferences,
     correspondences,
@@ -398,21 +546,28 @@ allow_tables_to_appear_in_same_query!(
     discussions,
     enrollments,
     mail_recipients,
+    master_plan_revisions,
+    master_plan_shares,
     master_plans,
     master_task_links,
     master_tasks,
     objectives,
     observations,
     options,
+    password_resets,
     platform_roles,
     program_genres,
     program_plans,
     programs,
     session_files,
     session_notes,
+    session_series,
+    session_slots,
     session_users,
     sessions,
     task_links,
+    task_moves,
     tasks,
     users,
+    working_hours,
 );
@@ -6,7 +6,8 @@ use crate::models::users::LoginRequest;
 
 use crate::services::users::register;
 use crate::services::users::authenticate;
-use crate::services::users::INVALID_CREDENTIAL;
+use crate::services::users::{issue_verification_token, verify_email};
+use crate::services::users::{EMAIL_NOT_VERIFIED, INVALID_CREDENTIAL};
 
 #[test]
 pub fn should_authenticate_valid_user() {
@@ -18,12 +19,35 @@ pub fn should_authenticate_valid_user() {
         let reg_request = build_registration_request();
         let reg_result = register(&connection,&reg_request);
 
-        assert_eq!(reg_result.is_ok(),true);
+        assert!(reg_result.is_ok());
+
+        let verification_token = issue_verification_token(reg_result.unwrap().id.as_str());
+        let verify_result = verify_email(&connection, verification_token.as_str());
+
+        assert!(verify_result.is_ok());
 
         let request = build_known_login_request();
         let result = authenticate(&connection, request);
 
-        assert_eq!(result.is_ok(),true);
+        assert!(result.is_ok());
+
+        Ok(())
+    });
+}
+
+#[test]
+pub fn should_defend_unverified_user() {
+    let connection = connection_without_transaction();
+
+    connection.test_transaction::<_,String,_>(||{
+
+        let reg_request = build_registration_request();
+        let reg_result = register(&connection,&reg_request);
+        assert!(reg_result.is_ok());
+
+        let request = build_known_login_request();
+        let result = authenticate(&connection, request);
+        assert_eq!(result.unwrap_err(),EMAIL_NOT_VERIFIED);
 
         Ok(())
     });
@@ -37,11 +61,11 @@ pub fn should_defend_invalid_login() {
 
         let reg_request = build_registration_request();
         let reg_result = register(&connection,&reg_request);
-        assert_eq!(reg_result.is_ok(),true);
+        assert!(reg_result.is_ok());
 
         let request = build_invalid_login_request();
         let result  = authenticate(&connection, request);
-        assert_eq!(result.is_err(),true);
+        assert!(result.is_err());
         assert_eq!(result.unwrap_err(),INVALID_CREDENTIAL);
 
         Ok(())
@@ -56,6 +80,7 @@ fn build_registration_request() -> Registration {
         full_name: String::from("Full_Name-1"),
         email: String::from("email3@krscode.com"),
         password: String::from("password"),
+        time_zone: None,
     }
 }
 
@@ -0,0 +1,104 @@
+use diesel::prelude::*;
+use super::prelude::connection_without_transaction;
+
+use crate::commons::authz::{authorize, Rule, NOT_AUTHORIZED};
+
+#[test]
+pub fn should_not_authorize_unknown_program() {
+
+    let connection = connection_without_transaction();
+
+    connection.test_transaction::<_,String,_>(||{
+        let result = authorize(&connection, "coach-x", Rule::OwnsProgram("program-x"));
+
+        assert_eq!(result.unwrap_err(), NOT_AUTHORIZED);
+
+        Ok(())
+    });
+}
+
+#[test]
+pub fn should_not_authorize_unknown_task() {
+
+    let connection = connection_without_transaction();
+
+    connection.test_transaction::<_,String,_>(||{
+        let result = authorize(&connection, "member-x", Rule::OwnsTask("task-x"));
+
+        assert_eq!(result.unwrap_err(), NOT_AUTHORIZED);
+
+        Ok(())
+    });
+}
+
+#[test]
+pub fn should_not_authorize_unknown_enrollment() {
+
+    let connection = connection_without_transaction();
+
+    connection.test_transaction::<_,String,_>(||{
+        let result = authorize(&connection, "member-x", Rule::AttendsEnrollment("enrollment-x"));
+
+        assert_eq!(result.unwrap_err(), NOT_AUTHORIZED);
+
+        Ok(())
+    });
+}
+
+#[test]
+pub fn should_not_authorize_unknown_master_task() {
+
+    let connection = connection_without_transaction();
+
+    connection.test_transaction::<_,String,_>(||{
+        let result = authorize(&connection, "coach-x", Rule::OwnsMasterTask("master-task-x"));
+
+        assert_eq!(result.unwrap_err(), NOT_AUTHORIZED);
+
+        Ok(())
+    });
+}
+
+#[test]
+pub fn should_not_authorize_writing_in_unknown_enrollment() {
+
+    let connection = connection_without_transaction();
+
+    connection.test_transaction::<_,String,_>(||{
+        let result = authorize(&connection, "member-x", Rule::WritesInEnrollment("enrollment-x", "coach-x"));
+
+        assert_eq!(result.unwrap_err(), NOT_AUTHORIZED);
+
+        Ok(())
+    });
+}
+
+#[test]
+pub fn should_authorize_only_the_users_met() {
+
+    let connection = connection_without_transaction();
+
+    connection.test_transaction::<_,String,_>(||{
+        assert!(authorize(&connection, "member-x", Rule::MeetsUser("member-x")).is_ok());
+
+        let result = authorize(&connection, "member-x", Rule::MeetsUser("stranger-x"));
+
+        assert_eq!(result.unwrap_err(), NOT_AUTHORIZED);
+
+        Ok(())
+    });
+}
+
+#[test]
+pub fn should_not_authorize_unpaired_coach_and_member() {
+
+    let connection = connection_without_transaction();
+
+    connection.test_transaction::<_,String,_>(||{
+        let result = authorize(&connection, "member-x", Rule::PairsCoachMember("coach-x", "member-x"));
+
+        assert_eq!(result.unwrap_err(), NOT_AUTHORIZED);
+
+        Ok(())
+    });
+}
@@ -0,0 +1,80 @@
+use diesel::prelude::*;
+use super::prelude::*;
+
+use crate::services::calendar_feeds::{issue_feed, render_feed, revoke_feed, INVALID_FEED};
+use crate::services::users::register;
+
+use crate::models::users::Registration;
+
+#[test]
+pub fn should_render_the_feed_of_the_user() {
+
+    let connection = connection_without_transaction();
+
+    connection.test_transaction::<_,String,_>(||{
+
+        let user = register(&connection, &build_registration_request()).unwrap();
+
+        let feed_url = issue_feed(&connection, user.id.as_str()).unwrap();
+        let result = render_feed(&connection, feed_token_of(feed_url.as_str()));
+
+        assert!(result.unwrap().starts_with("BEGIN:VCALENDAR"));
+
+        Ok(())
+    });
+}
+
+/**
+ * Once revoked, the shared URL should not reveal anything.
+ */
+#[test]
+pub fn should_deny_a_revoked_feed() {
+
+    let connection = connection_without_transaction();
+
+    connection.test_transaction::<_,String,_>(||{
+
+        let user = register(&connection, &build_registration_request()).unwrap();
+
+        let feed_url = issue_feed(&connection, user.id.as_str()).unwrap();
+        revoke_feed(&connection, user.id.as_str()).unwrap();
+
+        let result = render_feed(&connection, feed_token_of(feed_url.as_str()));
+
+        assert_eq!(Err(INVALID_FEED), result);
+
+        Ok(())
+    });
+}
+
+#[test]
+pub fn should_revoke_the_earlier_feed_on_issue() {
+
+    let connection = connection_without_transaction();
+
+    connection.test_transaction::<_,String,_>(||{
+
+        let user = register(&connection, &build_registration_request()).unwrap();
+
+        let old_url = issue_feed(&connection, user.id.as_str()).unwrap();
+        let new_url = issue_feed(&connection, user.id.as_str()).unwrap();
+
+        assert!(render_feed(&connection, feed_token_of(old_url.as_str())).is_err());
+        assert!(render_feed(&connection, feed_token_of(new_url.as_str())).is_ok());
+
+        Ok(())
+    });
+}
+
+fn feed_token_of(feed_url: &str) -> &str {
+    feed_url.rsplit('/').next().unwrap().trim_end_matches(".ics")
+}
+
+fn build_registration_request() -> Registration {
+    Registration {
+        full_name: String::from("Full_Name-1"),
+        email: String::from("email1@krscode.com"),
+        password: String::from("password"),
+        time_zone: None,
+    }
+}
@@ -0,0 +1,63 @@
+use diesel::prelude::*;
+use super::prelude::*;
+
+use crate::models::coach_applications::{ApplicationTargetState, ReviewApplicationRequest, APPROVED, PENDING};
+use crate::models::users::Registration;
+
+use crate::services::coaches::{apply_as_coach, review_application, ALREADY_APPLIED};
+use crate::services::users::{find, find_coach_by_id, register};
+
+use crate::commons::util;
+
+#[test]
+pub fn should_not_apply_twice_while_pending() {
+
+    let connection = connection_without_transaction();
+
+    connection.test_transaction::<_,String,_>(||{
+        let user = register(&connection, &build_registration_request()).unwrap();
+
+        let application = apply_as_coach(&connection, user.id.as_str()).unwrap();
+        assert_eq!(application.status, PENDING);
+
+        let result = apply_as_coach(&connection, user.id.as_str());
+        assert_eq!(result.unwrap_err(), ALREADY_APPLIED);
+
+        Ok(())
+    });
+}
+
+#[test]
+pub fn should_promote_approved_applicant_to_coach() {
+
+    let connection = connection_without_transaction();
+
+    connection.test_transaction::<_,String,_>(||{
+        let user = register(&connection, &build_registration_request()).unwrap();
+        let application = apply_as_coach(&connection, user.id.as_str()).unwrap();
+
+        let request = ReviewApplicationRequest {
+            id: application.id.to_owned(),
+            target_state: ApplicationTargetState::APPROVE,
+        };
+        let reviewed = review_application(&connection, "admin-1", &request).unwrap();
+        assert_eq!(reviewed.status, APPROVED);
+
+        let coach = find_coach_by_id(&connection, user.id.as_str());
+        assert!(coach.is_ok());
+
+        let promoted = find(&connection, user.id.as_str()).unwrap();
+        assert_eq!(promoted.user_type, util::COACH);
+
+        Ok(())
+    });
+}
+
+fn build_registration_request() -> Registration {
+    Registration {
+        full_name: String::from("Applicant-1"),
+        email: String::from("applicant1@krscode.com"),
+        password: String::from("password"),
+        time_zone: None,
+    }
+}
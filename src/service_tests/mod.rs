@@ -2,6 +2,16 @@ pub mod prelude {
 
     use diesel::prelude::*;
 
+    use crate::models::coach_applications::{ApplicationTargetState, ReviewApplicationRequest};
+    use crate::models::enrollments::{Enrollment, ManagedEnrollmentRequest};
+    use crate::models::programs::NewProgramRequest;
+    use crate::models::users::{Registration, User};
+
+    use crate::services::coaches::{apply_as_coach, review_application};
+    use crate::services::enrollments::create_managed_enrollment;
+    use crate::services::programs::create_new_program;
+    use crate::services::users::{issue_verification_token, register, verify_email};
+
     fn get_test_database_url() -> String {
         dotenv::dotenv().ok();
         std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL environment variable should be test.")
@@ -11,12 +21,61 @@ pub mod prelude {
         let db_url = get_test_database_url();
         MysqlConnection::establish(db_url.as_str()).unwrap()
     }
+
+    /**
+     * A registered user with the email verified.
+     */
+    pub fn active_user(connection: &MysqlConnection, the_email: &str) -> User {
+        let user = register(connection, &Registration {
+            full_name: String::from("User-1"),
+            email: String::from(the_email),
+            password: String::from("password"),
+            time_zone: None,
+        }).unwrap();
+
+        verify_email(connection, issue_verification_token(user.id.as_str()).as_str()).unwrap()
+    }
+
+    /**
+     * A coach (approved by an admin) with a member enrolled into a program of the coach.
+     */
+    pub fn coach_with_member(connection: &MysqlConnection) -> (User, User, Enrollment) {
+        let coach = active_user(connection, "coach1@krscode.com");
+        let application = apply_as_coach(connection, coach.id.as_str()).unwrap();
+        let review = ReviewApplicationRequest {
+            id: application.id.to_owned(),
+            target_state: ApplicationTargetState::APPROVE,
+        };
+        review_application(connection, "admin-1", &review).unwrap();
+
+        let program = create_new_program(connection, coach.id.as_str(), &NewProgramRequest {
+            name: String::from("Program-1"),
+            description: String::from("desc"),
+            genre_id: None,
+            is_private: true,
+        }).unwrap();
+
+        let member = active_user(connection, "member1@krscode.com");
+        let enrollment = create_managed_enrollment(connection, coach.id.as_str(), &ManagedEnrollmentRequest {
+            program_id: program.id.to_owned(),
+            member_mail: member.email.to_owned(),
+            subject: String::from("Welcome"),
+            message: String::from("Welcome"),
+        }).unwrap();
+
+        (coach, member, enrollment)
+    }
 }
 
 pub mod authentication_feature;
+pub mod authorization_feature;
 pub mod registration_feature;
 pub mod password_reset_feature;
+pub mod calendar_feed_feature;
 
 pub mod program_creation_feature;
+pub mod coach_onboarding_feature;
+pub mod task_editing_feature;
+pub mod plan_application_feature;
 
 pub mod session_tests;
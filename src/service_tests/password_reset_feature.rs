@@ -3,10 +3,14 @@ use super::prelude::*;
 
 use crate::services::users::register;
 use crate::services::users::reset_password;
+use crate::services::users::{complete_password_reset, request_password_reset, INVALID_RESET_TOKEN};
 
+use crate::models::password_resets::{CompletePasswordResetRequest, NewPasswordReset};
 use crate::models::users::Registration;
 use crate::models::users::ResetPasswordRequest;
 
+use crate::schema::password_resets::dsl::password_resets;
+
 #[test]
 pub fn should_reset_password() {
 
@@ -53,6 +57,53 @@ pub fn should_deny_if_wrong_request() {
     });
 }
 
+/**
+ * The reset link can be used only once.
+ */
+#[test]
+pub fn should_complete_password_reset_only_once() {
+
+    let connection = connection_without_transaction();
+
+    connection.test_transaction::<_,String,_>(||{
+
+        let user = register(&connection,&build_registration_request()).unwrap();
+
+        let reset = NewPasswordReset::from(&user, "known-token");
+        diesel::insert_into(password_resets).values(&reset).execute(&connection).unwrap();
+
+        let request = build_complete_password_reset_request("known-token");
+        let result = complete_password_reset(&connection, &request);
+        assert!(result.is_ok());
+
+        let result = complete_password_reset(&connection, &request);
+        assert_eq!(result.unwrap_err(), INVALID_RESET_TOKEN);
+
+        Ok(())
+    });
+}
+
+#[test]
+pub fn should_not_disclose_unknown_email() {
+
+    let connection = connection_without_transaction();
+
+    connection.test_transaction::<_,String,_>(||{
+
+        let result = request_password_reset(&connection, "unknown@krscode.com");
+        assert!(result.is_ok());
+
+        Ok(())
+    });
+}
+
+fn build_complete_password_reset_request(token: &str) -> CompletePasswordResetRequest {
+    CompletePasswordResetRequest {
+        token: token.to_string(),
+        new_password: "new_password".to_string(),
+    }
+}
+
 fn build_reset_password_request() -> ResetPasswordRequest{
 
     ResetPasswordRequest{
@@ -79,5 +130,6 @@ fn build_registration_request() -> Registration {
         full_name: String::from("Full_Name-1"),
         email: String::from("email1@krscode.com"),
         password: String::from("password"),
+        time_zone: None,
     }
 }
\ No newline at end of file
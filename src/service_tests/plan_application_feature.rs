@@ -0,0 +1,56 @@
+use diesel::prelude::*;
+use super::prelude::*;
+
+use crate::models::abstract_tasks::NewAbstractTaskRequest;
+use crate::models::master_plans::{ApplyMasterPlanRequest, NewMasterPlanRequest};
+use crate::models::master_tasks::NewMasterTaskRequest;
+
+use crate::services::abstract_tasks::create_abstract_task;
+use crate::services::master_plans::{apply_master_plan, create_master_plan, PLAN_ALREADY_APPLIED};
+use crate::services::master_tasks::create_master_task;
+
+use crate::schema::platform_roles;
+
+#[test]
+pub fn should_not_apply_a_plan_twice() {
+
+    let connection = connection_without_transaction();
+
+    connection.test_transaction::<_,String,_>(||{
+        let (coach, _, enrollment) = coach_with_member(&connection);
+
+        diesel::insert_or_ignore_into(platform_roles::table).values(platform_roles::id.eq("member")).execute(&connection).unwrap();
+
+        let abstract_task = create_abstract_task(&connection, coach.id.as_str(), &NewAbstractTaskRequest { name: String::from("Task-1") }).unwrap();
+        let plan = create_master_plan(&connection, coach.id.as_str(), &NewMasterPlanRequest {
+            name: String::from("Plan-1"),
+            description: String::from("desc"),
+        }).unwrap();
+
+        create_master_task(&connection, coach.id.as_str(), &NewMasterTaskRequest {
+            master_plan_id: plan.id.to_owned(),
+            version: plan.version,
+            abstract_task_id: abstract_task.id.to_owned(),
+            duration: 2,
+            min: 1,
+            max: 3,
+            task_type: String::from("activity"),
+            role_id: String::from("member"),
+            coordinates: String::new(),
+        }).unwrap();
+
+        let request = ApplyMasterPlanRequest {
+            enrollment_id: enrollment.id.to_owned(),
+            master_plan_id: plan.id.to_owned(),
+            start_date: String::from("2030-01-01T09:00:00Z"),
+        };
+
+        assert_eq!(apply_master_plan(&connection, &request).unwrap().len(), 1);
+
+        let ferror = apply_master_plan(&connection, &request).unwrap_err();
+        assert_eq!(ferror.errors[0].field, "master_plan_id");
+        assert_eq!(ferror.errors[0].message, PLAN_ALREADY_APPLIED);
+
+        Ok(())
+    });
+}
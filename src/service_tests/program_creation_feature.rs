@@ -15,7 +15,7 @@ pub fn should_not_create_program_if_unknown_coach() {
     connection.test_transaction::<_,String,_>(||{
         let request = build_program_with_unknown_coach();
         
-        let result = create_new_program(&connection, &request);
+        let result = create_new_program(&connection, "coach-x", &request);
 
         assert_eq!(result.is_err(),true);
         
@@ -30,7 +30,6 @@ pub fn should_not_create_program_if_unknown_coach() {
 
 fn build_program_request() -> NewProgramRequest {
     NewProgramRequest{
-        coach_id:"coach-1".to_string(),
         name: "name-1".to_string(),
         description: "desc".to_string(),
         genre_id: None,
@@ -40,7 +39,6 @@ fn build_program_request() -> NewProgramRequest {
 
 fn build_program_with_unknown_coach() -> NewProgramRequest {
     NewProgramRequest{
-        coach_id:"coach-x".to_string(),
         name: "name-1".to_string(),
         description: "desc".to_string(),
         genre_id: None,
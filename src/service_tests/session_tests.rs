@@ -9,11 +9,10 @@ use crate::models::sessions::{NewSessionRequest};
 use crate::services::users::{register};
 use crate::services::sessions::{create_session};
 
-fn program_request(coach_id: String) -> NewProgramRequest {
+fn program_request() -> NewProgramRequest {
 
     NewProgramRequest{
         name: String::from("Program-1"),
-        coach_id: coach_id,
         description: String::from("Prog Description"),
         is_private: false,
         genre_id: None,
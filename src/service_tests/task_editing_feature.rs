@@ -0,0 +1,50 @@
+use diesel::prelude::*;
+use super::prelude::*;
+
+use crate::models::tasks::{NewTaskRequest, UpdateTaskRequest};
+
+use crate::services::tasks::{create_task, update_task};
+
+use crate::commons::util;
+use crate::schema::tasks;
+
+#[test]
+pub fn should_take_the_edit_of_a_moved_task() {
+
+    let connection = connection_without_transaction();
+
+    connection.test_transaction::<_,String,_>(||{
+        let (coach, member, enrollment) = coach_with_member(&connection);
+
+        let task = create_task(&connection, &NewTaskRequest {
+            enrollment_id: enrollment.id.to_owned(),
+            actor_id: member.id.to_owned(),
+            start_time: String::from("2030-01-01T09:00:00Z"),
+            duration: 2,
+            description: String::from("desc"),
+            name: String::from("Task-1"),
+        }).unwrap();
+
+        let moved_start = util::as_date("2030-01-05T09:00:00Z").unwrap();
+        let moved_end = util::as_date("2030-01-05T11:00:00Z").unwrap();
+        diesel::update(tasks::table.find(task.id.as_str()))
+            .set((tasks::revised_start_date.eq(moved_start), tasks::revised_end_date.eq(moved_end)))
+            .execute(&connection)
+            .unwrap();
+
+        let edited = update_task(&connection, coach.id.as_str(), &UpdateTaskRequest {
+            id: task.id.to_owned(),
+            name: String::from("Task-1"),
+            description: String::from("desc"),
+            start_time: String::from("2030-01-03T09:00:00Z"),
+            duration: 2,
+            notify_actors: None,
+        }).unwrap();
+
+        assert_eq!(edited.original_start_date, util::as_date("2030-01-03T09:00:00Z").unwrap());
+        assert_eq!(edited.revised_start_date, None);
+        assert_eq!(edited.revised_end_date, None);
+
+        Ok(())
+    });
+}
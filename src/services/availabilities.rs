@@ -0,0 +1,329 @@
+use chrono::{Duration, NaiveDateTime, Timelike};
+use chrono_tz::Tz;
+use diesel::prelude::*;
+
+use crate::commons::chassis::ValidationError;
+use crate::commons::util;
+
+use crate::models::availabilities::{covered_by, Availability, Blackout, CandidateSlot, NewBlackout, NewBlackoutRequest, NewWorkingHour, WorkingHour, WorkingHoursRequest};
+use crate::models::ferror::Ferror;
+use crate::models::session_users::SessionUser;
+use crate::models::sessions::Session;
+
+use crate::schema::blackouts;
+use crate::schema::session_users;
+use crate::schema::sessions;
+use crate::schema::working_hours;
+
+use crate::services::users::{find, find_coach_by_id};
+
+const WORKING_HOURS_ERROR: &str = "Unable to save the working hours.";
+const BLACKOUT_ERROR: &str = "Unable to save the blackout.";
+const INVALID_BLACKOUT: &str = "Invalid Blackout Id";
+const SCHEDULE_CHECK_ERROR: &str = "Unable to check the schedule for conflicts.";
+
+pub const OUTSIDE_WORKING_HOURS: &str = "The schedule is outside the working hours of the coach.";
+pub const IN_BLACKOUT: &str = "The coach is not available during the schedule.";
+pub const DOUBLE_BOOKED: &str = "The schedule clashes with other sessions.";
+
+const SHORT_DURATION: &str = "The duration should be a minimum of 15 minutes.";
+const BAD_RANGE: &str = "The range should end on or after it starts, spanning at most 31 days.";
+
+const SLOT_STEP_IN_MINUTES: i64 = 30;
+const MAX_RANGE_IN_DAYS: i64 = 31;
+const MAX_SLOTS: usize = 20;
+// A gap beyond this from the nearest engagement makes no difference to the rank
+const MAX_GAP_IN_MINUTES: i64 = 240;
+
+pub fn save_working_hours(connection: &MysqlConnection, the_coach_id: &str, request: &WorkingHoursRequest) -> Result<Vec<WorkingHour>, &'static str> {
+    find_coach_by_id(connection, the_coach_id)?;
+
+    let new_hours: Vec<NewWorkingHour> = request.hours.iter().map(|hour| NewWorkingHour::from(hour, the_coach_id)).collect();
+
+    let result = connection.transaction::<_, diesel::result::Error, _>(|| {
+        diesel::delete(working_hours::table.filter(working_hours::coach_id.eq(the_coach_id))).execute(connection)?;
+        diesel::insert_into(working_hours::table).values(&new_hours).execute(connection)
+    });
+
+    if result.is_err() {
+        return Err(WORKING_HOURS_ERROR);
+    }
+
+    get_working_hours(connection, the_coach_id).map_err(|_| WORKING_HOURS_ERROR)
+}
+
+pub fn add_blackout(connection: &MysqlConnection, the_coach_id: &str, request: &NewBlackoutRequest) -> Result<Blackout, &'static str> {
+    find_coach_by_id(connection, the_coach_id)?;
+
+    let new_blackout = NewBlackout::from(request, the_coach_id)?;
+
+    let result = diesel::insert_into(blackouts::table).values(&new_blackout).execute(connection);
+
+    if result.is_err() {
+        return Err(BLACKOUT_ERROR);
+    }
+
+    let result = blackouts::table.filter(blackouts::id.eq(new_blackout.id.as_str())).first(connection);
+
+    if result.is_err() {
+        return Err(INVALID_BLACKOUT);
+    }
+
+    Ok(result.unwrap())
+}
+
+/**
+ * A coach may remove only a blackout of his/her own.
+ */
+pub fn remove_blackout(connection: &MysqlConnection, the_coach_id: &str, the_blackout_id: &str) -> Result<usize, &'static str> {
+    let result = diesel::delete(blackouts::table.filter(blackouts::id.eq(the_blackout_id)).filter(blackouts::coach_id.eq(the_coach_id))).execute(connection);
+
+    match result {
+        Ok(0) | Err(_) => Err(INVALID_BLACKOUT),
+        Ok(rows) => Ok(rows),
+    }
+}
+
+pub fn get_availability(connection: &MysqlConnection, the_coach_id: &str) -> Result<Availability, diesel::result::Error> {
+    let hours = get_working_hours(connection, the_coach_id)?;
+
+    let periods: Vec<Blackout> = blackouts::table
+        .filter(blackouts::coach_id.eq(the_coach_id))
+        .order_by(blackouts::start_date.asc())
+        .load(connection)?;
+
+    Ok(Availability {
+        working_hours: hours,
+        blackouts: periods,
+    })
+}
+
+fn get_working_hours(connection: &MysqlConnection, the_coach_id: &str) -> Result<Vec<WorkingHour>, diesel::result::Error> {
+    working_hours::table
+        .filter(working_hours::coach_id.eq(the_coach_id))
+        .order_by((working_hours::day_of_week.asc(), working_hours::start_minute.asc()))
+        .load(connection)
+}
+
+/**
+ * Checks a schedule before a session (or a conference) is placed on it:
+ *
+ * 1. It should be within the working hours of the coach, if the coach has set any.
+ * 2. It should not fall on a blackout of the coach.
+ * 3. It should not clash with another live session of the coach or the members.
+ *
+ * The sessions being moved are excluded from the clash check.
+ */
+pub fn check_schedule(
+    connection: &MysqlConnection,
+    the_coach_id: &str,
+    member_ids: &[String],
+    start_date: NaiveDateTime,
+    end_date: NaiveDateTime,
+    excluded_ids: &[String],
+) -> Result<(), Ferror> {
+    let availability = get_availability(connection, the_coach_id).map_err(|_| Ferror::from(SCHEDULE_CHECK_ERROR))?;
+    let coach_zone = find_zone(connection, the_coach_id);
+
+    let mut errors = Ferror::new();
+
+    let hours = &availability.working_hours;
+    if !hours.is_empty() && !covered_by(hours, start_date, end_date, coach_zone) {
+        errors.push("schedule", OUTSIDE_WORKING_HOURS);
+    }
+
+    let blackout_ids: Vec<String> = availability
+        .blackouts
+        .iter()
+        .filter(|blackout| blackout.overlaps(start_date, end_date))
+        .map(|blackout| blackout.id.to_owned())
+        .collect();
+
+    if !blackout_ids.is_empty() {
+        errors.push_error(ValidationError::with_ids("schedule", IN_BLACKOUT, blackout_ids));
+    }
+
+    let mut user_ids: Vec<String> = member_ids.to_vec();
+    user_ids.push(the_coach_id.to_owned());
+
+    let clashing_ids = find_clashes(connection, &user_ids, start_date, end_date, excluded_ids).map_err(|_| Ferror::from(SCHEDULE_CHECK_ERROR))?;
+
+    if !clashing_ids.is_empty() {
+        errors.push_error(ValidationError::with_ids("schedule", DOUBLE_BOOKED, clashing_ids));
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(())
+}
+
+/**
+ * Finds the free slots for a session between a coach and a member, within
+ * the given dates (YYYY-MM-DD, both inclusive, in the given zone).
+ *
+ * The slots start at every half an hour within the working hours of the
+ * coach, avoiding the blackouts of the coach and the live sessions (and thus
+ * the conferences) of both, and are ranked by the gap they leave around.
+ */
+pub fn find_available_slots(connection: &MysqlConnection, the_coach_id: &str, the_member_id: &str, duration_minutes: i32, from: &str, to: &str, zone: Tz) -> Result<Vec<CandidateSlot>, String> {
+    if duration_minutes < 15 {
+        return Err(SHORT_DURATION.to_owned());
+    }
+
+    let range_start = util::as_start_date(from, zone)?.max(next_step(util::now()));
+    let range_end = util::as_end_date(to, zone)?;
+
+    if range_end < range_start || range_end - range_start > Duration::days(MAX_RANGE_IN_DAYS) {
+        return Err(BAD_RANGE.to_owned());
+    }
+
+    let availability = get_availability(connection, the_coach_id).map_err(|e| e.to_string())?;
+    let coach_zone = find_zone(connection, the_coach_id);
+
+    let user_ids = vec![the_coach_id.to_owned(), the_member_id.to_owned()];
+    let mut busy: Vec<(NaiveDateTime, NaiveDateTime)> = find_live_sessions(connection, &user_ids, range_start, range_end)
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|session| (session.schedule_start(), session.schedule_end()))
+        .collect();
+
+    busy.extend(availability.blackouts.iter().map(|blackout| (blackout.start_date, blackout.end_date)));
+
+    let duration = Duration::minutes(duration_minutes as i64);
+    let hours = &availability.working_hours;
+
+    let mut candidates: Vec<(i64, NaiveDateTime, NaiveDateTime)> = Vec::new();
+    let mut start_date = range_start;
+
+    while start_date + duration <= range_end {
+        let end_date = start_date + duration;
+
+        let is_working = hours.is_empty() || covered_by(hours, start_date, end_date, coach_zone);
+        let is_free = !busy.iter().any(|(busy_start, busy_end)| *busy_start < end_date && start_date < *busy_end);
+
+        if is_working && is_free {
+            candidates.push((gap_around(&busy, start_date, end_date), start_date, end_date));
+        }
+
+        start_date += Duration::minutes(SLOT_STEP_IN_MINUTES);
+    }
+
+    // The widest gap first, the earliest one among equals
+    candidates.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+    let slots = candidates
+        .into_iter()
+        .take(MAX_SLOTS)
+        .enumerate()
+        .map(|(index, (_, start_date, end_date))| CandidateSlot {
+            start_date,
+            end_date,
+            rank: index as i32 + 1,
+        })
+        .collect();
+
+    Ok(slots)
+}
+
+fn find_zone(connection: &MysqlConnection, the_user_id: &str) -> Tz {
+    find(connection, the_user_id).map(|user| user.zone()).unwrap_or(Tz::UTC)
+}
+
+/**
+ * The minutes between the slot and the nearest engagement, capped.
+ */
+fn gap_around(busy: &[(NaiveDateTime, NaiveDateTime)], start_date: NaiveDateTime, end_date: NaiveDateTime) -> i64 {
+    busy.iter()
+        .map(|(busy_start, busy_end)| {
+            if *busy_end <= start_date {
+                (start_date - *busy_end).num_minutes()
+            } else {
+                (*busy_start - end_date).num_minutes()
+            }
+        })
+        .fold(MAX_GAP_IN_MINUTES, i64::min)
+}
+
+/**
+ * The slots begin at the next half an hour from the given time.
+ */
+fn next_step(date: NaiveDateTime) -> NaiveDateTime {
+    let date = util::strip_seconds(date);
+    let past = date.minute() as i64 % SLOT_STEP_IN_MINUTES;
+
+    if past == 0 {
+        return date;
+    }
+
+    date + Duration::minutes(SLOT_STEP_IN_MINUTES - past)
+}
+
+/**
+ * The live sessions (neither cancelled nor done, nor a pending request) of the
+ * given users that overlap the range. As the schedule of a session is the
+ * revised date if any, else the original one, we filter the overlap here.
+ */
+fn find_live_sessions(connection: &MysqlConnection, user_ids: &[String], start_date: NaiveDateTime, end_date: NaiveDateTime) -> Result<Vec<Session>, diesel::result::Error> {
+    let rows: Vec<(Session, SessionUser)> = sessions::table
+        .inner_join(session_users::table)
+        .filter(session_users::user_id.eq_any(user_ids))
+        .filter(sessions::cancelled_at.is_null())
+        .filter(sessions::actual_end_date.is_null())
+        .filter(sessions::is_request.eq(false))
+        .filter(sessions::original_start_date.lt(end_date).or(sessions::revised_start_date.lt(end_date)))
+        .load(connection)?;
+
+    let live_sessions = rows
+        .into_iter()
+        .map(|row| row.0)
+        .filter(|session| session.schedule_start() < end_date && start_date < session.schedule_end())
+        .collect();
+
+    Ok(live_sessions)
+}
+
+fn find_clashes(
+    connection: &MysqlConnection,
+    user_ids: &[String],
+    start_date: NaiveDateTime,
+    end_date: NaiveDateTime,
+    excluded_ids: &[String],
+) -> Result<Vec<String>, diesel::result::Error> {
+    let mut clashing_ids: Vec<String> = find_live_sessions(connection, user_ids, start_date, end_date)?
+        .into_iter()
+        .filter(|session| !excluded_ids.contains(&session.id))
+        .map(|session| session.id)
+        .collect();
+
+    clashing_ids.sort();
+    clashing_ids.dedup();
+
+    Ok(clashing_ids)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn should_step_to_the_next_half_an_hour() {
+        assert_eq!(util::as_date("2021-02-26T10:30:00Z").unwrap(), next_step(util::as_date("2021-02-26T10:07:00Z").unwrap()));
+        assert_eq!(util::as_date("2021-02-26T10:30:00Z").unwrap(), next_step(util::as_date("2021-02-26T10:30:00Z").unwrap()));
+    }
+
+    #[test]
+    fn should_measure_the_gap_to_the_nearest_engagement() {
+        let busy = vec![
+            (util::as_date("2021-02-26T09:00:00Z").unwrap(), util::as_date("2021-02-26T10:00:00Z").unwrap()),
+            (util::as_date("2021-02-26T13:00:00Z").unwrap(), util::as_date("2021-02-26T14:00:00Z").unwrap()),
+        ];
+
+        let gap = gap_around(&busy, util::as_date("2021-02-26T10:30:00Z").unwrap(), util::as_date("2021-02-26T11:00:00Z").unwrap());
+        assert_eq!(30, gap);
+
+        assert_eq!(MAX_GAP_IN_MINUTES, gap_around(&[], util::as_date("2021-02-26T10:30:00Z").unwrap(), util::as_date("2021-02-26T11:00:00Z").unwrap()));
+    }
+}
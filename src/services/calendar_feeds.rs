@@ -0,0 +1,169 @@
+use chrono::Duration;
+use diesel::prelude::*;
+
+use crate::commons::ical::{self, CalendarEvent, Method};
+use crate::commons::util;
+
+use crate::models::calendar_feeds::NewCalendarFeed;
+use crate::models::users::User;
+use crate::models::user_events::{get_events, get_plan_events, EventCriteria, EventRow, PlanRow};
+
+use crate::schema::calendar_feeds;
+use crate::schema::calendar_feeds::dsl::*;
+use crate::schema::users;
+
+pub const INVALID_FEED: &str = "The calendar feed is either revoked or does not exist.";
+const FEED_ISSUE_ERROR: &str = "Unable to issue the calendar feed.";
+const FEED_REVOKE_ERROR: &str = "Unable to revoke the calendar feed.";
+const FEED_ERROR: &str = "Unable to prepare the calendar feed.";
+
+const REMINDER_IN_MINUTES: i64 = 15;
+
+/**
+ * A user holds at most one live feed. Issuing a feed again revokes the
+ * earlier one, so the user gets a fresh URL whenever the old one leaks.
+ */
+pub fn issue_feed(connection: &MysqlConnection, the_user_id: &str) -> Result<String, &'static str> {
+    let feed_token = util::random_token();
+    let new_feed = NewCalendarFeed::from(the_user_id, feed_token.as_str());
+
+    let result = connection.transaction::<_, diesel::result::Error, _>(|| {
+        revoke_live_feeds(connection, the_user_id)?;
+        diesel::insert_into(calendar_feeds).values(&new_feed).execute(connection)
+    });
+
+    if result.is_err() {
+        return Err(FEED_ISSUE_ERROR);
+    }
+
+    let api_url = dotenv::var("API_URL").unwrap_or_default();
+
+    Ok(format!("{}/calendar/{}.ics", api_url, feed_token))
+}
+
+pub fn revoke_feed(connection: &MysqlConnection, the_user_id: &str) -> Result<usize, &'static str> {
+    let result = revoke_live_feeds(connection, the_user_id);
+
+    if result.is_err() {
+        return Err(FEED_REVOKE_ERROR);
+    }
+
+    Ok(result.unwrap())
+}
+
+fn revoke_live_feeds(connection: &MysqlConnection, the_user_id: &str) -> QueryResult<usize> {
+    diesel::update(calendar_feeds.filter(user_id.eq(the_user_id)).filter(revoked_at.is_null()))
+        .set(revoked_at.eq(util::now()))
+        .execute(connection)
+}
+
+/**
+ * The user of the live feed the token belongs to.
+ */
+fn find_live_feed(connection: &MysqlConnection, feed_token: &str) -> Result<String, &'static str> {
+    let the_digest = util::digest(feed_token);
+
+    let result = calendar_feeds
+        .filter(token_digest.eq(the_digest))
+        .filter(revoked_at.is_null())
+        .order_by(calendar_feeds::created_at.desc())
+        .select(user_id)
+        .first(connection);
+
+    if result.is_err() {
+        return Err(INVALID_FEED);
+    }
+
+    Ok(result.unwrap())
+}
+
+/**
+ * The feed carries the sessions of the user along with the plan events
+ * (objectives, tasks and the reminders of the notes). A cancelled session
+ * is left out, so that it disappears from the subscribed calendar.
+ */
+pub fn render_feed(connection: &MysqlConnection, feed_token: &str) -> Result<String, &'static str> {
+    let feed_user_id = find_live_feed(connection, feed_token)?;
+    let the_user_id = feed_user_id.as_str();
+
+    let event_rows = get_events(connection, the_user_id, all_events()).map_err(|_| FEED_ERROR)?;
+    let plan_rows = get_plan_events(connection, the_user_id, all_events()).map_err(|_| FEED_ERROR)?;
+    let user: User = users::table.find(the_user_id).first(connection).map_err(|_| FEED_ERROR)?;
+
+    let mut events: Vec<CalendarEvent> = event_rows.iter().filter(|row| row.session.cancelled_at.is_none()).map(session_event).collect();
+
+    events.extend(plan_rows.iter().filter_map(plan_event));
+
+    Ok(ical::as_feed(&events, user.zone()))
+}
+
+fn all_events() -> EventCriteria {
+    EventCriteria {
+        program_id: None,
+        start_date: None,
+        end_date: None,
+    }
+}
+
+fn session_event(row: &EventRow) -> CalendarEvent {
+    let session = &row.session;
+
+    CalendarEvent {
+        uid: session.id.to_owned(),
+        sequence: session.sequence,
+        summary: format!("{}: {}", row.program.name, session.name),
+        description: session.description.clone(),
+        organizer: None,
+        attendees: Vec::new(),
+        start: session.schedule_start(),
+        end: session.schedule_end(),
+        method: Method::Publish,
+    }
+}
+
+fn plan_event(row: &PlanRow) -> Option<CalendarEvent> {
+    let program_name = row.program.name.as_str();
+
+    if let Some(objective) = &row.objective {
+        return Some(CalendarEvent {
+            uid: objective.id.to_owned(),
+            sequence: 0,
+            summary: format!("Objective: {}", program_name),
+            description: objective.description.clone(),
+            organizer: None,
+            attendees: Vec::new(),
+            start: objective.revised_start_date.unwrap_or(objective.original_start_date),
+            end: objective.revised_end_date.unwrap_or(objective.original_end_date),
+            method: Method::Publish,
+        });
+    }
+
+    if let Some(task) = &row.task {
+        return Some(CalendarEvent {
+            uid: task.id.to_owned(),
+            sequence: 0,
+            summary: format!("{}: {}", program_name, task.name),
+            description: task.description.clone(),
+            organizer: None,
+            attendees: Vec::new(),
+            start: task.revised_start_date.unwrap_or(task.original_start_date),
+            end: task.revised_end_date.unwrap_or(task.original_end_date),
+            method: Method::Publish,
+        });
+    }
+
+    let note = row.note.as_ref()?;
+    let remind_at = note.remind_at?;
+
+    Some(CalendarEvent {
+        uid: note.id.to_owned(),
+        sequence: 0,
+        summary: format!("Reminder: {}", program_name),
+        description: Some(note.description.to_owned()),
+        organizer: None,
+        attendees: Vec::new(),
+        start: remind_at,
+        end: remind_at + Duration::minutes(REMINDER_IN_MINUTES),
+        method: Method::Publish,
+    })
+}
@@ -0,0 +1,116 @@
+use diesel::prelude::*;
+
+use crate::commons::util;
+
+use crate::models::coach_applications::{ApplicationTargetState, CoachApplication, NewCoachApplication, ReviewApplicationRequest, APPROVED, PENDING, REJECTED};
+use crate::models::coaches::NewCoach;
+use crate::models::users::User;
+
+use crate::schema::coach_applications;
+use crate::schema::coach_applications::dsl::*;
+use crate::schema::coaches::dsl::coaches;
+use crate::schema::users;
+
+use crate::services::users::find;
+
+pub const ALREADY_A_COACH: &str = "You are already a Coach.";
+pub const ALREADY_APPLIED: &str = "Your application to become a Coach is pending.";
+pub const APPLICATION_ERROR: &str = "Unable to accept the application.";
+pub const INVALID_APPLICATION: &str = "Invalid Application Id";
+pub const ALREADY_REVIEWED: &str = "The application has already been reviewed.";
+pub const REVIEW_ERROR: &str = "Unable to review the application.";
+
+pub fn apply_as_coach(connection: &MysqlConnection, the_user_id: &str) -> Result<CoachApplication, &'static str> {
+    let user = find(connection, the_user_id)?;
+
+    if user.user_type == util::COACH {
+        return Err(ALREADY_A_COACH);
+    }
+
+    gate_pending_application(connection, the_user_id)?;
+
+    let new_application = NewCoachApplication::from(&user);
+
+    let result = diesel::insert_into(coach_applications).values(&new_application).execute(connection);
+
+    if result.is_err() {
+        return Err(APPLICATION_ERROR);
+    }
+
+    find_application(connection, new_application.id.as_str())
+}
+
+pub fn get_pending_applications(connection: &MysqlConnection) -> Result<Vec<CoachApplication>, diesel::result::Error> {
+    coach_applications
+        .filter(status.eq(PENDING))
+        .order_by(coach_applications::created_at.asc())
+        .load(connection)
+}
+
+/**
+ * Approving an application creates the coaches row and flips the user_type
+ * of the applicant, in one go. A Rejected applicant is free to apply again.
+ */
+pub fn review_application(connection: &MysqlConnection, the_admin_id: &str, request: &ReviewApplicationRequest) -> Result<CoachApplication, &'static str> {
+    let application = find_application(connection, request.id.as_str())?;
+
+    if !application.is_pending() {
+        return Err(ALREADY_REVIEWED);
+    }
+
+    let result = match request.target_state {
+        ApplicationTargetState::APPROVE => {
+            let user = find(connection, application.user_id.as_str())?;
+            connection.transaction::<_, diesel::result::Error, _>(|| {
+                promote_to_coach(connection, &user)?;
+                mark_as_reviewed(connection, &application, the_admin_id, APPROVED)
+            })
+        }
+        ApplicationTargetState::REJECT => mark_as_reviewed(connection, &application, the_admin_id, REJECTED),
+    };
+
+    if result.is_err() {
+        return Err(REVIEW_ERROR);
+    }
+
+    find_application(connection, application.id.as_str())
+}
+
+fn promote_to_coach(connection: &MysqlConnection, user: &User) -> Result<usize, diesel::result::Error> {
+    let new_coach = NewCoach::from(user);
+
+    diesel::insert_into(coaches).values(&new_coach).execute(connection)?;
+
+    diesel::update(users::table.filter(users::id.eq(user.id.as_str())))
+        .set(users::user_type.eq(util::COACH))
+        .execute(connection)
+}
+
+fn mark_as_reviewed(connection: &MysqlConnection, application: &CoachApplication, the_admin_id: &str, target_status: &str) -> Result<usize, diesel::result::Error> {
+    diesel::update(coach_applications.filter(coach_applications::id.eq(application.id.as_str())))
+        .set((status.eq(target_status), reviewed_by_id.eq(the_admin_id), reviewed_at.eq(util::now())))
+        .execute(connection)
+}
+
+fn find_application(connection: &MysqlConnection, the_id: &str) -> Result<CoachApplication, &'static str> {
+    let result = coach_applications.filter(coach_applications::id.eq(the_id)).first(connection);
+
+    if result.is_err() {
+        return Err(INVALID_APPLICATION);
+    }
+
+    Ok(result.unwrap())
+}
+
+fn gate_pending_application(connection: &MysqlConnection, the_user_id: &str) -> Result<(), &'static str> {
+    let result: QueryResult<CoachApplication> = coach_applications
+        .filter(user_id.eq(the_user_id))
+        .filter(status.eq(PENDING))
+        .first(connection);
+
+    if result.is_ok() {
+        return Err(ALREADY_APPLIED);
+    }
+
+    Ok(())
+}
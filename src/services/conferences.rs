@@ -2,29 +2,36 @@ use diesel::prelude::*;
 
 use crate::commons::util;
 
+use crate::services::availabilities::check_schedule;
 use crate::services::enrollments;
 use crate::services::programs;
 use crate::services::sessions::{find_by_conference, insert_session, insert_session_member, remove_conference_session,create_session_mail};
+use crate::services::sessions::{get_conference_attendance, revise_conference_sessions, send_conference_reschedule_mails};
 use crate::services::users;
 
 use crate::models::conferences::{Conference, IntentionState, MemberRequest, NewConference, NewConferenceRequest};
+use crate::models::ferror::Ferror;
 use crate::models::programs::Program;
-use crate::models::sessions::{ChangeSessionStateRequest, NewSession, Session, TargetState};
+use crate::models::sessions::{ChangeSessionStateRequest, NewSession, RescheduleRequest, Session, TargetState};
 use crate::models::users::User;
 use crate::schema::conferences::dsl::*;
 
 const CONFERENCE_CREATION_ERROR: &str = "Unable to create conference.";
 const FINDER_ERROR: &str = "Unable to find the conference.";
 const CONFERENCE_STATE_UPDATE_ERROR: &str = "Unable to complete the requested action on the state of the conference";
+const CONFERENCE_RESCHEDULE_PROHIBITED: &str = "The conference is either started, cancelled or completed. Hence it cannot be rescheduled.";
+const CONFERENCE_RESCHEDULE_ERROR: &str = "Unable to reschedule the conference";
 
-pub fn create_conference(connection: &MysqlConnection, request: &NewConferenceRequest) -> Result<Conference, &'static str> {
+pub fn create_conference(connection: &MysqlConnection, request: &NewConferenceRequest) -> Result<Conference, Ferror> {
     let program = programs::find(connection, request.program_id.as_str())?;
 
     let coach = users::find(connection, &program.coach_id.as_str())?;
 
     let people_involved = coach.full_name.to_owned();
 
-    let new_conference = NewConference::from(request, people_involved);
+    let new_conference = NewConference::from(request, people_involved)?;
+
+    check_schedule(connection, coach.id.as_str(), &[], new_conference.original_start_date, new_conference.original_end_date, &[])?;
 
     let conference = insert_conference(connection, &new_conference)?;
 
@@ -41,6 +48,42 @@ pub fn manage_members(connection: &MysqlConnection, member_request: &MemberReque
     remove_members(connection, member_request)
 }
 
+/**
+ * The conference and its sessions move together, so that every member
+ * receives a revised invitation.
+ */
+pub fn reschedule_conference(connection: &MysqlConnection, request: &RescheduleRequest) -> Result<Conference, Ferror> {
+    let conference = find(connection, request.id.as_str())?;
+
+    let can_reschedule = conference.cancelled_at.is_none() && conference.actual_start_date.is_none() && conference.actual_end_date.is_none();
+    if !can_reschedule {
+        return Err(Ferror::from(CONFERENCE_RESCHEDULE_PROHIBITED));
+    }
+
+    let (start_date, end_date) = request.revised_dates(conference.duration)?;
+    let new_duration = request.duration.unwrap_or(conference.duration);
+
+    let program = programs::find(connection, conference.program_id.as_str())?;
+    let (session_ids, user_ids) = get_conference_attendance(connection, conference.id.as_str())?;
+    check_schedule(connection, program.coach_id.as_str(), &user_ids, start_date, end_date, &session_ids)?;
+
+    let result = connection.transaction::<_, diesel::result::Error, _>(|| {
+        diesel::update(conferences.filter(id.eq(conference.id.as_str())))
+            .set((revised_start_date.eq(start_date), revised_end_date.eq(end_date), duration.eq(new_duration)))
+            .execute(connection)?;
+
+        revise_conference_sessions(connection, conference.id.as_str(), start_date, end_date, new_duration)
+    });
+
+    if result.is_err() {
+        return Err(Ferror::from(CONFERENCE_RESCHEDULE_ERROR));
+    }
+
+    send_conference_reschedule_mails(connection, conference.program_id.as_str(), conference.id.as_str(), request.reason.as_deref())?;
+
+    Ok(find(connection, conference.id.as_str())?)
+}
+
 fn insert_conference(connection: &MysqlConnection, new_conference: &NewConference) -> Result<Conference, &'static str> {
     let result = diesel::insert_into(conferences).values(new_conference).execute(connection);
 
@@ -80,6 +123,10 @@ fn add_members(connection: &MysqlConnection, member_request: &MemberRequest) ->
     Ok(added_members)
 }
 
+/**
+ * A member joining after the conference was rescheduled gets a session on
+ * the revised dates, like the sessions of the other members.
+ */
 fn find_or_create_session(connection: &MysqlConnection, conference: &Conference, member_id: &str, program: &Program, coach: &User) -> Result<Session, &'static str> {
     if let Ok(session) = find_by_conference(connection, conference.id.as_str(), member_id) {
         return Ok(session);
@@ -110,9 +157,15 @@ fn find_or_create_session(connection: &MysqlConnection, conference: &Conference,
         duration: conference.duration,
         original_start_date: conference.original_start_date,
         original_end_date: conference.original_end_date,
+        revised_start_date: conference.revised_start_date,
+        revised_end_date: conference.revised_end_date,
         conference_id: Some(conference.id.to_owned()),
         session_type: util::MULTI.to_owned(),
         is_ready: conference.is_ready,
+        is_request: false,
+        offered_start_date: None,
+        offered_end_date: None,
+        series_id: None,
     };
 
     let session = insert_session(connection, &new_session)?;
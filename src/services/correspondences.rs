@@ -1,68 +1,98 @@
+use chrono::NaiveDateTime;
 use diesel::prelude::*;
 
+use crate::commons::util;
+
 use crate::schema::correspondences::dsl::*;
 use crate::schema::mail_recipients::dsl::*;
 
-use crate::models::correspondences::{Correspondence, MailCriteria, MailOut, MailRecipient, Mailable};
+use crate::models::correspondences::{Correspondence, MailOut, MailRecipient, Mailable, FAILED, ONE_TIME_LINK_MAILS, PENDING, REDACTED, SENT};
 
 const MAIL_CREATION_ERROR: &str = "Error in creating the invitation mail. But enrollment is done.";
 
-pub type MailType = (Correspondence, Vec<MailRecipient>);
-pub type MailResult = Result<Vec<MailType>, diesel::result::Error>;
 pub type MailableResult = Result<Vec<Mailable>, diesel::result::Error>;
 
+pub fn create_mail(connection: &MysqlConnection, mail_out: MailOut, recipients: Vec<MailRecipient>) ->Result<usize,&'static str> {
+
+    let result = diesel::insert_into(correspondences).values(mail_out).execute(connection);
+    if result.is_err() {
+        return Err(MAIL_CREATION_ERROR);
+    }
+
+    let result = diesel::insert_into(mail_recipients).values(recipients).execute(connection);
+    if result.is_err() {
+        return Err(MAIL_CREATION_ERROR);
+    }
+
+    Ok(result.unwrap())
+}
+
 /**
- * Let us offer 3 pending mails and mark them as Marked for avoiding
- * repeat mails
+ * The pending outbound mails that are due by now, along with their recipients,
+ * for the mail dispatcher to deliver.
  */
+pub fn dispatchable_mails(connection: &MysqlConnection, batch_size: i64) -> MailableResult {
+    let corres: Vec<Correspondence> = correspondences
+        .filter(status.eq(PENDING))
+        .filter(in_out.eq("out"))
+        .filter(to_send_on.le(util::now()))
+        .order_by(to_send_on.asc())
+        .limit(batch_size)
+        .load(connection)?;
 
-pub fn sendable_mails(connection: &MysqlConnection) -> MailableResult {
-    let criteria = MailCriteria {
-        status: "pending".to_owned(),
-        in_out: "out".to_owned(),
-    };
+    let people = MailRecipient::belonging_to(&corres).load::<MailRecipient>(connection)?.grouped_by(&corres);
 
-    let mailables: Vec<Mailable> = get_mails(connection, &criteria)?
+    let mailables = corres
         .into_iter()
+        .zip(people)
         .map(|item| Mailable {
             correspondence: item.0,
             receipients: item.1,
         })
         .collect();
 
-    let ids: Vec<&str> = mailables.iter().map(|item| item.correspondence.id.as_str()).collect();
-    let query = correspondences.filter(crate::schema::correspondences::id.eq_any(ids));
-    diesel::update(query).set(status.eq("marked")).execute(connection)?;
-
     Ok(mailables)
 }
 
-pub fn get_mails(connection: &MysqlConnection, criteria: &MailCriteria) -> MailResult {
-    let corres: Vec<Correspondence> = correspondences
-        .filter(status.eq(criteria.status.as_str()))
-        .filter(in_out.eq(criteria.in_out.as_str()))
-        .order_by(created_at.asc())
-        .limit(3)
-        .load(connection)?;
+pub fn mark_as_sent(connection: &MysqlConnection, the_id: &str) -> Result<usize, diesel::result::Error> {
+    let query = correspondences.filter(crate::schema::correspondences::id.eq(the_id));
 
-    let people = MailRecipient::belonging_to(&corres).load::<MailRecipient>(connection)?.grouped_by(&corres);
+    let rows = diesel::update(query)
+        .set((status.eq(SENT), sent_at.eq(util::now()), error.eq(""), error_reason.eq(None::<String>), attempts.eq(attempts + 1)))
+        .execute(connection)?;
 
-    let mails = corres.into_iter().zip(people).collect::<Vec<_>>();
+    redact_one_time_link(connection, the_id)?;
 
-    Ok(mails)
+    Ok(rows)
 }
 
-pub fn create_mail(connection: &MysqlConnection, mail_out: MailOut, recipients: Vec<MailRecipient>) ->Result<usize,&'static str> {
-
-    let result = diesel::insert_into(correspondences).values(mail_out).execute(connection);
-    if result.is_err() {
-        return Err(MAIL_CREATION_ERROR);
+/**
+ * A failed mail is retried on the given time, unless we have given up on it.
+ */
+pub fn mark_as_failed(connection: &MysqlConnection, the_id: &str, reason: &str, retry_on: Option<NaiveDateTime>) -> Result<usize, diesel::result::Error> {
+    let query = correspondences.filter(crate::schema::correspondences::id.eq(the_id));
+    let reason: String = reason.chars().take(255).collect();
+
+    match retry_on {
+        Some(retry_on) => diesel::update(query)
+            .set((error.eq("retry"), error_reason.eq(reason), to_send_on.eq(retry_on), attempts.eq(attempts + 1)))
+            .execute(connection),
+        None => {
+            let rows = diesel::update(query)
+                .set((status.eq(FAILED), error.eq("gave up"), error_reason.eq(reason), attempts.eq(attempts + 1)))
+                .execute(connection)?;
+
+            redact_one_time_link(connection, the_id)?;
+
+            Ok(rows)
+        }
     }
+}
 
-    let result = diesel::insert_into(mail_recipients).values(recipients).execute(connection);
-    if result.is_err() {
-        return Err(MAIL_CREATION_ERROR);
-    }
+fn redact_one_time_link(connection: &MysqlConnection, the_id: &str) -> Result<usize, diesel::result::Error> {
+    let query = correspondences
+        .filter(crate::schema::correspondences::id.eq(the_id))
+        .filter(mail_type.eq_any(ONE_TIME_LINK_MAILS.iter()));
 
-    Ok(result.unwrap())
+    diesel::update(query).set(content.eq(REDACTED)).execute(connection)
 }
@@ -0,0 +1,117 @@
+use diesel::prelude::*;
+
+use crate::commons::diagrams::{self, Bar, Edge, Node};
+use crate::commons::util;
+use crate::models::abstract_tasks::AbstractTask;
+use crate::models::master_plans::{MasterPlan, MasterTaskLink};
+use crate::models::master_tasks::MasterTask;
+use crate::models::schedules::EnrollmentSchedule;
+use crate::services::schedules::get_enrollment_schedule;
+
+use crate::schema::abstract_tasks;
+use crate::schema::master_plans;
+use crate::schema::master_task_links;
+use crate::schema::master_tasks;
+
+pub const PLAN_NOT_FOUND: &str = "Unable to find the master plan.";
+const SCHEDULE_ERROR: &str = "Unable to schedule the tasks of the enrollment.";
+
+/**
+ * The kinds of diagrams we render, along with their content types.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Dot,
+    Svg,
+    Mermaid,
+}
+
+impl Format {
+    pub fn parse(extension: &str) -> Option<Format> {
+        match extension {
+            "dot" | "gv" => Some(Format::Dot),
+            "svg" => Some(Format::Svg),
+            "mmd" => Some(Format::Mermaid),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Format::Dot => "text/vnd.graphviz; charset=utf-8",
+            Format::Svg => "image/svg+xml; charset=utf-8",
+            Format::Mermaid => "text/plain; charset=utf-8",
+        }
+    }
+}
+
+/**
+ * The graph of the master plan, as DOT or SVG.
+ */
+pub fn render_plan(connection: &MysqlConnection, the_master_plan_id: &str, format: Format) -> Result<String, &'static str> {
+    let plan: MasterPlan = master_plans::table.find(the_master_plan_id).first(connection).map_err(|_| PLAN_NOT_FOUND)?;
+
+    let plan_tasks: Vec<(MasterTask, AbstractTask)> = master_tasks::table
+        .inner_join(abstract_tasks::table)
+        .filter(master_tasks::master_plan_id.eq(the_master_plan_id))
+        .order_by(master_tasks::created_at.asc())
+        .load(connection)
+        .map_err(|_| PLAN_NOT_FOUND)?;
+
+    let plan_links: Vec<MasterTaskLink> = master_task_links::table
+        .filter(master_task_links::master_plan_id.eq(the_master_plan_id))
+        .load(connection)
+        .map_err(|_| PLAN_NOT_FOUND)?;
+
+    let nodes: Vec<Node> = plan_tasks
+        .iter()
+        .map(|(master_task, abstract_task)| Node {
+            id: master_task.id.to_owned(),
+            label: abstract_task.name.to_owned(),
+            kind: master_task.task_type.to_owned(),
+            role: master_task.role_id.to_owned(),
+        })
+        .collect();
+
+    let edges: Vec<Edge> = plan_links
+        .iter()
+        .map(|link| Edge {
+            source_id: link.source_task_id.to_owned(),
+            target_id: link.target_task_id.to_owned(),
+            lead_time: link.lead_time,
+            is_forward: link.is_forward,
+        })
+        .collect();
+
+    match format {
+        Format::Dot => Ok(diagrams::as_dot(plan.name.as_str(), &nodes, &edges)),
+        _ => Ok(diagrams::as_graph_svg(plan.name.as_str(), &nodes, &edges)),
+    }
+}
+
+/**
+ * The live tasks of the enrollment as a Gantt chart, in SVG or Mermaid.
+ * The bars go from the scheduled start to the scheduled end of the task,
+ * as revised; the critical path is taken from the schedule.
+ */
+pub fn render_gantt(connection: &MysqlConnection, the_enrollment_id: &str, format: Format) -> Result<String, &'static str> {
+    let schedule: EnrollmentSchedule = get_enrollment_schedule(connection, the_enrollment_id).map_err(|_| SCHEDULE_ERROR)?;
+
+    let bars: Vec<Bar> = schedule
+        .timings
+        .iter()
+        .map(|timing| Bar {
+            label: timing.task.name.to_owned(),
+            start: timing.task.revised_start_date.unwrap_or(timing.task.original_start_date),
+            end: timing.task.revised_end_date.unwrap_or(timing.task.original_end_date),
+            actual_start: timing.task.actual_start_date,
+            actual_end: timing.task.actual_end_date,
+            is_critical: timing.timing.is_critical(),
+        })
+        .collect();
+
+    match format {
+        Format::Mermaid => Ok(diagrams::as_mermaid_gantt("Tasks", &bars)),
+        _ => Ok(diagrams::as_gantt_svg("Tasks", &bars, util::now())),
+    }
+}
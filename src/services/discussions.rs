@@ -12,12 +12,10 @@ use crate::models::discussion_queue::{Feed, NewFeed, PendingFeed};
 use crate::models::discussions::{Discussion, DiscussionCriteria, NewDiscussion, NewDiscussionRequest};
 use crate::models::users::User;
 
-use crate::models::users::UserCriteria;
-
 const FEED_COUNT_ERROR: &str = "Error while counting pending feeds.";
 
-pub fn create_new_discussion(connection: &MysqlConnection, request: &NewDiscussionRequest) -> QueryResult<Discussion> {
-    let new_discussion = NewDiscussion::from(request);
+pub fn create_new_discussion(connection: &MysqlConnection, the_user_id: &str, request: &NewDiscussionRequest) -> QueryResult<Discussion> {
+    let new_discussion = NewDiscussion::from(request, the_user_id);
 
     diesel::insert_into(discussions).values(&new_discussion).execute(connection)?;
 
@@ -28,7 +26,7 @@ pub fn create_new_discussion(connection: &MysqlConnection, request: &NewDiscussi
     diesel::insert_into(discussion_queue).values(&new_feed).execute(connection)?;
 
     // Mark any prior pending feeds for the user as read
-    mark_as_read(connection, request.created_by_id.as_str(), request.enrollment_id.as_str());
+    mark_as_read(connection, the_user_id, request.enrollment_id.as_str());
 
     Ok(discussion)
 }
@@ -47,13 +45,13 @@ pub fn get_discussions(connection: &MysqlConnection, criteria: DiscussionCriteri
 *  We need to know the User who created the message.
 */
 
-pub fn get_pending_discussions(connection: &MysqlConnection, criteria: &UserCriteria) -> Result<Vec<PendingFeed>, diesel::result::Error> {
+pub fn get_pending_discussions(connection: &MysqlConnection, the_user_id: &str) -> Result<Vec<PendingFeed>, diesel::result::Error> {
     type FeedRow = (Feed, (Discussion,User));
 
     let rows: Vec<FeedRow> = discussion_queue
         .inner_join(discussions.inner_join(users))
         .filter(is_pending.eq(true))
-        .filter(to_id.eq(criteria.id.as_str()))
+        .filter(to_id.eq(the_user_id))
         .order_by(discussions::created_at.desc())
         .limit(50)
         .load(connection)?;
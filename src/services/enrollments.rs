@@ -7,6 +7,7 @@ use crate::models::correspondences::{MailOut, MailRecipient};
 use crate::models::enrollments::{Enrollment, EnrollmentCriteria, EnrollmentFilter, ManagedEnrollmentRequest, NewEnrollment, NewEnrollmentRequest};
 
 use crate::services::correspondences::create_mail;
+use crate::services::program_plans::apply_default_plan;
 use crate::services::programs;
 use crate::services::users;
 
@@ -20,14 +21,14 @@ const ERROR_003: &str = "Error in finding enrollment for the program and member.
 const ERROR_004: &str = "Error in marking the enrollment as Old";
 const QUERY_ERROR: &str = "Error in fetching enrolled members";
 
-pub fn create_new_enrollment(connection: &MysqlConnection, request: &NewEnrollmentRequest) -> Result<Enrollment, &'static str> {
-    let user: User = users::find(connection, request.user_id.as_str())?;
+pub fn create_new_enrollment(connection: &MysqlConnection, the_member_id: &str, request: &NewEnrollmentRequest) -> Result<Enrollment, &'static str> {
+    let user: User = users::find(connection, the_member_id)?;
+    users::gate_active_user(&user)?;
+
     let program: Program = programs::find(connection, request.program_id.as_str())?;
 
     gate_prior_enrollment(connection, &program, &user)?;
-    insert_enrollment(connection, &program, &user)?;
-
-    let enrollment = find(connection, &program, &user)?;
+    let enrollment = enroll(connection, &program, &user)?;
 
     let coach = users::find(connection, program.coach_id.as_str())?;
 
@@ -36,6 +37,27 @@ pub fn create_new_enrollment(connection: &MysqlConnection, request: &NewEnrollme
     Ok(enrollment)
 }
 
+/**
+ * Enrolls the user along with the tasks of the default plan of the program;
+ * when the plan cannot be applied, the enrollment is rolled back as well.
+ */
+fn enroll(connection: &MysqlConnection, program: &Program, user: &User) -> Result<Enrollment, &'static str> {
+    let mut failure = ERROR_002;
+
+    let result = connection.transaction::<_, diesel::result::Error, _>(|| {
+        let enrolled = insert_enrollment(connection, program, user)
+            .and_then(|_| find(connection, program, user))
+            .and_then(|enrollment| apply_default_plan(connection, &enrollment).map(|_| enrollment));
+
+        enrolled.map_err(|e| {
+            failure = e;
+            diesel::result::Error::RollbackTransaction
+        })
+    });
+
+    result.map_err(|_| failure)
+}
+
 fn insert_enrollment(connection: &MysqlConnection, program: &Program, user: &User) -> Result<usize, &'static str> {
     let enrollment: NewEnrollment = NewEnrollment::from(&program, &user);
     let insert_result = diesel::insert_into(enrollments).values(enrollment).execute(connection);
@@ -135,7 +157,7 @@ const CONFLICT_PROGRAM_OWNER_MAIL: &str = "The coach does not have rights to enr
 /**
  * When a coach enrolls a member into her program
  */
-pub fn create_managed_enrollment(connection: &MysqlConnection, request: &ManagedEnrollmentRequest) -> Result<Enrollment, &'static str> {
+pub fn create_managed_enrollment(connection: &MysqlConnection, the_coach_id: &str, request: &ManagedEnrollmentRequest) -> Result<Enrollment, &'static str> {
     let user_result: QueryResult<User> = users.filter(email.eq(request.member_mail.as_str())).first(connection);
 
     if user_result.is_err() {
@@ -144,7 +166,7 @@ pub fn create_managed_enrollment(connection: &MysqlConnection, request: &Managed
 
     let program_result: QueryResult<Program> = programs
         .filter(crate::schema::programs::id.eq(request.program_id.as_str()))
-        .filter(coach_id.eq(request.coach_id.as_str()))
+        .filter(coach_id.eq(the_coach_id))
         .first(connection);
 
     if program_result.is_err() {
@@ -153,12 +175,12 @@ pub fn create_managed_enrollment(connection: &MysqlConnection, request: &Managed
 
     let member = user_result.unwrap();
     let program = program_result.unwrap();
-    let coach = users::find(connection, request.coach_id.as_str())?;
+    let coach = users::find(connection, the_coach_id)?;
+    users::gate_active_user(&coach)?;
+    users::gate_active_user(&member)?;
 
     gate_prior_enrollment(connection, &program, &member)?;
-    insert_enrollment(connection, &program, &member)?;
-
-    let enrollment = find(connection, &program, &member)?;
+    let enrollment = enroll(connection, &program, &member)?;
 
     create_managed_enrollment_mail(connection, request, enrollment.id.as_str(), &member, &coach)?;
 
@@ -169,7 +191,7 @@ pub fn create_managed_enrollment(connection: &MysqlConnection, request: &Managed
  * Mail when a coach enrolls a member into his program
  */
 fn create_managed_enrollment_mail(connection: &MysqlConnection, request: &ManagedEnrollmentRequest, new_enroll_id: &str, member: &User, coach: &User) -> Result<usize, &'static str> {
-    let mail_out = MailOut::for_managed_enrollment(request, new_enroll_id);
+    let mail_out = MailOut::for_managed_enrollment(request, coach, new_enroll_id);
     let recipients = MailRecipient::build_recipients(member, coach, mail_out.id.as_str());
 
     create_mail(connection, mail_out, recipients)
@@ -0,0 +1,85 @@
+use diesel::prelude::*;
+
+use crate::models::master_plan_shares::{MasterPlanShare, NewMasterPlanShare, SharePlanRequest};
+use crate::models::master_plans::MasterPlan;
+use crate::models::programs::Program;
+
+use crate::services::programs;
+
+use crate::schema::master_plan_shares;
+use crate::schema::master_plans;
+use crate::schema::programs as program;
+
+const ALREADY_SHARED: &str = "The master plan is already shared with the coaches of the program.";
+const SHARE_ERROR: &str = "Unable to share the master plan.";
+const SHARE_NOT_FOUND: &str = "The master plan is not shared with the coaches of the program.";
+
+/**
+ * Shares the plan with the parent program of the given program, that is
+ * with the coach of the parent program and all its peer coaches.
+ */
+pub fn share_master_plan(connection: &MysqlConnection, the_coach_id: &str, request: &SharePlanRequest) -> Result<MasterPlanShare, &'static str> {
+    let program: Program = programs::find(connection, request.program_id.as_str())?;
+    let parent_id = program.coalesce_parent_id();
+
+    if find(connection, request.master_plan_id.as_str(), parent_id).is_ok() {
+        return Err(ALREADY_SHARED);
+    }
+
+    let new_share = NewMasterPlanShare::from(request.master_plan_id.as_str(), parent_id, the_coach_id);
+
+    let result = diesel::insert_into(master_plan_shares::table).values(&new_share).execute(connection);
+
+    if result.is_err() {
+        return Err(SHARE_ERROR);
+    }
+
+    find(connection, request.master_plan_id.as_str(), parent_id)
+}
+
+pub fn unshare_master_plan(connection: &MysqlConnection, request: &SharePlanRequest) -> Result<MasterPlanShare, &'static str> {
+    let program: Program = programs::find(connection, request.program_id.as_str())?;
+    let share = find(connection, request.master_plan_id.as_str(), program.coalesce_parent_id())?;
+
+    let result = diesel::delete(master_plan_shares::table.filter(master_plan_shares::id.eq(share.id.as_str()))).execute(connection);
+
+    if result.is_err() {
+        return Err(SHARE_ERROR);
+    }
+
+    Ok(share)
+}
+
+pub fn get_plan_shares(connection: &MysqlConnection, the_master_plan_id: &str) -> Result<Vec<MasterPlanShare>, diesel::result::Error> {
+    master_plan_shares::table
+        .filter(master_plan_shares::master_plan_id.eq(the_master_plan_id))
+        .order_by(master_plan_shares::created_at.asc())
+        .load(connection)
+}
+
+/**
+ * The plans other coaches shared with the parent programs of the programs
+ * the coach offers.
+ */
+pub fn get_shared_master_plans(connection: &MysqlConnection, the_coach_id: &str) -> Result<Vec<MasterPlan>, diesel::result::Error> {
+    let coach_programs: Vec<Program> = program::table.filter(program::coach_id.eq(the_coach_id)).load(connection)?;
+    let parent_ids: Vec<&str> = coach_programs.iter().map(|a_program| a_program.coalesce_parent_id()).collect();
+
+    let shared_plan_ids = master_plan_shares::table
+        .filter(master_plan_shares::program_id.eq_any(parent_ids))
+        .select(master_plan_shares::master_plan_id);
+
+    master_plans::table
+        .filter(master_plans::id.eq_any(shared_plan_ids))
+        .filter(master_plans::coach_id.ne(the_coach_id))
+        .order_by(master_plans::name.asc())
+        .load(connection)
+}
+
+fn find(connection: &MysqlConnection, the_master_plan_id: &str, the_program_id: &str) -> Result<MasterPlanShare, &'static str> {
+    master_plan_shares::table
+        .filter(master_plan_shares::master_plan_id.eq(the_master_plan_id))
+        .filter(master_plan_shares::program_id.eq(the_program_id))
+        .first(connection)
+        .map_err(|_| SHARE_NOT_FOUND)
+}
@@ -1,27 +1,203 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
 use diesel::prelude::*;
 
+use crate::commons::chassis::ValidationError;
+use crate::commons::scheduler::{self, Activity, Dependency, Estimate};
 use crate::commons::util;
-use crate::models::master_plans::UpdateMasterPlanRequest;
-use crate::models::master_plans::{MasterPlan, MasterPlanCriteria, NewMasterPlan, NewMasterPlanRequest, TaskUnit};
+use crate::models::abstract_tasks::{AbstractTask, NewAbstractTask, NewAbstractTaskRequest};
+use crate::models::enrollments::Enrollment;
+use crate::models::ferror::Ferror;
+use crate::models::master_plan_revisions::{MasterPlanRevision, NewMasterPlanRevision, PlanSnapshot, RestoreRevisionRequest};
+use crate::models::master_plans::{ApplyMasterPlanRequest, ClonePlanRequest, MasterTaskLink, UpdateMasterPlanRequest};
+use crate::models::master_plans::{validate_graph, MasterPlan, NewMasterPlan, NewMasterPlanRequest, TaskUnit};
+use crate::models::master_tasks::{MasterTask, NewMasterTask, UpdateMasterTask};
+use crate::models::plan_documents::PlanDocument;
+use crate::models::programs::Program;
+use crate::models::schedules::PlanForecast;
+use crate::models::task_links::NewTaskLink;
+use crate::models::tasks::{NewTask, Task};
 
+use crate::schema::abstract_tasks;
+use crate::schema::enrollments;
+use crate::schema::master_plan_revisions;
 use crate::schema::master_plans;
 use crate::schema::master_task_links;
 use crate::schema::master_tasks;
+use crate::schema::platform_roles;
+use crate::schema::programs;
+use crate::schema::task_links;
+use crate::schema::tasks;
 
 use crate::schema::master_plans::dsl::*;
 use crate::schema::master_task_links::dsl::*;
 use crate::schema::master_tasks::dsl::*;
 
-pub fn create_master_plan(connection: &MysqlConnection, request: &NewMasterPlanRequest) -> Result<MasterPlan, diesel::result::Error> {
-    let new_master_plan = NewMasterPlan::from(request);
+const PLAN_NOT_FOUND: &str = "Unable to find the master plan.";
+const ENROLLMENT_NOT_FOUND: &str = "Unable to find the enrollment.";
+pub const EMPTY_PLAN: &str = "The master plan has no tasks.";
+const APPLY_ERROR: &str = "Unable to apply the master plan.";
+pub const PLAN_ALREADY_APPLIED: &str = "has been applied to the enrollment already.";
+const SAVE_ERROR: &str = "Unable to save the master plan.";
+const CLONE_ERROR: &str = "Unable to clone the master plan.";
+const EXPORT_ERROR: &str = "Unable to export the master plan.";
+const IMPORT_ERROR: &str = "Unable to import the master plan.";
+const REVISION_NOT_FOUND: &str = "Unable to find the revision of the master plan.";
+const RESTORE_ERROR: &str = "Unable to restore the master plan.";
+pub const VERSION_CONFLICT: &str = "The master plan was saved by someone else in the meantime. Please reload it and try again.";
+
+/** The revisions we keep of a plan; the older ones are pruned. */
+const MAX_REVISIONS: i32 = 50;
+
+pub fn create_master_plan(connection: &MysqlConnection, the_coach_id: &str, request: &NewMasterPlanRequest) -> Result<MasterPlan, diesel::result::Error> {
+    let new_master_plan = NewMasterPlan::from(request, the_coach_id);
 
     diesel::insert_into(master_plans).values(&new_master_plan).execute(connection)?;
 
     master_plans.filter(master_plans::id.eq(new_master_plan.id)).first(connection)
 }
 
-pub fn get_master_plans(connection: &MysqlConnection, criteria: &MasterPlanCriteria) -> Result<Vec<MasterPlan>, diesel::result::Error> {
-    master_plans.filter(master_plans::coach_id.eq(&criteria.coach_id)).order_by(name.asc()).load(connection)
+pub fn get_master_plans(connection: &MysqlConnection, the_coach_id: &str) -> Result<Vec<MasterPlan>, diesel::result::Error> {
+    master_plans.filter(master_plans::coach_id.eq(the_coach_id)).order_by(name.asc()).load(connection)
+}
+
+/**
+ * Copies the plan, with its tasks and links, for the target coach. The
+ * target coach gets copies of the abstract tasks of the plan, unless they
+ * already have abstract tasks of the same name.
+ */
+pub fn clone_master_plan(connection: &MysqlConnection, request: &ClonePlanRequest) -> Result<MasterPlan, &'static str> {
+    let target_id = request.target_coach_id.as_str();
+    let plan = find(connection, request.master_plan_id.as_str())?;
+
+    let plan_tasks: Vec<MasterTask> = master_tasks.filter(master_tasks::master_plan_id.eq(plan.id.as_str())).load(connection).map_err(|_| CLONE_ERROR)?;
+    let plan_links: Vec<MasterTaskLink> = master_task_links.filter(master_task_links::master_plan_id.eq(plan.id.as_str())).load(connection).map_err(|_| CLONE_ERROR)?;
+
+    let abstract_ids: Vec<&str> = plan_tasks.iter().map(|task| task.abstract_task_id.as_str()).collect();
+    let plan_abstract_tasks: Vec<AbstractTask> = abstract_tasks::table.filter(abstract_tasks::id.eq_any(abstract_ids)).load(connection).map_err(|_| CLONE_ERROR)?;
+
+    let wanted: Vec<(&str, &str)> = plan_abstract_tasks
+        .iter()
+        .filter(|abstract_task| abstract_task.coach_id != target_id)
+        .map(|abstract_task| (abstract_task.id.as_str(), abstract_task.name.as_str()))
+        .collect();
+
+    let (abstract_map, new_abstract_tasks) = adopt_abstract_tasks(connection, target_id, &wanted).map_err(|_| CLONE_ERROR)?;
+
+    let new_plan = NewMasterPlan::copy_of(&plan, target_id);
+    let snapshot = PlanSnapshot::of(&plan_tasks, &plan_links).rekeyed(target_id, &abstract_map);
+
+    insert_plan(connection, &new_plan, &snapshot, &new_abstract_tasks).map_err(|_| CLONE_ERROR)?;
+
+    find(connection, new_plan.id.as_str())
+}
+
+/**
+ * The plan as a portable document; see PlanDocument.
+ */
+pub fn export_master_plan(connection: &MysqlConnection, the_master_plan_id: &str) -> Result<String, &'static str> {
+    let plan = find(connection, the_master_plan_id)?;
+
+    let plan_tasks: Vec<MasterTask> = master_tasks
+        .filter(master_tasks::master_plan_id.eq(plan.id.as_str()))
+        .order_by(master_tasks::created_at.asc())
+        .load(connection)
+        .map_err(|_| EXPORT_ERROR)?;
+    let plan_links: Vec<MasterTaskLink> = master_task_links.filter(master_task_links::master_plan_id.eq(plan.id.as_str())).load(connection).map_err(|_| EXPORT_ERROR)?;
+
+    let abstract_ids: Vec<&str> = plan_tasks.iter().map(|task| task.abstract_task_id.as_str()).collect();
+    let plan_abstract_tasks: Vec<AbstractTask> = abstract_tasks::table
+        .filter(abstract_tasks::id.eq_any(abstract_ids))
+        .order_by(abstract_tasks::name.asc())
+        .load(connection)
+        .map_err(|_| EXPORT_ERROR)?;
+
+    let document = PlanDocument::of(&plan, &plan_tasks, &plan_links, &plan_abstract_tasks);
+
+    serde_json::to_string_pretty(&document).map_err(|_| EXPORT_ERROR)
+}
+
+/**
+ * Creates the plan of the document, with fresh ids, for the target coach.
+ * The abstract tasks go the way of a clone; the roles should be known to
+ * the platform, and the plan should make a sound graph (see validate_graph).
+ */
+pub fn import_master_plan(connection: &MysqlConnection, the_coach_id: &str, document: &PlanDocument) -> Result<MasterPlan, Ferror> {
+    let errors = document.validate();
+    if !errors.is_empty() {
+        return Err(Ferror { errors });
+    }
+
+    let known_roles: Vec<String> = platform_roles::table.select(platform_roles::id).load(connection).map_err(|_| Ferror::from(IMPORT_ERROR))?;
+    let unknown_roles: Vec<String> = document.roles.iter().filter(|role| !known_roles.contains(role)).cloned().collect();
+
+    if !unknown_roles.is_empty() {
+        let mut ferror = Ferror::new();
+        ferror.push_error(ValidationError::with_ids("roles", "should be the roles of the platform.", unknown_roles));
+        return Err(ferror);
+    }
+
+    let wanted: Vec<(&str, &str)> = document.abstract_tasks.iter().map(|entry| (entry.id.as_str(), entry.name.as_str())).collect();
+    let (abstract_map, new_abstract_tasks) = adopt_abstract_tasks(connection, the_coach_id, &wanted).map_err(|_| Ferror::from(IMPORT_ERROR))?;
+
+    let request = NewMasterPlanRequest {
+        name: document.name.to_owned(),
+        description: document.description.to_owned(),
+    };
+    let new_plan = NewMasterPlan::from(&request, the_coach_id);
+    let snapshot = document.as_snapshot(the_coach_id).rekeyed(the_coach_id, &abstract_map);
+
+    let errors = validate_graph(&snapshot.tasks, &snapshot.links);
+    if !errors.is_empty() {
+        return Err(Ferror { errors });
+    }
+
+    insert_plan(connection, &new_plan, &snapshot, &new_abstract_tasks).map_err(|_| Ferror::from(IMPORT_ERROR))?;
+
+    find(connection, new_plan.id.as_str()).map_err(Ferror::from)
+}
+
+/**
+ * Maps the given abstract tasks (id, name) to those of the coach: to the
+ * one of the same name if the coach has it, to a new copy otherwise.
+ */
+fn adopt_abstract_tasks(connection: &MysqlConnection, the_coach_id: &str, wanted: &[(&str, &str)]) -> QueryResult<(HashMap<String, String>, Vec<NewAbstractTask>)> {
+    let coach_abstract_tasks: Vec<AbstractTask> = abstract_tasks::table.filter(abstract_tasks::coach_id.eq(the_coach_id)).load(connection)?;
+
+    let mut abstract_map: HashMap<String, String> = HashMap::new();
+    let mut new_abstract_tasks: Vec<NewAbstractTask> = Vec::new();
+
+    for (wanted_id, wanted_name) in wanted {
+        let namesake = coach_abstract_tasks.iter().find(|abstract_task| abstract_task.name == *wanted_name);
+
+        let copy_id = match namesake {
+            Some(abstract_task) => abstract_task.id.to_owned(),
+            None => {
+                let request = NewAbstractTaskRequest { name: wanted_name.to_string() };
+                let new_abstract_task = NewAbstractTask::from(&request, the_coach_id);
+                let new_id = new_abstract_task.id.to_owned();
+                new_abstract_tasks.push(new_abstract_task);
+                new_id
+            }
+        };
+
+        abstract_map.insert(wanted_id.to_string(), copy_id);
+    }
+
+    Ok((abstract_map, new_abstract_tasks))
+}
+
+fn insert_plan(connection: &MysqlConnection, new_plan: &NewMasterPlan, snapshot: &PlanSnapshot, new_abstract_tasks: &[NewAbstractTask]) -> QueryResult<usize> {
+    let new_tasks = snapshot.as_new_tasks(new_plan.id.as_str());
+    let new_links = snapshot.as_new_links(new_plan.id.as_str());
+
+    connection.transaction::<_, diesel::result::Error, _>(|| {
+        diesel::insert_into(abstract_tasks::table).values(new_abstract_tasks).execute(connection)?;
+        diesel::insert_into(master_plans).values(new_plan).execute(connection)?;
+        diesel::insert_into(master_tasks).values(&new_tasks).execute(connection)?;
+        diesel::insert_into(master_task_links).values(&new_links).execute(connection)
+    })
 }
 
 fn delete_current_links(connection: &MysqlConnection, plan_id: &str) -> Result<String, diesel::result::Error> {
@@ -41,7 +217,46 @@ fn deletable_tasks(connection: &MysqlConnection, plan_id: &str, given_tasks: &[T
     Ok(tasks_to_delete)
 }
 
-pub fn update_master_plan(connection: &MysqlConnection, request: &UpdateMasterPlanRequest) -> Result<String, diesel::result::Error> {
+/**
+ * Saves the graph of the plan, unless it is broken (see validate_graph) or
+ * someone else saved it after the version the request is based on. The
+ * save is all or nothing, and it keeps a revision of the saved plan.
+ */
+pub fn update_master_plan(connection: &MysqlConnection, the_coach_id: &str, request: &UpdateMasterPlanRequest) -> Result<MasterPlan, Ferror> {
+    let plan_id = request.master_plan_id.as_str();
+    let mut errors: Vec<ValidationError> = Vec::new();
+
+    let result = connection.transaction::<_, diesel::result::Error, _>(|| {
+        if !bump_version(connection, plan_id, request.version)? {
+            return Ok(false);
+        }
+
+        // The bump holds the plan, so the tasks cannot change under the check
+        let plan_tasks: Vec<MasterTask> = master_tasks.filter(master_tasks::master_plan_id.eq(plan_id)).load(connection)?;
+
+        errors = request.validate_graph(&plan_tasks);
+        if !errors.is_empty() {
+            return Err(diesel::result::Error::RollbackTransaction);
+        }
+
+        save_graph(connection, request)?;
+        keep_revision(connection, plan_id, request.version + 1, the_coach_id)?;
+
+        Ok(true)
+    });
+
+    if !errors.is_empty() {
+        return Err(Ferror { errors });
+    }
+
+    match result {
+        Ok(true) => find(connection, plan_id).map_err(Ferror::from),
+        Ok(false) => Err(Ferror::from(VERSION_CONFLICT)),
+        Err(_) => Err(Ferror::from(SAVE_ERROR)),
+    }
+}
+
+fn save_graph(connection: &MysqlConnection, request: &UpdateMasterPlanRequest) -> Result<String, diesel::result::Error> {
     let plan_id: String = request.master_plan_id.to_owned();
 
     delete_current_links(connection, request.master_plan_id.as_str())?;
@@ -62,3 +277,269 @@ pub fn update_master_plan(connection: &MysqlConnection, request: &UpdateMasterPl
 
     Ok(String::from("Ok"))
 }
+
+/**
+ * Moves the plan to the next version, only if it is still at the given
+ * one (optimistic locking).
+ */
+pub fn bump_version(connection: &MysqlConnection, plan_id: &str, the_version: i32) -> Result<bool, diesel::result::Error> {
+    let rows = diesel::update(master_plans.filter(master_plans::id.eq(plan_id)).filter(master_plans::version.eq(the_version)))
+        .set(master_plans::version.eq(the_version + 1))
+        .execute(connection)?;
+
+    Ok(rows > 0)
+}
+
+pub fn keep_revision(connection: &MysqlConnection, plan_id: &str, the_version: i32, the_coach_id: &str) -> Result<usize, diesel::result::Error> {
+    let plan_tasks: Vec<MasterTask> = master_tasks.filter(master_tasks::master_plan_id.eq(plan_id)).load(connection)?;
+    let plan_links: Vec<MasterTaskLink> = master_task_links.filter(master_task_links::master_plan_id.eq(plan_id)).load(connection)?;
+
+    let snapshot = PlanSnapshot::of(&plan_tasks, &plan_links);
+    let new_revision = NewMasterPlanRevision::from(plan_id, the_version, &snapshot, the_coach_id);
+
+    diesel::insert_into(master_plan_revisions::table).values(&new_revision).execute(connection)?;
+
+    let pruned = master_plan_revisions::table
+        .filter(master_plan_revisions::master_plan_id.eq(plan_id))
+        .filter(master_plan_revisions::version.le(the_version - MAX_REVISIONS));
+
+    diesel::delete(pruned).execute(connection)
+}
+
+pub fn get_revisions(connection: &MysqlConnection, the_master_plan_id: &str) -> Result<Vec<MasterPlanRevision>, diesel::result::Error> {
+    master_plan_revisions::table
+        .filter(master_plan_revisions::master_plan_id.eq(the_master_plan_id))
+        .order_by(master_plan_revisions::version.desc())
+        .load(connection)
+}
+
+/**
+ * Brings the tasks and the links of the plan back to the revision. The
+ * restore is a save in its own right: it moves the plan to the next version
+ * and keeps a revision. A task still in the plan is updated in place, so the
+ * rows that hang off it survive the restore.
+ */
+pub fn restore_revision(connection: &MysqlConnection, the_coach_id: &str, request: &RestoreRevisionRequest) -> Result<MasterPlan, &'static str> {
+    let plan_id = request.master_plan_id.as_str();
+
+    let revision: MasterPlanRevision = master_plan_revisions::table
+        .filter(master_plan_revisions::id.eq(request.revision_id.as_str()))
+        .filter(master_plan_revisions::master_plan_id.eq(plan_id))
+        .first(connection)
+        .map_err(|_| REVISION_NOT_FOUND)?;
+
+    let snapshot = revision.as_snapshot().ok_or(RESTORE_ERROR)?;
+    let new_tasks = snapshot.as_new_tasks(plan_id);
+    let new_links = snapshot.as_new_links(plan_id);
+
+    let result = connection.transaction::<_, diesel::result::Error, _>(|| {
+        if !bump_version(connection, plan_id, request.version)? {
+            return Ok(false);
+        }
+
+        delete_current_links(connection, plan_id)?;
+
+        let kept_ids: Vec<&str> = new_tasks.iter().map(|task| task.id.as_str()).collect();
+        diesel::delete(master_tasks.filter(master_tasks::master_plan_id.eq(plan_id)).filter(master_tasks::id.ne_all(kept_ids))).execute(connection)?;
+
+        restore_tasks(connection, plan_id, &new_tasks)?;
+        diesel::insert_into(master_task_links).values(&new_links).execute(connection)?;
+
+        keep_revision(connection, plan_id, request.version + 1, the_coach_id)?;
+
+        Ok(true)
+    });
+
+    match result {
+        Ok(true) => find(connection, plan_id),
+        Ok(false) => Err(VERSION_CONFLICT),
+        Err(_) => Err(RESTORE_ERROR),
+    }
+}
+
+fn restore_tasks(connection: &MysqlConnection, plan_id: &str, new_tasks: &[NewMasterTask]) -> Result<usize, diesel::result::Error> {
+    let current_ids: Vec<String> = master_tasks.filter(master_tasks::master_plan_id.eq(plan_id)).select(master_tasks::id).load(connection)?;
+
+    let (current_tasks, missing_tasks): (Vec<&NewMasterTask>, Vec<&NewMasterTask>) = new_tasks.iter().partition(|task| current_ids.contains(&task.id));
+
+    for task in current_tasks {
+        diesel::update(master_tasks.filter(master_tasks::id.eq(task.id.as_str())))
+            .set(&UpdateMasterTask {
+                duration: task.duration,
+                min: task.min,
+                max: task.max,
+                task_type: task.task_type.to_owned(),
+                role_id: task.role_id.to_owned(),
+                coordinates: task.coordinates.to_owned(),
+            })
+            .execute(connection)?;
+    }
+
+    diesel::insert_into(master_tasks).values(missing_tasks).execute(connection)
+}
+
+fn find(connection: &MysqlConnection, plan_id: &str) -> Result<MasterPlan, &'static str> {
+    master_plans.filter(master_plans::id.eq(plan_id)).first(connection).map_err(|_| PLAN_NOT_FOUND)
+}
+
+/**
+ * Instantiates the master plan as the tasks (and the task links) of the
+ * enrollment. Each task is scheduled at the earliest, as per the forward
+ * links, with the plan beginning at the given date. A task of the coach
+ * role goes to the coach of the program; the rest go to the member.
+ * A plan is applied to an enrollment only once.
+ */
+pub fn apply_master_plan(connection: &MysqlConnection, request: &ApplyMasterPlanRequest) -> Result<Vec<Task>, Ferror> {
+    let applied: i64 = tasks::table
+        .filter(tasks::enrollment_id.eq(request.enrollment_id.as_str()))
+        .filter(tasks::master_plan_id.eq(request.master_plan_id.as_str()))
+        .count()
+        .get_result(connection)
+        .map_err(|_| Ferror::from(APPLY_ERROR))?;
+
+    if applied > 0 {
+        let mut ferror = Ferror::new();
+        ferror.push("master_plan_id", PLAN_ALREADY_APPLIED);
+        return Err(ferror);
+    }
+
+    let start_date = util::as_date(request.start_date.as_str())?;
+
+    instantiate(connection, request.master_plan_id.as_str(), request.enrollment_id.as_str(), start_date).map_err(Ferror::from)
+}
+
+pub fn instantiate(connection: &MysqlConnection, the_master_plan_id: &str, the_enrollment_id: &str, start_date: NaiveDateTime) -> Result<Vec<Task>, &'static str> {
+    let plan: MasterPlan = master_plans.find(the_master_plan_id).first(connection).map_err(|_| PLAN_NOT_FOUND)?;
+
+    let (enrollment, program): (Enrollment, Program) = enrollments::table
+        .inner_join(programs::table)
+        .filter(enrollments::id.eq(the_enrollment_id))
+        .first(connection)
+        .map_err(|_| ENROLLMENT_NOT_FOUND)?;
+
+    let plan_tasks: Vec<(MasterTask, AbstractTask)> = master_tasks
+        .inner_join(abstract_tasks::table)
+        .filter(master_tasks::master_plan_id.eq(plan.id.as_str()))
+        .order_by(master_tasks::created_at.asc())
+        .load(connection)
+        .map_err(|_| PLAN_NOT_FOUND)?;
+
+    if plan_tasks.is_empty() {
+        return Err(EMPTY_PLAN);
+    }
+
+    let plan_links: Vec<MasterTaskLink> = master_task_links
+        .filter(master_task_links::master_plan_id.eq(plan.id.as_str()))
+        .load(connection)
+        .map_err(|_| PLAN_NOT_FOUND)?;
+
+    let activities: Vec<Activity> = plan_tasks
+        .iter()
+        .map(|(master_task, _)| Activity {
+            id: master_task.id.to_owned(),
+            duration: master_task.duration as i64,
+            not_before: None,
+        })
+        .collect();
+
+    let dependencies: Vec<Dependency> = plan_links
+        .iter()
+        .filter(|link| link.is_forward)
+        .map(|link| Dependency {
+            source_id: link.source_task_id.to_owned(),
+            target_id: link.target_task_id.to_owned(),
+            lead_time: link.lead_time as i64,
+        })
+        .collect();
+
+    let schedules = scheduler::earliest_schedules(&activities, &dependencies, start_date)?;
+
+    let new_tasks: Vec<NewTask> = plan_tasks
+        .iter()
+        .zip(schedules.iter())
+        .map(|((master_task, abstract_task), schedule)| {
+            let actor_id = if master_task.role_id.eq_ignore_ascii_case(util::COACH) {
+                program.coach_id.as_str()
+            } else {
+                enrollment.member_id.as_str()
+            };
+
+            NewTask::for_plan(master_task, enrollment.id.as_str(), actor_id, abstract_task.name.as_str(), plan.name.as_str(), schedule)
+        })
+        .collect();
+
+    let task_ids: HashMap<&str, &str> = plan_tasks.iter().zip(new_tasks.iter()).map(|((master_task, _), new_task)| (master_task.id.as_str(), new_task.id.as_str())).collect();
+
+    let new_links: Vec<NewTaskLink> = plan_links
+        .iter()
+        .filter_map(|link| {
+            let source_id = task_ids.get(link.source_task_id.as_str())?;
+            let target_id = task_ids.get(link.target_task_id.as_str())?;
+
+            Some(NewTaskLink::for_plan(link, source_id, target_id, enrollment.id.as_str()))
+        })
+        .collect();
+
+    let result = connection.transaction::<_, diesel::result::Error, _>(|| {
+        diesel::insert_into(tasks::table).values(&new_tasks).execute(connection)?;
+        diesel::insert_into(task_links::table).values(&new_links).execute(connection)
+    });
+
+    if result.is_err() {
+        return Err(APPLY_ERROR);
+    }
+
+    let new_task_ids: Vec<&str> = new_tasks.iter().map(|new_task| new_task.id.as_str()).collect();
+
+    tasks::table
+        .filter(tasks::id.eq_any(new_task_ids))
+        .order_by(tasks::original_start_date.asc())
+        .load(connection)
+        .map_err(|_| APPLY_ERROR)
+}
+
+/**
+ * The PERT forecast of the plan over its forward links, with the min and
+ * the max of a task as its optimistic and pessimistic estimates. The tasks
+ * saved before the estimates were validated may have them off the
+ * duration; we widen such estimates to take in the duration.
+ */
+pub fn get_plan_forecast(connection: &MysqlConnection, the_master_plan_id: &str) -> Result<PlanForecast, String> {
+    let plan_tasks: Vec<MasterTask> = master_tasks
+        .filter(master_tasks::master_plan_id.eq(the_master_plan_id))
+        .order_by(master_tasks::created_at.asc())
+        .load(connection)
+        .map_err(|e| e.to_string())?;
+
+    let plan_links: Vec<MasterTaskLink> = master_task_links
+        .filter(master_task_links::master_plan_id.eq(the_master_plan_id))
+        .filter(is_forward.eq(true))
+        .load(connection)
+        .map_err(|e| e.to_string())?;
+
+    let estimates: Vec<Estimate> = plan_tasks
+        .iter()
+        .map(|master_task| Estimate {
+            id: master_task.id.to_owned(),
+            optimistic: master_task.min.min(master_task.duration) as f64,
+            likely: master_task.duration as f64,
+            pessimistic: master_task.max.max(master_task.duration) as f64,
+        })
+        .collect();
+
+    let dependencies: Vec<Dependency> = plan_links
+        .iter()
+        .map(|link| Dependency {
+            source_id: link.source_task_id.to_owned(),
+            target_id: link.target_task_id.to_owned(),
+            lead_time: link.lead_time as i64,
+        })
+        .collect();
+
+    let forecast = scheduler::pert(&estimates, &dependencies)?;
+
+    Ok(PlanForecast {
+        master_plan_id: the_master_plan_id.to_owned(),
+        forecast,
+    })
+}
@@ -3,32 +3,77 @@ use diesel::prelude::*;
 use crate::models::master_tasks::MasterTaskCriteria;
 use crate::models::master_tasks::{MasterTask, NewMasterTask, NewMasterTaskRequest, UpdateMasterTask, UpdateMasterTaskRequest};
 use crate::schema::master_tasks::dsl::*;
+use crate::services::master_plans::{bump_version, keep_revision, VERSION_CONFLICT};
 
-pub fn create_master_task(connection: &MysqlConnection, request: &NewMasterTaskRequest) -> Result<MasterTask, diesel::result::Error> {
-    let new_master_task = NewMasterTask::from(request);
+const TASK_NOT_FOUND: &str = "Unable to find the master task.";
+const SAVE_ERROR: &str = "Unable to save the master task.";
 
-    diesel::insert_into(master_tasks).values(&new_master_task).execute(connection)?;
+/**
+ * Adds the task to the plan. Like any save of the plan, it moves the plan to
+ * the next version and keeps a revision, all or nothing.
+ */
+pub fn create_master_task(connection: &MysqlConnection, the_coach_id: &str, request: &NewMasterTaskRequest) -> Result<MasterTask, &'static str> {
+    let new_master_task = NewMasterTask::from(request, the_coach_id);
+    let plan_id = request.master_plan_id.as_str();
 
-    master_tasks.filter(id.eq(new_master_task.id)).first(connection)
+    let result = connection.transaction::<_, diesel::result::Error, _>(|| {
+        if !bump_version(connection, plan_id, request.version)? {
+            return Ok(false);
+        }
+
+        diesel::insert_into(master_tasks).values(&new_master_task).execute(connection)?;
+        keep_revision(connection, plan_id, request.version + 1, the_coach_id)?;
+
+        Ok(true)
+    });
+
+    match result {
+        Ok(true) => find(connection, new_master_task.id.as_str()),
+        Ok(false) => Err(VERSION_CONFLICT),
+        Err(_) => Err(SAVE_ERROR),
+    }
 }
 
-pub fn update_master_task(connection: &MysqlConnection, request: &UpdateMasterTaskRequest) -> Result<MasterTask, diesel::result::Error> {
-    let the_id = &request.id.as_str();
-
-    diesel::update(master_tasks.filter(id.eq(the_id)))
-        .set(&UpdateMasterTask {
-            duration: request.duration,
-            min: request.min,
-            max: request.max,
-            task_type: request.task_type.to_owned(),
-            role_id: request.role_id.to_owned(),
-            coordinates: request.coordinates.to_owned(),
-        })
-        .execute(connection)?;
-
-    master_tasks.filter(id.eq(the_id)).first(connection)
+/**
+ * Edits the task; a save of its plan, as with create_master_task.
+ */
+pub fn update_master_task(connection: &MysqlConnection, the_coach_id: &str, request: &UpdateMasterTaskRequest) -> Result<MasterTask, &'static str> {
+    let the_id = request.id.as_str();
+    let task = find(connection, the_id)?;
+    let plan_id = task.master_plan_id.as_str();
+
+    let result = connection.transaction::<_, diesel::result::Error, _>(|| {
+        if !bump_version(connection, plan_id, request.version)? {
+            return Ok(false);
+        }
+
+        diesel::update(master_tasks.filter(id.eq(the_id)))
+            .set(&UpdateMasterTask {
+                duration: request.duration,
+                min: request.min,
+                max: request.max,
+                task_type: request.task_type.to_owned(),
+                role_id: request.role_id.to_owned(),
+                coordinates: request.coordinates.to_owned(),
+            })
+            .execute(connection)?;
+
+        keep_revision(connection, plan_id, request.version + 1, the_coach_id)?;
+
+        Ok(true)
+    });
+
+    match result {
+        Ok(true) => find(connection, the_id),
+        Ok(false) => Err(VERSION_CONFLICT),
+        Err(_) => Err(SAVE_ERROR),
+    }
 }
 
 pub fn get_master_tasks(connection: &MysqlConnection, criteria: MasterTaskCriteria) -> Result<Vec<MasterTask>, diesel::result::Error> {
     master_tasks.filter(master_plan_id.eq(criteria.master_plan_id)).load(connection)
 }
+
+fn find(connection: &MysqlConnection, the_id: &str) -> Result<MasterTask, &'static str> {
+    master_tasks.filter(id.eq(the_id)).first(connection).map_err(|_| TASK_NOT_FOUND)
+}
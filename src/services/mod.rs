@@ -1,13 +1,23 @@
 pub mod abstract_tasks;
+pub mod availabilities;
+pub mod calendar_feeds;
+pub mod coaches;
+pub mod diagrams;
 pub mod enrollments;
+pub mod master_plan_shares;
 pub mod master_plans;
 pub mod master_tasks;
 pub mod notes;
 pub mod objectives;
 pub mod observations;
 pub mod options;
+pub mod program_plans;
 pub mod programs;
+pub mod schedules;
+pub mod session_series;
 pub mod sessions;
+pub mod task_links;
+pub mod task_moves;
 pub mod tasks;
 pub mod users;
 pub mod correspondences;
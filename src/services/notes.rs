@@ -7,18 +7,21 @@ use crate::services::sessions::find_session_user;
 use crate::schema::session_files::dsl::*;
 use crate::schema::session_notes::dsl::*;
 
-pub fn create_new_note(connection: &MysqlConnection, request: &NewNoteRequest) -> QueryResult<Note> {
+const SESSION_USER_NOT_FOUND: &str = "Unable to find the attendee of the session.";
+const SAVE_ERROR: &str = "Unable to save the note.";
+
+pub fn create_new_note(connection: &MysqlConnection, request: &NewNoteRequest) -> Result<Note, &'static str> {
     let the_session_user_id = &request.session_user_id.as_str();
 
-    let session_user = find_session_user(connection, the_session_user_id)?;
+    let session_user = find_session_user(connection, the_session_user_id).map_err(|_| SESSION_USER_NOT_FOUND)?;
 
-    let new_note = NewNote::from(request, session_user);
+    let new_note = NewNote::from(request, session_user)?;
 
-    diesel::insert_into(session_notes).values(&new_note).execute(connection)?;
+    diesel::insert_into(session_notes).values(&new_note).execute(connection).map_err(|_| SAVE_ERROR)?;
 
-    let note: Note = find(connection, &new_note.id.as_str())?;
+    let note: Note = find(connection, &new_note.id.as_str()).map_err(|_| SAVE_ERROR)?;
 
-    insert_files(connection, request, &note)?;
+    insert_files(connection, request, &note).map_err(|_| SAVE_ERROR)?;
 
     Ok(note)
 }
@@ -5,19 +5,22 @@ use crate::models::enrollments::PlanCriteria;
 use crate::models::objectives::{NewObjective, NewObjectiveRequest, Objective, UpdateObjective, UpdateObjectiveRequest};
 use crate::schema::objectives::dsl::*;
 
-pub fn create_objective(connection: &MysqlConnection, request: &NewObjectiveRequest) -> Result<Objective, diesel::result::Error> {
-    let new_objective = NewObjective::from(request);
+const OBJECTIVE_NOT_FOUND: &str = "Unable to find the objective.";
+const SAVE_ERROR: &str = "Unable to save the objective.";
 
-    diesel::insert_into(objectives).values(&new_objective).execute(connection)?;
+pub fn create_objective(connection: &MysqlConnection, request: &NewObjectiveRequest) -> Result<Objective, &'static str> {
+    let new_objective = NewObjective::from(request)?;
 
-    objectives.filter(id.eq(new_objective.id)).first(connection)
+    diesel::insert_into(objectives).values(&new_objective).execute(connection).map_err(|_| SAVE_ERROR)?;
+
+    find(connection, new_objective.id.as_str())
 }
 
-pub fn update_objective(connection: &MysqlConnection, request: &UpdateObjectiveRequest) -> Result<Objective, diesel::result::Error> {
+pub fn update_objective(connection: &MysqlConnection, request: &UpdateObjectiveRequest) -> Result<Objective, &'static str> {
     let the_id = &request.id.as_str();
 
-    let start_date = util::as_date(request.start_time.as_str());
-    let end_date = util::as_date(request.end_time.as_str());
+    let start_date = util::as_date(request.start_time.as_str())?;
+    let end_date = util::as_date(request.end_time.as_str())?;
 
     diesel::update(objectives.filter(id.eq(the_id)))
         .set(&UpdateObjective {
@@ -25,9 +28,14 @@ pub fn update_objective(connection: &MysqlConnection, request: &UpdateObjectiveR
             original_start_date: start_date,
             original_end_date: end_date,
         })
-        .execute(connection)?;
+        .execute(connection)
+        .map_err(|_| SAVE_ERROR)?;
+
+    find(connection, the_id)
+}
 
-    objectives.filter(id.eq(the_id)).first(connection)
+fn find(connection: &MysqlConnection, the_id: &str) -> Result<Objective, &'static str> {
+    objectives.filter(id.eq(the_id)).first(connection).map_err(|_| OBJECTIVE_NOT_FOUND)
 }
 /**
  * Let us stuff the content form the file system
@@ -0,0 +1,116 @@
+use diesel::prelude::*;
+
+use crate::commons::util;
+use crate::models::enrollments::Enrollment;
+use crate::models::program_plans::{AttachPlanRequest, NewProgramPlan, ProgramPlan, ProgramPlanRequest};
+use crate::models::programs::Program;
+use crate::models::tasks::Task;
+
+use crate::services::master_plans::{self, EMPTY_PLAN};
+use crate::services::programs;
+
+use crate::schema::program_plans::dsl::*;
+
+const ALREADY_ATTACHED: &str = "The master plan is already part of the program.";
+const ATTACH_ERROR: &str = "Unable to attach the master plan to the program.";
+const PROGRAM_PLAN_NOT_FOUND: &str = "Unable to find the plan of the program.";
+const DETACH_ERROR: &str = "Unable to detach the master plan from the program.";
+
+pub fn attach_plan(connection: &MysqlConnection, request: &AttachPlanRequest) -> Result<ProgramPlan, &'static str> {
+    let prior: QueryResult<ProgramPlan> = program_plans
+        .filter(program_id.eq(request.program_id.as_str()))
+        .filter(master_plan_id.eq(request.master_plan_id.as_str()))
+        .first(connection);
+
+    if prior.is_ok() {
+        return Err(ALREADY_ATTACHED);
+    }
+
+    let new_plan = NewProgramPlan::from(request);
+
+    let result = connection.transaction::<_, diesel::result::Error, _>(|| {
+        if new_plan.is_default {
+            clear_default(connection, new_plan.program_id.as_str())?;
+        }
+
+        diesel::insert_into(program_plans).values(&new_plan).execute(connection)
+    });
+
+    if result.is_err() {
+        return Err(ATTACH_ERROR);
+    }
+
+    find(connection, request.program_id.as_str(), new_plan.id.as_str())
+}
+
+/**
+ * Only one plan of a program is the default one.
+ */
+pub fn make_default(connection: &MysqlConnection, request: &ProgramPlanRequest) -> Result<ProgramPlan, &'static str> {
+    let program_plan = find(connection, request.program_id.as_str(), request.program_plan_id.as_str())?;
+
+    let result = connection.transaction::<_, diesel::result::Error, _>(|| {
+        clear_default(connection, program_plan.program_id.as_str())?;
+        diesel::update(program_plans.filter(id.eq(program_plan.id.as_str()))).set(is_default.eq(true)).execute(connection)
+    });
+
+    if result.is_err() {
+        return Err(ATTACH_ERROR);
+    }
+
+    find(connection, request.program_id.as_str(), request.program_plan_id.as_str())
+}
+
+pub fn detach_plan(connection: &MysqlConnection, request: &ProgramPlanRequest) -> Result<ProgramPlan, &'static str> {
+    let program_plan = find(connection, request.program_id.as_str(), request.program_plan_id.as_str())?;
+
+    let result = diesel::delete(program_plans.filter(id.eq(program_plan.id.as_str()))).execute(connection);
+
+    if result.is_err() {
+        return Err(DETACH_ERROR);
+    }
+
+    Ok(program_plan)
+}
+
+pub fn get_program_plans(connection: &MysqlConnection, the_program_id: &str) -> Result<Vec<ProgramPlan>, diesel::result::Error> {
+    program_plans.filter(program_id.eq(the_program_id)).order_by(name.asc()).load(connection)
+}
+
+/**
+ * Applies the default plan of the program, starting now, to a new
+ * enrollment. A program of a peer coach without a default plan of its own
+ * falls back to the default plan of its parent program. No default plan, or
+ * an empty one, leaves the enrollment without tasks.
+ */
+pub fn apply_default_plan(connection: &MysqlConnection, enrollment: &Enrollment) -> Result<Vec<Task>, &'static str> {
+    let program: Program = programs::find(connection, enrollment.program_id.as_str())?;
+
+    let default_plan = find_default(connection, program.id.as_str()).or_else(|| find_default(connection, program.coalesce_parent_id()));
+
+    let default_plan = match default_plan {
+        Some(plan) => plan,
+        None => return Ok(Vec::new()),
+    };
+
+    match master_plans::instantiate(connection, default_plan.master_plan_id.as_str(), enrollment.id.as_str(), util::now()) {
+        Err(EMPTY_PLAN) => Ok(Vec::new()),
+        result => result,
+    }
+}
+
+fn find_default(connection: &MysqlConnection, the_program_id: &str) -> Option<ProgramPlan> {
+    program_plans.filter(program_id.eq(the_program_id)).filter(is_default.eq(true)).first(connection).ok()
+}
+
+fn find(connection: &MysqlConnection, the_program_id: &str, the_program_plan_id: &str) -> Result<ProgramPlan, &'static str> {
+    program_plans
+        .filter(id.eq(the_program_plan_id))
+        .filter(program_id.eq(the_program_id))
+        .first(connection)
+        .map_err(|_| PROGRAM_PLAN_NOT_FOUND)
+}
+
+fn clear_default(connection: &MysqlConnection, the_program_id: &str) -> Result<usize, diesel::result::Error> {
+    diesel::update(program_plans.filter(program_id.eq(the_program_id))).set(is_default.eq(false)).execute(connection)
+}
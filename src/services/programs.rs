@@ -39,9 +39,9 @@ pub fn find(connection: &MysqlConnection, the_id: &str) -> Result<Program, &'sta
  * The program will be the parent program through this route
  *
  */
-pub fn create_new_program(connection: &MysqlConnection, request: &NewProgramRequest) -> Result<Program, &'static str> {
+pub fn create_new_program(connection: &MysqlConnection, the_coach_id: &str, request: &NewProgramRequest) -> Result<Program, &'static str> {
     //Finding coach with fuzzy_id
-    let coach = find_coach_by_id(connection, request.coach_id.as_str())?;
+    let coach = find_coach_by_id(connection, the_coach_id)?;
 
     //Transform result into new_program
     let new_program = NewProgram::from_request(request, &coach);
@@ -0,0 +1,285 @@
+use std::collections::{HashMap, VecDeque};
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::commons::scheduler::{self, Activity, Dependency, Schedule};
+use crate::commons::util;
+
+use crate::models::schedules::{EnrollmentSchedule, TaskTiming};
+use crate::models::task_links::TaskLink;
+use crate::models::tasks::Task;
+
+use crate::schema::task_links;
+use crate::schema::tasks;
+
+/**
+ * The critical path schedule of the live (not cancelled) tasks of an
+ * enrollment, over the forward links among them.
+ *
+ * The tasks keep to their dates: a task does not start before its scheduled
+ * start, a started task holds its actual start, and a finished task its
+ * actual dates. A task overdue to start is taken to start now, and a task
+ * running past its duration to run on till now; thus the delays so far
+ * flow down to the end of the programme.
+ */
+pub fn get_enrollment_schedule(connection: &MysqlConnection, the_enrollment_id: &str) -> Result<EnrollmentSchedule, String> {
+    let (live_tasks, links) = load_graph(connection, the_enrollment_id).map_err(|e| e.to_string())?;
+
+    as_schedule(the_enrollment_id, live_tasks, &links, util::now())
+}
+
+/**
+ * The live (not cancelled) tasks of the enrollment and the forward links.
+ */
+pub fn load_graph(connection: &MysqlConnection, the_enrollment_id: &str) -> QueryResult<(Vec<Task>, Vec<TaskLink>)> {
+    let live_tasks: Vec<Task> = tasks::table
+        .filter(tasks::enrollment_id.eq(the_enrollment_id))
+        .filter(tasks::cancelled_at.is_null())
+        .order_by(tasks::original_start_date.asc())
+        .load(connection)?;
+
+    let links: Vec<TaskLink> = task_links::table
+        .filter(task_links::enrollment_id.eq(the_enrollment_id))
+        .filter(task_links::is_forward.eq(true))
+        .load(connection)?;
+
+    Ok((live_tasks, links))
+}
+
+/**
+ * The successors (through the forward links) of the given causes that have
+ * to move later, given the delays so far: a successor yet to start moves to
+ * its earliest start, when that is after its scheduled start. Each move
+ * names the nearest of the causes behind it, along with the index of the
+ * task moved.
+ */
+pub fn downstream_moves(live_tasks: &[Task], links: &[TaskLink], cause_ids: &[&str], now: NaiveDateTime) -> Result<Vec<(usize, String, Schedule)>, String> {
+    let activities: Vec<Activity> = live_tasks.iter().map(|task| as_activity(task, now)).collect();
+    let dependencies = as_dependencies(&activities, links);
+
+    let start_date = activities.iter().filter_map(|activity| activity.not_before).min().unwrap_or(now);
+    let schedules = scheduler::earliest_schedules(&activities, &dependencies, start_date)?;
+
+    // The nearest cause of every successor, breadth first from the causes
+    let mut causes: HashMap<&str, &str> = cause_ids.iter().map(|id| (*id, *id)).collect();
+    let mut pending: VecDeque<&str> = cause_ids.iter().copied().collect();
+
+    while let Some(current) = pending.pop_front() {
+        let cause = causes[current];
+
+        for dependency in dependencies.iter().filter(|d| d.source_id == current) {
+            let target = dependency.target_id.as_str();
+
+            if !causes.contains_key(target) {
+                causes.insert(target, cause);
+                pending.push_back(target);
+            }
+        }
+    }
+
+    let moves = live_tasks
+        .iter()
+        .zip(schedules)
+        .enumerate()
+        .filter(|(_, (task, _))| !cause_ids.contains(&task.id.as_str()) && task.actual_start_date.is_none())
+        .filter_map(|(index, (task, schedule))| {
+            let cause = causes.get(task.id.as_str())?;
+            let scheduled_start = task.revised_start_date.unwrap_or(task.original_start_date);
+
+            if schedule.start_date <= scheduled_start {
+                return None;
+            }
+
+            Some((index, cause.to_string(), schedule))
+        })
+        .collect();
+
+    Ok(moves)
+}
+
+/**
+ * The forward links among the live tasks; a link to a cancelled task holds
+ * nothing.
+ */
+fn as_dependencies(activities: &[Activity], links: &[TaskLink]) -> Vec<Dependency> {
+    links
+        .iter()
+        .filter(|link| link.is_forward)
+        .filter(|link| activities.iter().any(|a| a.id == link.source_task_id) && activities.iter().any(|a| a.id == link.target_task_id))
+        .map(|link| Dependency {
+            source_id: link.source_task_id.to_owned(),
+            target_id: link.target_task_id.to_owned(),
+            lead_time: link.lead_time as i64,
+        })
+        .collect()
+}
+
+fn as_schedule(the_enrollment_id: &str, live_tasks: Vec<Task>, links: &[TaskLink], now: NaiveDateTime) -> Result<EnrollmentSchedule, String> {
+    let activities: Vec<Activity> = live_tasks.iter().map(|task| as_activity(task, now)).collect();
+    let dependencies = as_dependencies(&activities, links);
+
+    let start_date = activities.iter().filter_map(|activity| activity.not_before).min().unwrap_or(now);
+    let timings = scheduler::critical_path(&activities, &dependencies, start_date)?;
+
+    let planned_end_date = live_tasks.iter().map(schedule_end).max();
+    let projected_end_date = timings.iter().map(|timing| timing.earliest.end_date).max();
+
+    let timings: Vec<TaskTiming> = live_tasks
+        .into_iter()
+        .zip(timings)
+        .map(|(task, timing)| TaskTiming {
+            is_delayed: is_delayed(&task, now),
+            task,
+            timing,
+        })
+        .collect();
+
+    Ok(EnrollmentSchedule {
+        enrollment_id: the_enrollment_id.to_owned(),
+        planned_end_date,
+        projected_end_date,
+        timings,
+    })
+}
+
+fn as_activity(task: &Task, now: NaiveDateTime) -> Activity {
+    let scheduled_start = task.revised_start_date.unwrap_or(task.original_start_date);
+
+    let (not_before, duration) = match (task.actual_start_date, task.actual_end_date) {
+        (Some(start), Some(end)) => (start, hours_between(start, end)),
+        (Some(start), None) => (start, hours_between(start, now).max(task.duration as i64)),
+        _ => (scheduled_start.max(now), task.duration as i64),
+    };
+
+    Activity {
+        id: task.id.to_owned(),
+        duration,
+        not_before: Some(not_before),
+    }
+}
+
+pub fn is_delayed(task: &Task, now: NaiveDateTime) -> bool {
+    if task.actual_end_date.is_some() {
+        return false;
+    }
+
+    let scheduled_start = task.revised_start_date.unwrap_or(task.original_start_date);
+    schedule_end(task) < now || (task.actual_start_date.is_none() && scheduled_start < now)
+}
+
+fn schedule_end(task: &Task) -> NaiveDateTime {
+    task.revised_end_date.unwrap_or(task.original_end_date)
+}
+
+/**
+ * The whole hours from the start to the end, a part of an hour counting as
+ * an hour.
+ */
+fn hours_between(start: NaiveDateTime, end: NaiveDateTime) -> i64 {
+    let minutes = (end - start).num_minutes().max(0);
+
+    (minutes + 59) / 60
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use chrono::Duration;
+
+    fn task(id: &str, start_time: &str, duration: i32) -> Task {
+        let start_date = util::as_date(start_time).unwrap();
+
+        Task {
+            id: String::from(id),
+            enrollment_id: String::from("enrollment"),
+            actor_id: String::from("member"),
+            name: String::from(id),
+            duration,
+            min: duration,
+            max: duration,
+            original_start_date: start_date,
+            original_end_date: start_date + Duration::hours(duration as i64),
+            revised_start_date: None,
+            revised_end_date: None,
+            offered_start_date: None,
+            offered_end_date: None,
+            actual_start_date: None,
+            actual_end_date: None,
+            locked: false,
+            created_at: start_date,
+            updated_at: start_date,
+            description: None,
+            closing_notes: None,
+            response: None,
+            approved_at: None,
+            cancelled_at: None,
+            responded_date: None,
+            master_plan_id: None,
+        }
+    }
+
+    fn link(source_id: &str, target_id: &str) -> TaskLink {
+        TaskLink {
+            id: format!("{}-{}", source_id, target_id),
+            source_task_id: String::from(source_id),
+            target_task_id: String::from(target_id),
+            lead_time: 0,
+            coordinates: String::new(),
+            priority: 1,
+            is_forward: true,
+            enrollment_id: String::from("enrollment"),
+        }
+    }
+
+    #[test]
+    fn should_push_out_the_end_by_a_critical_delay() {
+        // a -> c and b -> c; a is overdue to start by 2 hours and has no slack
+        let tasks = vec![task("a", "2021-03-01T08:00:00Z", 4), task("b", "2021-03-01T08:00:00Z", 1), task("c", "2021-03-01T12:00:00Z", 2)];
+        let links = vec![link("a", "c"), link("b", "c")];
+        let now = util::as_date("2021-03-01T10:00:00Z").unwrap();
+
+        let schedule = as_schedule("enrollment", tasks, &links, now).unwrap();
+
+        assert_eq!(Some(util::as_date("2021-03-01T14:00:00Z").unwrap()), schedule.planned_end_date);
+        assert_eq!(Some(util::as_date("2021-03-01T16:00:00Z").unwrap()), schedule.projected_end_date);
+
+        assert!(schedule.timings[0].is_delayed && schedule.timings[0].timing.is_critical());
+        assert!(schedule.timings[1].is_delayed && !schedule.timings[1].timing.is_critical());
+    }
+
+    #[test]
+    fn should_move_only_the_successors_of_the_slip() {
+        // a -> b -> c, with d on its own; a runs 3 hours over its end
+        let mut a = task("a", "2021-03-01T08:00:00Z", 2);
+        a.actual_start_date = Some(a.original_start_date);
+
+        let tasks = vec![a, task("b", "2021-03-01T11:00:00Z", 2), task("c", "2021-03-01T16:00:00Z", 1), task("d", "2021-03-01T12:00:00Z", 1)];
+        let links = vec![link("a", "b"), link("b", "c")];
+        let now = util::as_date("2021-03-01T13:00:00Z").unwrap();
+
+        let moves = downstream_moves(&tasks, &links, &["a"], now).unwrap();
+
+        // b moves from 11:00 to 13:00, but c (16:00) still fits after b
+        assert_eq!(1, moves.len());
+        assert_eq!(1, moves[0].0);
+        assert_eq!("a", moves[0].1);
+        assert_eq!(util::as_date("2021-03-01T13:00:00Z").unwrap(), moves[0].2.start_date);
+        assert_eq!(util::as_date("2021-03-01T15:00:00Z").unwrap(), moves[0].2.end_date);
+    }
+
+    #[test]
+    fn should_reject_a_cyclic_plan() {
+        let tasks = vec![task("a", "2021-03-01T08:00:00Z", 1), task("b", "2021-03-01T09:00:00Z", 1)];
+        let links = vec![link("a", "b"), link("b", "a")];
+
+        assert!(as_schedule("enrollment", tasks, &links, util::as_date("2021-03-01T07:00:00Z").unwrap()).is_err());
+    }
+
+    #[test]
+    fn should_count_part_of_an_hour_as_an_hour() {
+        assert_eq!(2, hours_between(util::as_date("2021-03-01T08:00:00Z").unwrap(), util::as_date("2021-03-01T09:10:00Z").unwrap()));
+        assert_eq!(0, hours_between(util::as_date("2021-03-01T09:00:00Z").unwrap(), util::as_date("2021-03-01T08:00:00Z").unwrap()));
+    }
+}
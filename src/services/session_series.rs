@@ -0,0 +1,354 @@
+use chrono::{Duration, NaiveDateTime};
+use diesel::prelude::*;
+
+use crate::commons::chassis::ValidationError;
+use crate::commons::recurrence::{Recurrence, MAX_OCCURRENCES};
+use crate::commons::util;
+
+use crate::models::correspondences::{MailOut, MailRecipient};
+use crate::models::ferror::Ferror;
+use crate::models::session_series::{as_exceptions, CancelOccurrenceRequest, NewSessionSeries, NewSessionSeriesRequest, SeriesRow, SessionSeries, UpdateSeriesRequest};
+use crate::models::session_users::NewSessionUser;
+use crate::models::sessions::{NewSession, Session};
+use crate::models::users::User;
+
+use crate::schema::session_series;
+use crate::schema::session_users;
+use crate::schema::sessions;
+
+use crate::services::availabilities::check_schedule;
+use crate::services::correspondences::create_mail;
+use crate::services::enrollments;
+use crate::services::programs;
+use crate::services::sessions::{create_session_mail, find, find_team, send_session_cancel_mail};
+use crate::services::users;
+
+const SERIES_CREATION_ERROR: &str = "Unable to create the session series.";
+const SERIES_UPDATE_ERROR: &str = "Unable to revise the session series.";
+const SERIES_NOT_FOUND: &str = "Unable to find the session series.";
+const NOT_IN_SERIES: &str = "The session is not a part of a series.";
+const NO_OCCURRENCES: &str = "The rule yields no sessions.";
+const TOO_MANY_OCCURRENCES: &str = "The rule yields more than 104 sessions.";
+const UNCHANGEABLE_OCCURRENCE: &str = "The session is either cancelled or started. Hence it cannot be changed.";
+const CANCEL_ERROR: &str = "Unable to cancel the session.";
+const REVISED_NOTES: &str = "Replaced by the revised series.";
+
+/**
+ * Creates the series and its sessions, after checking each of the sessions
+ * against the schedule of the coach and the member.
+ */
+pub fn create_series(connection: &MysqlConnection, request: &NewSessionSeriesRequest) -> Result<SeriesRow, Ferror> {
+    let program = programs::find(connection, request.program_id.as_str())?;
+    let coach: User = users::find(connection, program.coach_id.as_str())?;
+    let member: User = users::find(connection, request.member_id.as_str())?;
+    let enrollment = enrollments::find(connection, &program, &member)?;
+
+    let new_series = NewSessionSeries::from(request, enrollment.id.to_owned(), coach.time_zone.to_owned())?;
+    let dates = occurrences_of(&new_series)?;
+
+    check_occurrences(connection, &coach, &member, &dates, new_series.duration, &[])?;
+
+    let result = connection.transaction::<_, diesel::result::Error, _>(|| {
+        diesel::insert_into(session_series::table).values(&new_series).execute(connection)?;
+        let series: SessionSeries = session_series::table.find(new_series.id.as_str()).first(connection)?;
+
+        insert_occurrences(connection, &series, &coach, &member, &dates)
+    });
+
+    if result.is_err() {
+        return Err(Ferror::from(SERIES_CREATION_ERROR));
+    }
+
+    enrollments::mark_as_old(connection, enrollment.id())?;
+
+    let row = find_series_row(connection, new_series.id.as_str())?;
+
+    let mail_out = MailOut::for_new_series(&row.series, &coach, &member, &row.sessions);
+    let recipients = MailRecipient::build_recipients(&member, &coach, mail_out.id.as_str());
+    create_mail(connection, mail_out, recipients)?;
+
+    send_occurrence_mails(connection, &coach, &member, &row.sessions, &[])?;
+
+    Ok(row)
+}
+
+/**
+ * Edits the given occurrence and the following ones ("this and following").
+ *
+ * The series is split at the occurrence: the earlier occurrences stay with
+ * the series, which now ends before the occurrence, while the revised
+ * occurrences make a new series. The following sessions are cancelled and
+ * replaced by those of the new series. The first occurrence revises the
+ * series as a whole, so the series is updated in place instead.
+ */
+pub fn update_following(connection: &MysqlConnection, request: &UpdateSeriesRequest) -> Result<SeriesRow, Ferror> {
+    let occurrence = find(connection, request.session_id.as_str())?;
+    let series = find_series_of(connection, &occurrence)?;
+
+    if !occurrence.can_reschedule() {
+        return Err(Ferror::from(UNCHANGEABLE_OCCURRENCE));
+    }
+
+    let (coach, member) = find_team(connection, &occurrence)?;
+
+    let recurrence = series.recurrence().map_err(|e| Ferror::from(e.as_str()))?;
+    let index = recurrence
+        .occurrences(series.start_date, series.zone())
+        .iter()
+        .position(|date| *date == occurrence.original_start_date)
+        .ok_or(NOT_IN_SERIES)?;
+
+    let start_date = request.start_time.as_deref().map(util::as_date).transpose()?.unwrap_or(occurrence.original_start_date);
+
+    // The following exceptions move along with the occurrences; a new rule leaves them behind
+    let (rule, exceptions) = match &request.rule {
+        Some(given_rule) => (Recurrence::parse(given_rule).map_err(|e| Ferror::from(e.as_str()))?.to_rule(), Vec::new()),
+        None => {
+            let remaining = match recurrence.count {
+                Some(count) => recurrence.ending_after(count - index as u32),
+                None => recurrence.clone(),
+            };
+
+            let shift = start_date - occurrence.original_start_date;
+            let moved: Vec<NaiveDateTime> = series
+                .exception_dates()
+                .into_iter()
+                .filter(|date| *date >= occurrence.original_start_date)
+                .map(|date| date + shift)
+                .collect();
+
+            (remaining.to_rule(), moved)
+        }
+    };
+
+    let mut revised = NewSessionSeries::revise(&series, request, start_date, rule, &exceptions);
+    if index == 0 {
+        revised.id = series.id.to_owned();
+    }
+
+    let dates = occurrences_of(&revised)?;
+
+    let following: Vec<Session> = sessions::table
+        .filter(sessions::series_id.eq(series.id.as_str()))
+        .filter(sessions::original_start_date.ge(occurrence.original_start_date))
+        .filter(sessions::cancelled_at.is_null())
+        .filter(sessions::actual_start_date.is_null())
+        .load(connection)
+        .map_err(|_| Ferror::from(SERIES_UPDATE_ERROR))?;
+
+    let following_ids: Vec<String> = following.iter().map(|session| session.id.to_owned()).collect();
+
+    check_occurrences(connection, &coach, &member, &dates, revised.duration, &following_ids)?;
+
+    let result = connection.transaction::<_, diesel::result::Error, _>(|| {
+        if index == 0 {
+            diesel::update(session_series::table.find(series.id.as_str()))
+                .set((
+                    session_series::name.eq(&revised.name),
+                    session_series::description.eq(&revised.description),
+                    session_series::duration.eq(revised.duration),
+                    session_series::start_date.eq(revised.start_date),
+                    session_series::rule.eq(&revised.rule),
+                    session_series::exceptions.eq(&revised.exceptions),
+                ))
+                .execute(connection)?;
+        } else {
+            let earlier_exceptions: Vec<NaiveDateTime> = series.exception_dates().into_iter().filter(|date| *date < occurrence.original_start_date).collect();
+
+            diesel::update(session_series::table.find(series.id.as_str()))
+                .set((
+                    session_series::rule.eq(recurrence.ending_after(index as u32).to_rule()),
+                    session_series::exceptions.eq(as_exceptions(&earlier_exceptions)),
+                ))
+                .execute(connection)?;
+
+            diesel::insert_into(session_series::table).values(&revised).execute(connection)?;
+        }
+
+        diesel::update(sessions::table.filter(sessions::id.eq_any(&following_ids)))
+            .set((
+                sessions::cancelled_at.eq(util::now()),
+                sessions::closing_notes.eq(REVISED_NOTES),
+                sessions::sequence.eq(sessions::sequence + 1),
+            ))
+            .execute(connection)?;
+
+        let target: SessionSeries = session_series::table.find(revised.id.as_str()).first(connection)?;
+        insert_occurrences(connection, &target, &coach, &member, &dates)
+    });
+
+    let new_ids = match result {
+        Ok(new_ids) => new_ids,
+        Err(_) => return Err(Ferror::from(SERIES_UPDATE_ERROR)),
+    };
+
+    let row = find_series_row(connection, revised.id.as_str())?;
+    let live_sessions: Vec<Session> = row.sessions.iter().filter(|session| session.cancelled_at.is_none()).cloned().collect();
+
+    let mail_out = MailOut::for_revised_series(&row.series, &coach, &member, &live_sessions);
+    let recipients = MailRecipient::build_recipients(&member, &coach, mail_out.id.as_str());
+    create_mail(connection, mail_out, recipients)?;
+
+    // The replaced sessions, as cancelled, and the sessions replacing them
+    let replaced: Vec<Session> = sessions::table
+        .filter(sessions::id.eq_any(&following_ids))
+        .load(connection)
+        .map_err(|_| Ferror::from(SERIES_UPDATE_ERROR))?;
+    let replacing: Vec<Session> = live_sessions.into_iter().filter(|session| new_ids.contains(&session.id)).collect();
+
+    send_occurrence_mails(connection, &coach, &member, &replacing, &replaced)?;
+
+    Ok(row)
+}
+
+/**
+ * Cancels a single occurrence, leaving the rest of the series as it is. The
+ * occurrence becomes an exception of the series.
+ */
+pub fn cancel_occurrence(connection: &MysqlConnection, request: &CancelOccurrenceRequest) -> Result<Session, &'static str> {
+    let occurrence = find(connection, request.session_id.as_str())?;
+    let series = find_series_of(connection, &occurrence)?;
+
+    if !occurrence.can_reschedule() {
+        return Err(UNCHANGEABLE_OCCURRENCE);
+    }
+
+    let mut exceptions = series.exception_dates();
+    exceptions.push(occurrence.original_start_date);
+
+    let result = connection.transaction::<_, diesel::result::Error, _>(|| {
+        diesel::update(sessions::table.find(occurrence.id.as_str()))
+            .set((
+                sessions::cancelled_at.eq(util::now()),
+                sessions::closing_notes.eq(request.reason.as_str()),
+                sessions::sequence.eq(sessions::sequence + 1),
+            ))
+            .execute(connection)?;
+
+        diesel::update(session_series::table.find(series.id.as_str()))
+            .set(session_series::exceptions.eq(as_exceptions(&exceptions)))
+            .execute(connection)
+    });
+
+    if result.is_err() {
+        return Err(CANCEL_ERROR);
+    }
+
+    let session = find(connection, occurrence.id.as_str())?;
+    send_session_cancel_mail(connection, &session)?;
+
+    Ok(session)
+}
+
+/**
+ * The series the given session belongs to, along with all its sessions.
+ */
+pub fn get_series(connection: &MysqlConnection, the_session_id: &str) -> Result<SeriesRow, &'static str> {
+    let session = find(connection, the_session_id)?;
+    let series = find_series_of(connection, &session)?;
+
+    find_series_row(connection, series.id.as_str())
+}
+
+fn find_series_of(connection: &MysqlConnection, session: &Session) -> Result<SessionSeries, &'static str> {
+    let the_series_id = session.series_id.as_deref().ok_or(NOT_IN_SERIES)?;
+
+    session_series::table.find(the_series_id).first(connection).map_err(|_| SERIES_NOT_FOUND)
+}
+
+fn find_series_row(connection: &MysqlConnection, the_series_id: &str) -> Result<SeriesRow, &'static str> {
+    let series: SessionSeries = session_series::table.find(the_series_id).first(connection).map_err(|_| SERIES_NOT_FOUND)?;
+
+    let series_sessions: Vec<Session> = sessions::table
+        .filter(sessions::series_id.eq(the_series_id))
+        .order_by(sessions::original_start_date.asc())
+        .load(connection)
+        .map_err(|_| SERIES_NOT_FOUND)?;
+
+    Ok(SeriesRow {
+        series,
+        sessions: series_sessions,
+    })
+}
+
+fn occurrences_of(new_series: &NewSessionSeries) -> Result<Vec<NaiveDateTime>, Ferror> {
+    let recurrence = Recurrence::parse(new_series.rule.as_str()).map_err(|e| Ferror::from(e.as_str()))?;
+    let zone = util::as_zone(new_series.time_zone.as_str()).map_err(|e| Ferror::from(e.as_str()))?;
+
+    let exceptions: Vec<NaiveDateTime> = new_series.exceptions.as_deref().unwrap_or_default().split(',').filter_map(|date| util::as_date(date).ok()).collect();
+
+    let dates = recurrence.expand(new_series.start_date, zone, &exceptions);
+
+    if dates.is_empty() {
+        return Err(Ferror::from(NO_OCCURRENCES));
+    }
+
+    if dates.len() > MAX_OCCURRENCES {
+        return Err(Ferror::from(TOO_MANY_OCCURRENCES));
+    }
+
+    Ok(dates)
+}
+
+/**
+ * Each occurrence is checked as a session on its own; the errors name the
+ * start date of the occurrence.
+ */
+fn check_occurrences(connection: &MysqlConnection, coach: &User, member: &User, dates: &[NaiveDateTime], duration: i32, excluded_ids: &[String]) -> Result<(), Ferror> {
+    let mut errors = Ferror::new();
+
+    for start_date in dates {
+        let end_date = *start_date + Duration::minutes(duration as i64);
+
+        if let Err(e) = check_schedule(connection, coach.id.as_str(), &[member.id.to_owned()], *start_date, end_date, excluded_ids) {
+            for error in e.errors {
+                errors.push_error(ValidationError {
+                    message: format!("{} ({})", error.message, util::with_offset(*start_date).to_rfc3339()),
+                    ..error
+                });
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(())
+}
+
+/**
+ * Inserts the sessions of the series on the given dates, returning their ids.
+ */
+fn insert_occurrences(connection: &MysqlConnection, series: &SessionSeries, coach: &User, member: &User, dates: &[NaiveDateTime]) -> QueryResult<Vec<String>> {
+    let people_involved = util::concat(coach.full_name.as_str(), member.full_name.as_str());
+
+    let new_sessions: Vec<NewSession> = dates.iter().map(|date| NewSession::for_series(series, *date, people_involved.to_owned())).collect();
+
+    let new_session_users: Vec<NewSessionUser> = new_sessions
+        .iter()
+        .flat_map(|new_session| vec![NewSessionUser::for_new_session(new_session, coach, util::COACH), NewSessionUser::for_new_session(new_session, member, util::MEMBER)])
+        .collect();
+
+    diesel::insert_into(sessions::table).values(&new_sessions).execute(connection)?;
+    diesel::insert_into(session_users::table).values(&new_session_users).execute(connection)?;
+
+    Ok(new_sessions.into_iter().map(|new_session| new_session.id).collect())
+}
+
+/**
+ * Each session reaches the calendars as an invitation of its own, since an
+ * invitation carries a single event; the replaced sessions go out as
+ * cancellations, with the sequence bumped on cancelling them.
+ */
+fn send_occurrence_mails(connection: &MysqlConnection, coach: &User, member: &User, invited: &[Session], cancelled: &[Session]) -> Result<(), &'static str> {
+    for session in cancelled {
+        send_session_cancel_mail(connection, session)?;
+    }
+
+    for session in invited {
+        create_session_mail(connection, session, member, coach)?;
+    }
+
+    Ok(())
+}
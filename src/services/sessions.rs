@@ -1,10 +1,13 @@
 use diesel::prelude::*;
 
+use chrono::NaiveDateTime;
 use std::collections::HashMap;
 
 use crate::commons::util;
 
+use crate::services::availabilities::check_schedule;
 use crate::services::correspondences::create_mail;
+use crate::services::discussions::create_new_discussion;
 use crate::services::enrollments;
 use crate::services::programs;
 use crate::services::users;
@@ -12,12 +15,19 @@ use crate::services::users;
 use crate::services::conferences::{sync_conference_state};
 
 use crate::models::correspondences::{MailOut, MailRecipient};
+use crate::models::discussions::NewDiscussionRequest;
 use crate::models::enrollments::Enrollment;
+use crate::models::ferror::Ferror;
+use crate::models::programs::Program;
+use crate::models::session_slots::{NewSessionSlot, SessionSlot};
 use crate::models::session_users::{NewSessionUser, SessionUser};
-use crate::models::sessions::{ChangeSessionStateRequest, NewSession, NewSessionRequest, Session, TargetState};
+use crate::models::sessions::{
+    AcceptSessionRequest, ChangeSessionStateRequest, DeclineSessionRequest, MemberSessionRequest, NewSession, NewSessionRequest, RescheduleRequest, Session, TargetState,
+};
 use crate::models::users::User;
 
 use crate::schema::enrollments::dsl::*;
+use crate::schema::session_slots;
 use crate::schema::session_users::dsl::*;
 use crate::schema::sessions::dsl::*;
 use crate::schema::users::dsl::*;
@@ -32,8 +42,13 @@ const SESSION_UPDATE_ERROR: &str = "Unable to complete the requested action on t
 
 const NOT_IN_CONFERENCE: &str = "The member is not included in the conference";
 const UNREMOVABLE_SESSION: &str = "The session is not in a removable state";
+const NOT_A_PENDING_REQUEST: &str = "The session is not a pending request.";
+const INVALID_SLOT: &str = "The slot is not one of those offered for the session.";
+const PAST_SLOT: &str = "The slot is already past. Hence decline the request instead.";
+const NOTICE_ERROR: &str = "Unable to post the notice to the discussion.";
+const CONFERENCE_SESSION: &str = "The session is a part of a conference. Hence reschedule the conference instead.";
 
-pub fn create_session(connection: &MysqlConnection, request: &NewSessionRequest) -> Result<Session, &'static str> {
+pub fn create_session(connection: &MysqlConnection, request: &NewSessionRequest) -> Result<Session, Ferror> {
     // Obtain the Program
     let program = programs::find(connection, request.program_id.as_str())?;
 
@@ -47,7 +62,10 @@ pub fn create_session(connection: &MysqlConnection, request: &NewSessionRequest)
     let people_involved: String = util::concat(coach.full_name.as_str(), member.full_name.as_str());
 
     // Inserting the Session
-    let new_session = NewSession::from(request, enrollment.id.to_owned(), people_involved);
+    let new_session = NewSession::from(request, enrollment.id.to_owned(), people_involved)?;
+
+    check_schedule(connection, coach.id.as_str(), &[member.id.to_owned()], new_session.original_start_date, new_session.original_end_date, &[])?;
+
     let session = insert_session(connection, &new_session)?;
 
     // Inserting a pair of entries into the Session Users (For Coach & Member)
@@ -62,6 +80,163 @@ pub fn create_session(connection: &MysqlConnection, request: &NewSessionRequest)
     Ok(session)
 }
 
+/**
+ * A member requests a session with a few slots, for the coach of the program
+ * to accept one or to decline. The request reaches the coach through a mail
+ * and the pending feed.
+ */
+pub fn request_session(connection: &MysqlConnection, the_member_id: &str, request: &MemberSessionRequest) -> Result<Session, &'static str> {
+    let program = programs::find(connection, request.program_id.as_str())?;
+    let coach: User = users::find(connection, program.coach_id.as_str())?;
+    let member: User = users::find(connection, the_member_id)?;
+    let enrollment: Enrollment = enrollments::find(connection, &program, &member)?;
+
+    let people_involved: String = util::concat(coach.full_name.as_str(), member.full_name.as_str());
+
+    let new_session = NewSession::from_request(request, enrollment.id.to_owned(), people_involved)?;
+    let new_slots: Vec<NewSessionSlot> = request
+        .slots_dates()?
+        .into_iter()
+        .map(|(start_date, end_date)| NewSessionSlot::from(new_session.id.as_str(), start_date, end_date))
+        .collect();
+
+    let result = connection.transaction::<_, diesel::result::Error, _>(|| {
+        diesel::insert_into(sessions).values(&new_session).execute(connection)?;
+        diesel::insert_into(session_slots::table).values(&new_slots).execute(connection)
+    });
+
+    if result.is_err() {
+        return Err(SESSION_CREATION_ERROR);
+    }
+
+    let session = find(connection, new_session.id.as_str())?;
+
+    let new_session_coach = NewSessionUser::from(&session, &coach, util::COACH);
+    let new_session_member = NewSessionUser::from(&session, &member, util::MEMBER);
+    insert_session_users(connection, &new_session_coach, &new_session_member)?;
+
+    let slots = get_session_slots(connection, session.id.as_str()).map_err(|_| SESSION_NOT_FOUND)?;
+
+    let mail_out = MailOut::for_session_request(&session, &member, &coach, &slots);
+    let recipients = MailRecipient::build_recipient(&coach, mail_out.id.as_str());
+    create_mail(connection, mail_out, recipients)?;
+
+    let notice = format!("Requested the session '{}'. Please accept one of the offered slots or decline.", session.name);
+    post_notice(connection, &program, &session, &coach, &member, member.id.as_str(), notice)?;
+
+    Ok(session)
+}
+
+/**
+ * The accepted slot becomes the schedule of the session, which is then
+ * mailed as an invitation like any other session.
+ */
+pub fn accept_session_request(connection: &MysqlConnection, request: &AcceptSessionRequest) -> Result<Session, Ferror> {
+    use crate::schema::sessions::dsl::id;
+
+    let session = find(connection, request.id.as_str())?;
+
+    if !session.is_pending_request() {
+        return Err(Ferror::from(NOT_A_PENDING_REQUEST));
+    }
+
+    let result: QueryResult<SessionSlot> = session_slots::table
+        .filter(session_slots::id.eq(request.slot_id.as_str()))
+        .filter(session_slots::session_id.eq(session.id.as_str()))
+        .first(connection);
+
+    let slot = match result {
+        Ok(slot) => slot,
+        Err(_) => return Err(Ferror::from(INVALID_SLOT)),
+    };
+
+    if util::is_past_date(slot.start_date) {
+        return Err(Ferror::from(PAST_SLOT));
+    }
+
+    let (coach, member) = find_team(connection, &session)?;
+    check_schedule(connection, coach.id.as_str(), &[member.id.to_owned()], slot.start_date, slot.end_date, &[session.id.to_owned()])?;
+
+    let result = diesel::update(sessions.filter(id.eq(session.id.as_str())))
+        .set((
+            original_start_date.eq(slot.start_date),
+            original_end_date.eq(slot.end_date),
+            offered_start_date.eq(slot.start_date),
+            offered_end_date.eq(slot.end_date),
+            is_request.eq(false),
+            sequence.eq(sequence + 1),
+        ))
+        .execute(connection);
+
+    if result.is_err() {
+        return Err(Ferror::from(SESSION_UPDATE_ERROR));
+    }
+
+    let session = find(connection, session.id.as_str())?;
+    let program = programs::find(connection, session.program_id.as_str())?;
+
+    create_session_mail(connection, &session, &member, &coach)?;
+
+    let notice = format!("Accepted the request for the session '{}'.", session.name);
+    post_notice(connection, &program, &session, &coach, &member, coach.id.as_str(), notice)?;
+
+    Ok(session)
+}
+
+pub fn decline_session_request(connection: &MysqlConnection, request: &DeclineSessionRequest) -> Result<Session, &'static str> {
+    use crate::schema::sessions::dsl::id;
+
+    let session = find(connection, request.id.as_str())?;
+
+    if !session.is_pending_request() {
+        return Err(NOT_A_PENDING_REQUEST);
+    }
+
+    let result = diesel::update(sessions.filter(id.eq(session.id.as_str())))
+        .set((cancelled_at.eq(util::now()), closing_notes.eq(request.reason.as_str())))
+        .execute(connection);
+
+    if result.is_err() {
+        return Err(SESSION_UPDATE_ERROR);
+    }
+
+    let session = find(connection, session.id.as_str())?;
+    let program = programs::find(connection, session.program_id.as_str())?;
+    let (coach, member) = find_team(connection, &session)?;
+
+    let mail_out = MailOut::for_declined_session(&session, &coach);
+    let recipients = MailRecipient::build_recipients(&member, &coach, mail_out.id.as_str());
+    create_mail(connection, mail_out, recipients)?;
+
+    let notice = format!("Declined the request for the session '{}'. {}", session.name, request.reason);
+    post_notice(connection, &program, &session, &coach, &member, coach.id.as_str(), notice)?;
+
+    Ok(session)
+}
+
+pub fn get_session_slots(connection: &MysqlConnection, the_session_id: &str) -> Result<Vec<SessionSlot>, diesel::result::Error> {
+    session_slots::table
+        .filter(session_slots::session_id.eq(the_session_id))
+        .order_by(session_slots::start_date.asc())
+        .load(connection)
+}
+
+/**
+ * Posts the notice from the sender into the discussion of the enrollment,
+ * which lands in the pending feed of the other party.
+ */
+fn post_notice(connection: &MysqlConnection, program: &Program, session: &Session, coach: &User, member: &User, the_sender_id: &str, notice: String) -> Result<(), &'static str> {
+    let to_user_id = if the_sender_id == coach.id { member.id.as_str() } else { coach.id.as_str() };
+
+    let request = NewDiscussionRequest::notice(program, session.enrollment_id.as_str(), coach, member, to_user_id, notice);
+
+    if create_new_discussion(connection, the_sender_id, &request).is_err() {
+        return Err(NOTICE_ERROR);
+    }
+
+    Ok(())
+}
+
 pub fn find_by_conference(connection: &MysqlConnection, conf_id: &str, given_member_id: &str) -> Result<Session, &'static str> {
     
     let result: Result<(Session, Enrollment), diesel::result::Error> = sessions
@@ -120,8 +295,11 @@ pub fn change_session_state(connection: &MysqlConnection, request: &ChangeSessio
    
     let session = find(connection, &request.id.as_str())?;
     
-    if request.target_state == TargetState::CANCEL && !session.is_conference() {
-        send_session_cancel_mail(connection, &session)?;
+    if request.target_state == TargetState::CANCEL {
+        match &session.conference_id {
+            Some(conf_id) => send_conference_cancel_mails(connection, &session, conf_id.as_str())?,
+            None => send_session_cancel_mail(connection, &session)?,
+        };
     }
 
     Ok(session)
@@ -153,7 +331,9 @@ fn do_alter_multi_sessions_state(connection: &MysqlConnection, request: &ChangeS
         TargetState::DONE => diesel::update(target_sessions)
             .set((actual_end_date.eq(now), closing_notes.eq(&request.closing_notes)))
             .execute(connection),
-        TargetState::CANCEL => diesel::update(target_sessions).set((cancelled_at.eq(now), closing_notes.eq(&request.closing_notes))).execute(connection),
+        TargetState::CANCEL => diesel::update(target_sessions)
+            .set((cancelled_at.eq(now), closing_notes.eq(&request.closing_notes), sequence.eq(sequence + 1)))
+            .execute(connection),
     };
 
     if result.is_err() {
@@ -177,7 +357,9 @@ fn do_alter_mono_session_state(connection: &MysqlConnection, request: &ChangeSes
         TargetState::DONE => diesel::update(target_session)
             .set((actual_end_date.eq(now), closing_notes.eq(&request.closing_notes)))
             .execute(connection),
-        TargetState::CANCEL => diesel::update(target_session).set((cancelled_at.eq(now), closing_notes.eq(&request.closing_notes))).execute(connection),
+        TargetState::CANCEL => diesel::update(target_session)
+            .set((cancelled_at.eq(now), closing_notes.eq(&request.closing_notes), sequence.eq(sequence + 1)))
+            .execute(connection),
     };
 
     if result.is_err() {
@@ -236,15 +418,141 @@ pub fn create_session_mail(connection: &MysqlConnection, session: &Session, memb
     create_mail(connection, mail_out, recipients)
 }
 
-fn send_session_cancel_mail(connection: &MysqlConnection, session: &Session) -> Result<usize, &'static str> {
-    let sus: Vec<(SessionUser, User)> = session_users.inner_join(users).filter(session_id.eq(&session.id)).load(connection).unwrap();
+/**
+ * A session is rescheduled by revising its dates, while the original dates
+ * are retained for the record. The sequence is bumped so that the calendars
+ * replace the earlier invitation.
+ */
+pub fn reschedule_session(connection: &MysqlConnection, request: &RescheduleRequest) -> Result<Session, Ferror> {
+    use crate::schema::sessions::dsl::id;
 
-    let team: HashMap<String, User> = sus.iter().map(|tuple| (tuple.0.user_type.clone(), tuple.1.clone())).collect();
+    let session = find(connection, request.id.as_str())?;
 
-    let coach = team.get("coach").unwrap();
-    let member = team.get("member").unwrap();
+    if session.is_conference() {
+        return Err(Ferror::from(CONFERENCE_SESSION));
+    }
 
-    let mail_out = MailOut::for_cancel_session(session, coach, member);
-    let recipients = MailRecipient::build_recipients(member, coach, mail_out.id.as_str());
+    if !session.can_reschedule() {
+        return Err(Ferror::from(SESSION_STATE_CHANGE_PROHIBITED));
+    }
+
+    let (start_date, end_date) = request.revised_dates(session.duration)?;
+    let new_duration = request.duration.unwrap_or(session.duration);
+
+    let (coach, member) = find_team(connection, &session)?;
+    check_schedule(connection, coach.id.as_str(), &[member.id.to_owned()], start_date, end_date, &[session.id.to_owned()])?;
+
+    let result = diesel::update(sessions.filter(id.eq(session.id.as_str())))
+        .set((
+            revised_start_date.eq(start_date),
+            revised_end_date.eq(end_date),
+            duration.eq(new_duration),
+            sequence.eq(sequence + 1),
+        ))
+        .execute(connection);
+
+    if result.is_err() {
+        return Err(Ferror::from(SESSION_UPDATE_ERROR));
+    }
+
+    let session = find(connection, session.id.as_str())?;
+
+    let mail_out = MailOut::for_reschedule_session(&session, &coach, &member, request.reason.as_deref());
+    let recipients = MailRecipient::build_recipients(&member, &coach, mail_out.id.as_str());
+    create_mail(connection, mail_out, recipients)?;
+
+    Ok(session)
+}
+
+/**
+ * Moves all the live sessions of a conference along with the conference.
+ */
+pub fn revise_conference_sessions(connection: &MysqlConnection, conf_id: &str, start_date: NaiveDateTime, end_date: NaiveDateTime, new_duration: i32) -> QueryResult<usize> {
+    diesel::update(sessions.filter(conference_id.eq(conf_id)).filter(cancelled_at.is_null()))
+        .set((
+            revised_start_date.eq(start_date),
+            revised_end_date.eq(end_date),
+            duration.eq(new_duration),
+            sequence.eq(sequence + 1),
+        ))
+        .execute(connection)
+}
+
+/**
+ * The ids of the sessions of a conference and of the people attending them.
+ */
+pub fn get_conference_attendance(connection: &MysqlConnection, conf_id: &str) -> Result<(Vec<String>, Vec<String>), &'static str> {
+    let rows: Vec<(Session, SessionUser)> = sessions
+        .inner_join(session_users)
+        .filter(conference_id.eq(conf_id))
+        .load(connection)
+        .map_err(|_| SESSION_NOT_FOUND)?;
+
+    let session_ids: Vec<String> = rows.iter().map(|row| row.0.id.to_owned()).collect();
+    let user_ids: Vec<String> = rows.iter().map(|row| row.1.user_id.to_owned()).collect();
+
+    Ok((session_ids, user_ids))
+}
+
+pub fn send_conference_reschedule_mails(connection: &MysqlConnection, the_program_id: &str, conf_id: &str, reason: Option<&str>) -> Result<usize, &'static str> {
+    send_conference_mails(connection, the_program_id, conf_id, |session, coach, member| {
+        MailOut::for_reschedule_session(session, coach, member, reason)
+    })
+}
+
+pub fn find_team(connection: &MysqlConnection, session: &Session) -> Result<(User, User), &'static str> {
+    let sus: Vec<(SessionUser, User)> = session_users
+        .inner_join(users)
+        .filter(session_id.eq(&session.id))
+        .load(connection)
+        .map_err(|_| SESSION_NOT_FOUND)?;
+
+    let team: HashMap<String, User> = sus.into_iter().map(|tuple| (tuple.0.user_type, tuple.1)).collect();
+
+    match (team.get(util::COACH), team.get(util::MEMBER)) {
+        (Some(coach), Some(member)) => Ok((coach.clone(), member.clone())),
+        _ => Err(SESSION_NOT_FOUND),
+    }
+}
+
+pub fn send_session_cancel_mail(connection: &MysqlConnection, session: &Session) -> Result<usize, &'static str> {
+    let (coach, member) = find_team(connection, session)?;
+
+    let mail_out = MailOut::for_cancel_session(session, &coach, &member);
+    let recipients = MailRecipient::build_recipients(&member, &coach, mail_out.id.as_str());
     create_mail(connection, mail_out, recipients)
 }
+
+fn send_conference_cancel_mails(connection: &MysqlConnection, session: &Session, conf_id: &str) -> Result<usize, &'static str> {
+    send_conference_mails(connection, session.program_id.as_str(), conf_id, MailOut::for_cancel_session)
+}
+
+/**
+ * The member sessions of a conference have only the member in session_users,
+ * the coach being the one who offers the program.
+ */
+fn send_conference_mails<F>(connection: &MysqlConnection, the_program_id: &str, conf_id: &str, compose: F) -> Result<usize, &'static str>
+where
+    F: Fn(&Session, &User, &User) -> MailOut,
+{
+    use crate::schema::session_users::dsl::user_type;
+
+    let program = programs::find(connection, the_program_id)?;
+    let coach = users::find(connection, program.coach_id.as_str())?;
+
+    let sus: Vec<(Session, (SessionUser, User))> = sessions
+        .inner_join(session_users.inner_join(users))
+        .filter(conference_id.eq(conf_id))
+        .filter(user_type.eq(util::MEMBER))
+        .load(connection)
+        .map_err(|_| SESSION_NOT_FOUND)?;
+
+    let mut count = 0;
+    for (member_session, (_, member)) in sus {
+        let mail_out = compose(&member_session, &coach, &member);
+        let recipients = MailRecipient::build_recipients(&member, &coach, mail_out.id.as_str());
+        count += create_mail(connection, mail_out, recipients)?;
+    }
+
+    Ok(count)
+}
@@ -0,0 +1,68 @@
+use diesel::prelude::*;
+
+use crate::models::ferror::Ferror;
+use crate::models::task_links::{NewTaskLink, TaskLink, UpdateTaskLinksRequest};
+use crate::models::tasks::Task;
+
+use crate::schema::task_links;
+use crate::schema::tasks;
+
+const TASKS_NOT_FOUND: &str = "Unable to find the tasks of the enrollment.";
+const SAVE_ERROR: &str = "Unable to save the links between the tasks.";
+const PREDECESSORS_ERROR: &str = "Unable to find the tasks before the task.";
+
+pub fn get_task_links(connection: &MysqlConnection, the_enrollment_id: &str) -> Result<Vec<TaskLink>, diesel::result::Error> {
+    task_links::table
+        .filter(task_links::enrollment_id.eq(the_enrollment_id))
+        .order_by(task_links::priority.asc())
+        .load(connection)
+}
+
+/**
+ * Replaces the links of the enrollment with the given ones, unless they
+ * break the graph (see validate_graph).
+ */
+pub fn save_task_links(connection: &MysqlConnection, request: &UpdateTaskLinksRequest) -> Result<Vec<TaskLink>, Ferror> {
+    let the_enrollment_id = request.enrollment_id.as_str();
+
+    let enrollment_tasks: Vec<Task> = tasks::table
+        .filter(tasks::enrollment_id.eq(the_enrollment_id))
+        .load(connection)
+        .map_err(|_| Ferror::from(TASKS_NOT_FOUND))?;
+
+    let errors = request.validate_graph(&enrollment_tasks);
+    if !errors.is_empty() {
+        return Err(Ferror { errors });
+    }
+
+    let new_links: Vec<NewTaskLink> = request.links.iter().map(|link| NewTaskLink::from(link, the_enrollment_id)).collect();
+
+    let result = connection.transaction::<_, diesel::result::Error, _>(|| {
+        diesel::delete(task_links::table.filter(task_links::enrollment_id.eq(the_enrollment_id))).execute(connection)?;
+        diesel::insert_into(task_links::table).values(&new_links).execute(connection)
+    });
+
+    if result.is_err() {
+        return Err(Ferror::from(SAVE_ERROR));
+    }
+
+    get_task_links(connection, the_enrollment_id).map_err(|_| Ferror::from(SAVE_ERROR))
+}
+
+/**
+ * The tasks before the given one (over the forward links) that are neither
+ * done nor cancelled.
+ */
+pub fn pending_predecessors(connection: &MysqlConnection, the_task_id: &str) -> Result<Vec<Task>, &'static str> {
+    let source_ids = task_links::table
+        .filter(task_links::target_task_id.eq(the_task_id))
+        .filter(task_links::is_forward.eq(true))
+        .select(task_links::source_task_id);
+
+    tasks::table
+        .filter(tasks::id.eq_any(source_ids))
+        .filter(tasks::actual_end_date.is_null())
+        .filter(tasks::cancelled_at.is_null())
+        .load(connection)
+        .map_err(|_| PREDECESSORS_ERROR)
+}
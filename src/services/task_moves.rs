@@ -0,0 +1,139 @@
+use diesel::prelude::*;
+
+use crate::commons::util;
+
+use crate::models::correspondences::as_readable;
+use crate::models::discussions::NewDiscussionRequest;
+use crate::models::enrollments::Enrollment;
+use crate::models::programs::Program;
+use crate::models::task_moves::{NewTaskMove, PropagateDelaysRequest, TaskMove};
+use crate::models::tasks::Task;
+use crate::models::users::User;
+
+use crate::schema::enrollments;
+use crate::schema::programs;
+use crate::schema::task_moves;
+use crate::schema::tasks;
+
+use crate::services::discussions::create_new_discussion;
+use crate::services::schedules::{downstream_moves, is_delayed, load_graph};
+use crate::services::users;
+
+const TASK_NOT_FOUND: &str = "Unable to find the Task.";
+const ENROLLMENT_NOT_FOUND: &str = "Unable to find the enrollment.";
+const PROPAGATION_ERROR: &str = "Unable to reschedule the successors of the task.";
+const NOTICE_ERROR: &str = "Unable to post the notice to the discussion.";
+
+/**
+ * Moves the successors of the task as it slipped (say it was edited to a
+ * later date, or it started or finished late). The actors of the moved tasks
+ * get a notice from the notifier, if one is given.
+ */
+pub fn propagate_slip(connection: &MysqlConnection, the_task_id: &str, the_notifier_id: Option<&str>) -> Result<Vec<TaskMove>, &'static str> {
+    let task: Task = tasks::table.find(the_task_id).first(connection).map_err(|_| TASK_NOT_FOUND)?;
+
+    propagate(connection, task.enrollment_id.as_str(), Some(the_task_id), the_notifier_id)
+}
+
+/**
+ * Moves the successors of every delayed task of the enrollment.
+ */
+pub fn propagate_delays(connection: &MysqlConnection, the_caller_id: &str, request: &PropagateDelaysRequest) -> Result<Vec<TaskMove>, &'static str> {
+    let the_notifier_id = Some(the_caller_id).filter(|_| request.notify_actors.unwrap_or(false));
+
+    propagate(connection, request.enrollment_id.as_str(), None, the_notifier_id)
+}
+
+pub fn get_task_moves(connection: &MysqlConnection, the_enrollment_id: &str) -> Result<Vec<TaskMove>, diesel::result::Error> {
+    task_moves::table
+        .filter(task_moves::enrollment_id.eq(the_enrollment_id))
+        .order_by(task_moves::created_at.desc())
+        .load(connection)
+}
+
+/**
+ * Only the revised dates of the successors change; the original dates stay
+ * as planned. Each move is recorded along with its cause.
+ */
+fn propagate(connection: &MysqlConnection, the_enrollment_id: &str, the_cause_id: Option<&str>, the_notifier_id: Option<&str>) -> Result<Vec<TaskMove>, &'static str> {
+    let (live_tasks, links) = load_graph(connection, the_enrollment_id).map_err(|_| PROPAGATION_ERROR)?;
+    let now = util::now();
+
+    let cause_ids: Vec<&str> = match the_cause_id {
+        Some(cause_id) => vec![cause_id],
+        None => live_tasks.iter().filter(|task| is_delayed(task, now)).map(|task| task.id.as_str()).collect(),
+    };
+
+    let moves = downstream_moves(&live_tasks, &links, &cause_ids, now).map_err(|_| PROPAGATION_ERROR)?;
+
+    if moves.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let new_moves: Vec<NewTaskMove> = moves.iter().map(|(index, cause_id, schedule)| NewTaskMove::from(&live_tasks[*index], cause_id, schedule)).collect();
+
+    let result = connection.transaction::<_, diesel::result::Error, _>(|| {
+        for new_move in &new_moves {
+            diesel::update(tasks::table.find(new_move.task_id.as_str()))
+                .set((tasks::revised_start_date.eq(new_move.to_start_date), tasks::revised_end_date.eq(new_move.to_end_date)))
+                .execute(connection)?;
+        }
+
+        diesel::insert_into(task_moves::table).values(&new_moves).execute(connection)
+    });
+
+    if result.is_err() {
+        return Err(PROPAGATION_ERROR);
+    }
+
+    let move_ids: Vec<&str> = new_moves.iter().map(|new_move| new_move.id.as_str()).collect();
+    let recorded: Vec<TaskMove> = task_moves::table.filter(task_moves::id.eq_any(move_ids)).load(connection).map_err(|_| PROPAGATION_ERROR)?;
+
+    if let Some(the_sender_id) = the_notifier_id {
+        let moved: Vec<(&Task, &TaskMove)> = recorded
+            .iter()
+            .filter_map(|task_move| live_tasks.iter().find(|task| task.id == task_move.task_id).map(|task| (task, task_move)))
+            .collect();
+
+        notify(connection, the_enrollment_id, the_sender_id, &moved)?;
+    }
+
+    Ok(recorded)
+}
+
+/**
+ * Each actor of the moved tasks gets a notice listing the tasks with their
+ * revised dates, posted by the sender (the caller who moved them).
+ */
+fn notify(connection: &MysqlConnection, the_enrollment_id: &str, the_sender_id: &str, moved: &[(&Task, &TaskMove)]) -> Result<(), &'static str> {
+    let (enrollment, program): (Enrollment, Program) = enrollments::table
+        .inner_join(programs::table)
+        .filter(enrollments::id.eq(the_enrollment_id))
+        .first(connection)
+        .map_err(|_| ENROLLMENT_NOT_FOUND)?;
+
+    let coach: User = users::find(connection, program.coach_id.as_str())?;
+    let member: User = users::find(connection, enrollment.member_id.as_str())?;
+
+    for actor in &[&coach, &member] {
+        let lines: Vec<String> = moved
+            .iter()
+            .filter(|(task, _)| task.actor_id == actor.id)
+            .map(|(task, task_move)| format!("'{}' now runs {}", task.name, as_readable(task_move.to_start_date, task_move.to_end_date, &[actor.zone()])))
+            .collect();
+
+        if lines.is_empty() {
+            continue;
+        }
+
+        let notice = format!("Rescheduled, as the earlier tasks slipped: {}.", lines.join("; "));
+
+        let request = NewDiscussionRequest::notice(&program, enrollment.id.as_str(), &coach, &member, actor.id.as_str(), notice);
+
+        if create_new_discussion(connection, the_sender_id, &request).is_err() {
+            return Err(NOTICE_ERROR);
+        }
+    }
+
+    Ok(())
+}
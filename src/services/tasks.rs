@@ -6,38 +6,65 @@ use chrono::{Duration, NaiveDateTime};
 use crate::models::enrollments::PlanCriteria;
 use crate::models::tasks::{NewTask, NewTaskRequest, Task, UpdateTask, UpdateClosingNoteRequest, UpdateTaskRequest,UpdateResponseRequest, ChangeMemberTaskStateRequest, ChangeCoachTaskStateRequest, MemberTargetState, CoachTargetState};
 use crate::schema::tasks::dsl::*;
+use crate::services::task_links::pending_predecessors;
+use crate::services::task_moves::propagate_slip;
 
 const STATE_CHANGE_PROHIBITED: &str = "The task is either cancelled or responded.";
+const WAITING_ON_PREDECESSORS: &str = "The task can start only after the tasks before it are done.";
 const TASK_NOT_FOUND: &str = "Unable to find the Task.";
 const UPDATE_ERROR: &str = "Unable to complete the requested action.";
 const UPDATE_NOTES_ERROR: &str = "Unable to update the notes.";
+const CREATE_ERROR: &str = "Unable to create the task.";
 
-pub fn create_task(connection: &MysqlConnection, request: &NewTaskRequest) -> Result<Task, diesel::result::Error> {
-    let new_task = NewTask::from(request);
+pub fn create_task(connection: &MysqlConnection, request: &NewTaskRequest) -> Result<Task, &'static str> {
+    let new_task = NewTask::from(request)?;
 
-    diesel::insert_into(tasks).values(&new_task).execute(connection)?;
+    let result = diesel::insert_into(tasks).values(&new_task).execute(connection);
 
-    tasks.filter(id.eq(new_task.id)).first(connection)
+    if result.is_err() {
+        return Err(CREATE_ERROR);
+    }
+
+    find(connection, new_task.id.as_str())
 }
 
-pub fn update_task(connection: &MysqlConnection, request: &UpdateTaskRequest) -> Result<Task, diesel::result::Error> {
+/**
+ * Edits the task, and moves its successors when it slips past them.
+ * The edited dates become the plan of the task, so the revised dates of an
+ * earlier move are cleared; otherwise they would keep overriding the edit.
+ */
+pub fn update_task(connection: &MysqlConnection, the_caller_id: &str, request: &UpdateTaskRequest) -> Result<Task, &'static str> {
     let the_id = &request.id.as_str();
 
-    let start_date = util::as_date(request.start_time.as_str());
+    let start_date = util::as_date(request.start_time.as_str())?;
     let given_duration = Duration::hours(request.duration as i64);
     let end_date = start_date.checked_add_signed(given_duration);
 
-    diesel::update(tasks.filter(id.eq(the_id)))
-        .set(&UpdateTask {
-            description: request.description.to_owned(),
-            name: request.name.to_owned(),
-            duration: request.duration,
-            original_start_date: start_date,
-            original_end_date: end_date.unwrap_or(start_date),
-        })
-        .execute(connection)?;
+    let the_notifier_id = Some(the_caller_id).filter(|_| request.notify_actors.unwrap_or(false));
+
+    atomically(connection, || {
+        let result = diesel::update(tasks.filter(id.eq(the_id)))
+            .set((
+                &UpdateTask {
+                    description: request.description.to_owned(),
+                    name: request.name.to_owned(),
+                    duration: request.duration,
+                    original_start_date: start_date,
+                    original_end_date: end_date.unwrap_or(start_date),
+                },
+                revised_start_date.eq(None::<NaiveDateTime>),
+                revised_end_date.eq(None::<NaiveDateTime>),
+            ))
+            .execute(connection);
+
+        if result.is_err() {
+            return Err(UPDATE_ERROR);
+        }
+
+        propagate_slip(connection, the_id, the_notifier_id)
+    })?;
 
-    tasks.filter(id.eq(the_id)).first(connection)
+    find(connection, the_id)
 }
 
 pub fn update_closing_notes(connection: &MysqlConnection, request: &UpdateClosingNoteRequest) -> Result<Task, &'static str> {
@@ -96,16 +123,25 @@ pub fn change_coach_task_state(connection: &MysqlConnection, request: &ChangeCoa
 
     let none_date: Option<NaiveDateTime> = None;
 
-    let result = match request.target_state {
+    atomically(connection, || {
+        let result = match request.target_state {
 
-        CoachTargetState::CANCEL => diesel::update(target_task).set(cancelled_at.eq(now)).execute(connection),
-        CoachTargetState::DONE => diesel::update(target_task).set(actual_end_date.eq(now)).execute(connection),
-        CoachTargetState::REOPEN => diesel::update(target_task).set(responded_date.eq(none_date)).execute(connection)
-    };
+            CoachTargetState::CANCEL => diesel::update(target_task).set(cancelled_at.eq(now)).execute(connection),
+            CoachTargetState::DONE => diesel::update(target_task).set(actual_end_date.eq(now)).execute(connection),
+            CoachTargetState::REOPEN => diesel::update(target_task).set(responded_date.eq(none_date)).execute(connection)
+        };
 
-    if result.is_err() {
-        return Err(UPDATE_ERROR);
-    }
+        if result.is_err() {
+            return Err(UPDATE_ERROR);
+        }
+
+        // A task done past its end holds up its successors till now
+        if request.target_state == CoachTargetState::DONE {
+            propagate_slip(connection, the_id, None)?;
+        }
+
+        Ok(())
+    })?;
 
     find(connection, the_id)
 
@@ -119,15 +155,24 @@ pub fn change_member_task_state(connection: &MysqlConnection, request: &ChangeMe
     let target_task = tasks.filter(id.eq(the_id));
     let now = util::now();
 
-    let result = match request.target_state {
+    atomically(connection, || {
+        let result = match request.target_state {
 
-        MemberTargetState:: START => diesel::update(target_task).set(actual_start_date.eq(now)).execute(connection),
-        MemberTargetState:: FINISH => diesel::update(target_task).set(responded_date.eq(now)).execute(connection)
-    };
+            MemberTargetState:: START => diesel::update(target_task).set(actual_start_date.eq(now)).execute(connection),
+            MemberTargetState:: FINISH => diesel::update(target_task).set(responded_date.eq(now)).execute(connection)
+        };
 
-    if result.is_err() {
-        return Err(UPDATE_ERROR);
-    }
+        if result.is_err() {
+            return Err(UPDATE_ERROR);
+        }
+
+        // A task started late pushes out its successors
+        if request.target_state == MemberTargetState::START {
+            propagate_slip(connection, the_id, None)?;
+        }
+
+        Ok(())
+    })?;
 
     find(connection, the_id)
 }
@@ -164,6 +209,10 @@ fn can_allow_member_task_state_change(connection: &MysqlConnection, request: &Ch
         return Err(STATE_CHANGE_PROHIBITED);
     }
 
+    if request.target_state == MemberTargetState::START && !pending_predecessors(connection, the_id)?.is_empty() {
+        return Err(WAITING_ON_PREDECESSORS);
+    }
+
     Ok(1)
 }
 
@@ -183,4 +232,21 @@ pub fn get_tasks(connection: &MysqlConnection, criteria: PlanCriteria) -> Result
         .filter(enrollment_id.eq(criteria.enrollment_id))
         .order_by(original_start_date.asc())
         .load(connection)
-}
\ No newline at end of file
+}
+
+/**
+ * Runs the change of the task along with the moves of its successors, all or
+ * nothing; a failed change comes back with its own error.
+ */
+fn atomically<T>(connection: &MysqlConnection, change: impl FnOnce() -> Result<T, &'static str>) -> Result<T, &'static str> {
+    let mut failure = UPDATE_ERROR;
+
+    let result = connection.transaction::<_, diesel::result::Error, _>(|| {
+        change().map_err(|e| {
+            failure = e;
+            diesel::result::Error::RollbackTransaction
+        })
+    });
+
+    result.map_err(|_| failure)
+}
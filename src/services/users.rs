@@ -1,10 +1,14 @@
+use chrono::Duration;
 use diesel::prelude::*;
 
+use crate::commons::token;
 use crate::commons::util;
 
 use crate::models::ferror::Ferror;
 use crate::models::coaches::Coach;
-use crate::models::users::{LoginRequest, NewUser, Registration, ResetPasswordRequest, User};
+use crate::models::correspondences::{MailOut, MailRecipient};
+use crate::models::password_resets::{CompletePasswordResetRequest, NewPasswordReset, PasswordReset};
+use crate::models::users::{LoginRequest, NewUser, Registration, ResetPasswordRequest, TimeZoneRequest, User, ACTIVE, UNVERIFIED};
 
 use crate::schema::users;
 use crate::schema::users::dsl::*;
@@ -12,6 +16,11 @@ use crate::schema::users::dsl::*;
 use crate::schema::coaches;
 use crate::schema::coaches::dsl::*;
 
+use crate::schema::password_resets;
+use crate::schema::password_resets::dsl::*;
+
+use crate::services::correspondences::create_mail;
+
 pub const REGISTERED_ALREADY: &str = "It seems you have already registered with us.";
 pub const INVALID_USER_ID: &str = "Invalid User Id";
 pub const CREATION_ERROR: &str = "Unable to create a new user";
@@ -19,6 +28,14 @@ pub const INVALID_CREDENTIAL: &str = "Invalid Credential";
 pub const PASSWORD_RESET_FAILED: &str = "Failed to reset the password.";
 pub const INVALID_COACH_EMAIL: &str = "Invalid Coach email address";
 pub const INVALID_COACH_ID: &str = "Invalid Coach Id";
+pub const INVALID_RESET_TOKEN: &str = "The password reset link is invalid or has expired.";
+pub const RESET_REQUEST_FAILED: &str = "Unable to process the password reset request.";
+pub const EMAIL_NOT_VERIFIED: &str = "Please verify your email address to continue.";
+pub const ACCOUNT_BLOCKED: &str = "Your account has been blocked.";
+pub const VERIFICATION_FAILED: &str = "Failed to verify the email address.";
+pub const TIME_ZONE_ERROR: &str = "Unable to save the time zone.";
+
+const VERIFICATION_VALIDITY_IN_DAYS: i64 = 7;
 
 pub fn register(connection: &MysqlConnection, registration: &Registration) -> Result<User, Ferror> {
     
@@ -26,16 +43,88 @@ pub fn register(connection: &MysqlConnection, registration: &Registration) -> Re
 
     is_registered(connection, registration.email.as_str())?;
 
-    let user = create_user(connection, registration)?;
+    // A user without the verification mail could never log in, so neither is kept without the other
+    let mut failure = CREATION_ERROR;
+
+    let result = connection.transaction::<_, diesel::result::Error, _>(|| {
+        let user = create_user(connection, registration).map_err(|e| {
+            failure = e;
+            diesel::result::Error::RollbackTransaction
+        })?;
+
+        create_verification_mail(connection, &user).map_err(|e| {
+            failure = e;
+            diesel::result::Error::RollbackTransaction
+        })?;
+
+        Ok(user)
+    });
+
+    result.map_err(|_| Ferror::from(failure))
+}
+
+/**
+ * Only the verified users who are not blocked can login.
+ */
+pub fn authenticate(connection: &MysqlConnection, request: LoginRequest) -> Result<User, &'static str> {
+    let user = verify_credential(connection, request)?;
+
+    gate_active_user(&user)?;
 
     Ok(user)
 }
 
+pub fn gate_active_user(user: &User) -> Result<(), &'static str> {
+    if user.is_blocked() {
+        return Err(ACCOUNT_BLOCKED);
+    }
+
+    if !user.is_verified() {
+        return Err(EMAIL_NOT_VERIFIED);
+    }
+
+    Ok(())
+}
+
+pub fn issue_verification_token(the_user_id: &str) -> String {
+    token::sign(the_user_id, token::VERIFY_EMAIL, Duration::days(VERIFICATION_VALIDITY_IN_DAYS))
+}
+
+/**
+ * The verification link carries a signed token whose subject is the user id.
+ * Verifying an already verified (or blocked) user is a no-op.
+ */
+pub fn verify_email(connection: &MysqlConnection, verification_token: &str) -> Result<User, &'static str> {
+    let claims = token::verify(verification_token, token::VERIFY_EMAIL)?;
+
+    let user = find(connection, claims.sub.as_str())?;
+    if user.account_status != UNVERIFIED {
+        return Ok(user);
+    }
+
+    let result = diesel::update(users.filter(users::id.eq(user.id.as_str()))).set(account_status.eq(ACTIVE)).execute(connection);
+    if result.is_err() {
+        return Err(VERIFICATION_FAILED);
+    }
+
+    find(connection, user.id.as_str())
+}
+
+fn create_verification_mail(connection: &MysqlConnection, user: &User) -> Result<usize, &'static str> {
+    let app_url = dotenv::var("APP_URL").unwrap_or_default();
+    let verification_link = format!("{}/verify-email?token={}", app_url, issue_verification_token(user.id.as_str()));
+
+    let mail_out = MailOut::for_email_verification(user, verification_link.as_str());
+    let recipients = MailRecipient::build_recipient(user, mail_out.id.as_str());
+
+    create_mail(connection, mail_out, recipients)
+}
+
 /**
  * Mysql equal operation on var is case-insensitive, hence we need to
  * take any care to lowercase the users.email
  */
-pub fn authenticate(connection: &MysqlConnection, request: LoginRequest) -> Result<User, &'static str> {
+fn verify_credential(connection: &MysqlConnection, request: LoginRequest) -> Result<User, &'static str> {
     let result: QueryResult<String> = users.filter(users::email.eq(request.email.as_str().trim())).select(password).first(connection);
     if result.is_err() {
         return Err(INVALID_CREDENTIAL);
@@ -59,7 +148,7 @@ pub fn reset_password(connection: &MysqlConnection, request: &ResetPasswordReque
         email: request.email.to_owned(),
         password: request.password.to_owned(),
     };
-    let user = authenticate(connection, login_request)?;
+    let user = verify_credential(connection, login_request)?;
 
     let hashed_password = util::hash(request.new_password.as_str());
 
@@ -72,6 +161,83 @@ pub fn reset_password(connection: &MysqlConnection, request: &ResetPasswordReque
     Ok(user)
 }
 
+/**
+ * We do not disclose whether the email is registered with us. An unknown
+ * email is quietly ignored.
+ */
+pub fn request_password_reset(connection: &MysqlConnection, the_email: &str) -> Result<(), &'static str> {
+    let result: QueryResult<User> = users.filter(users::email.eq(the_email.trim())).first(connection);
+    let user = match result {
+        Ok(user) => user,
+        Err(_) => return Ok(()),
+    };
+
+    let reset_token = util::random_token();
+    let new_reset = NewPasswordReset::from(&user, reset_token.as_str());
+
+    let result = diesel::insert_into(password_resets).values(&new_reset).execute(connection);
+    if result.is_err() {
+        return Err(RESET_REQUEST_FAILED);
+    }
+
+    let app_url = dotenv::var("APP_URL").unwrap_or_default();
+    let reset_link = format!("{}/reset-password?token={}", app_url, reset_token);
+
+    let mail_out = MailOut::for_password_reset(&user, reset_link.as_str());
+    let recipients = MailRecipient::build_recipient(&user, mail_out.id.as_str());
+    create_mail(connection, mail_out, recipients)?;
+
+    Ok(())
+}
+
+pub fn complete_password_reset(connection: &MysqlConnection, request: &CompletePasswordResetRequest) -> Result<User, &'static str> {
+    let the_digest = util::digest(request.token.trim());
+
+    let result: QueryResult<PasswordReset> = password_resets
+        .filter(token_digest.eq(the_digest))
+        .select((password_resets::id, password_resets::user_id, expires_at, used_at))
+        .first(connection);
+    let reset = match result {
+        Ok(reset) if reset.is_usable() => reset,
+        _ => return Err(INVALID_RESET_TOKEN),
+    };
+
+    let hashed_password = util::hash(request.new_password.as_str());
+
+    // Of two completions racing with the same token, only the one that marks it used goes through
+    let result = connection.transaction::<_, diesel::result::Error, _>(|| {
+        let marked = diesel::update(password_resets.filter(password_resets::id.eq(reset.id.as_str())).filter(used_at.is_null()))
+            .set(used_at.eq(util::now()))
+            .execute(connection)?;
+
+        if marked == 0 {
+            return Err(diesel::result::Error::RollbackTransaction);
+        }
+
+        diesel::update(users.filter(users::id.eq(reset.user_id.as_str()))).set(password.eq(hashed_password)).execute(connection)
+    });
+
+    match result {
+        Ok(_) => {}
+        Err(diesel::result::Error::RollbackTransaction) => return Err(INVALID_RESET_TOKEN),
+        Err(_) => return Err(PASSWORD_RESET_FAILED),
+    }
+
+    find(connection, reset.user_id.as_str())
+}
+
+pub fn update_time_zone(connection: &MysqlConnection, the_user_id: &str, request: &TimeZoneRequest) -> Result<User, &'static str> {
+    let result = diesel::update(users.filter(users::id.eq(the_user_id)))
+        .set(time_zone.eq(request.time_zone.trim()))
+        .execute(connection);
+
+    if result.is_err() {
+        return Err(TIME_ZONE_ERROR);
+    }
+
+    find(connection, the_user_id)
+}
+
 pub fn find(connection: &MysqlConnection, the_id: &str) -> Result<User, &'static str> {
     
     let result = users.filter(users::id.eq(the_id)).first(connection);